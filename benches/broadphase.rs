@@ -0,0 +1,113 @@
+use criterion::{
+    criterion_group,
+    criterion_main,
+    BenchmarkId,
+    Criterion,
+};
+use specs::prelude::*;
+
+use eternalreckoning_client::simulation::{
+    component::{
+        collider::{
+            Collider,
+            ColliderType,
+        },
+        Position,
+    },
+    system::CollisionDetection,
+    PhysicsConfig,
+};
+
+/**
+ * Spheres with radii of 0.5-1.5 spread over an area that grows with the
+ * count, keeping the density roughly constant
+ */
+fn build_world(count: usize) -> World {
+    let mut state = 0x2545F4914F6CDD1Du64;
+    let mut next = move || {
+        state ^= state << 13;
+        state ^= state >> 7;
+        state ^= state << 17;
+        (state % 1_000_000) as f64 / 1_000_000.0
+    };
+
+    let mut world = World::new();
+    let mut detection = CollisionDetection::new(&PhysicsConfig::default());
+    System::setup(&mut detection, &mut world);
+
+    let extent = (count as f64).sqrt() * 4.0;
+    for _ in 0..count {
+        let center = nalgebra::Point3::new(
+            next() * extent,
+            next() * 4.0,
+            next() * extent,
+        );
+        world.create_entity()
+            .with(Position(center))
+            .with(Collider::new(ColliderType::Sphere(0.5 + next())))
+            .build();
+    }
+
+    world
+}
+
+/**
+ * Baseline with the shape of the narrowphase before the broadphase: every
+ * collider tested against every other, both ways round. Only the
+ * sphere-sphere overlap test is done and no collisions are recorded, so it
+ * understates what the old path cost per pair.
+ */
+fn all_pairs_spheres(world: &World) -> usize {
+    let entities = world.entities();
+    let positions = world.read_storage::<Position>();
+    let colliders = world.read_storage::<Collider>();
+
+    let mut collisions = 0;
+    for (ent, pos, collider) in (&entities, &positions, &colliders).join() {
+        for (target, target_pos, target_collider) in (&entities, &positions, &colliders).join() {
+            if ent == target {
+                continue;
+            }
+
+            if let (ColliderType::Sphere(a), ColliderType::Sphere(b)) =
+                (&collider.collider, &target_collider.collider)
+            {
+                let min_distance = a + b;
+                let offset = target_pos.0 - pos.0;
+                if offset.x.abs() > min_distance ||
+                    offset.y.abs() > min_distance ||
+                    offset.z.abs() > min_distance
+                {
+                    continue;
+                }
+                if offset.norm_squared() <= min_distance * min_distance {
+                    collisions += 1;
+                }
+            }
+        }
+    }
+
+    collisions
+}
+
+fn broadphase(c: &mut Criterion) {
+    let mut group = c.benchmark_group("broadphase");
+
+    for count in &[100, 1_000, 5_000] {
+        let world = build_world(*count);
+
+        group.bench_with_input(BenchmarkId::new("all_pairs_spheres", count), &world, |b, world| {
+            b.iter(|| all_pairs_spheres(world))
+        });
+
+        let mut detection = CollisionDetection::new(&PhysicsConfig::default());
+        group.bench_with_input(BenchmarkId::new("spatial_hash", count), &world, |b, world| {
+            b.iter(|| detection.run_now(world))
+        });
+    }
+
+    group.finish();
+}
+
+criterion_group!(benches, broadphase);
+criterion_main!(benches);
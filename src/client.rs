@@ -5,7 +5,10 @@ use std::sync::mpsc::{
     TryRecvError,
 };
 
-use failure::Error;
+use failure::{
+    Error,
+    format_err,
+};
 use futures::sync::mpsc::unbounded;
 
 use crate::{
@@ -17,7 +20,10 @@ use crate::{
         window::Window,
     },
     simulation::build_simulation,
-    util::config,
+    util::{
+        configloader::LoadedConfig,
+        configwatcher::ConfigWatcher,
+    },
 };
 
 #[derive(serde::Serialize, serde::Deserialize)]
@@ -25,6 +31,7 @@ use crate::{
 pub struct ClientConfig {
     pub server_address: String,
     pub tick_rate: u64,
+    pub max_catch_up_ticks: u32,
 }
 
 impl Default for ClientConfig {
@@ -32,19 +39,19 @@ impl Default for ClientConfig {
         ClientConfig {
             server_address: "127.0.0.1:6142".to_string(),
             tick_rate: 60,
+            max_catch_up_ticks: 5,
         }
     }
 }
 
-pub fn main(config: config::Config) -> Result<(), Error> {
+pub fn main(config: LoadedConfig) -> Result<(), Error> {
+    let LoadedConfig { data: config, merged, loader } = config;
+    let user_config = loader.user_file().map(|path| path.to_path_buf());
+
     let (event_tx, event_rx) = channel();
     let (net_update_tx, net_update_rx) = unbounded();
     let (main_update_tx, main_update_rx) = channel();
 
-    log::info!("Creating window...");
-
-    let window = Window::new(&config.display)?;
-
     log::info!("Initializing networking");
     
     let net_event_tx = event_tx.clone();
@@ -66,20 +73,43 @@ pub fn main(config: config::Config) -> Result<(), Error> {
         log::info!("IO closed");
     });
     
+    log::info!("Initializing config watcher");
+
+    let watcher = ConfigWatcher::new(
+        loader,
+        merged,
+        event_tx.clone(),
+        main_update_tx.clone()
+    );
+    thread::spawn(move || {
+        watcher.run();
+        log::info!("Config watcher closed");
+    });
+
     log::info!("Initializing simulation");
     
-    let tick_length = Duration::from_millis(
-        1000 / config.client.tick_rate
+    let tick_length = Duration::from_secs_f64(
+        1.0 / config.client.tick_rate as f64
     );
+    let max_catch_up = config.client.max_catch_up_ticks;
 
     let sim_config = config.simulation.clone();
+    let (ready_tx, ready_rx) = channel();
     thread::spawn(move || {
-        let mut game = build_simulation(
+        let mut game = match build_simulation(
             sim_config,
             main_update_tx,
             net_update_tx,
-            tick_length
-        );
+            tick_length,
+            max_catch_up
+        ) {
+            Ok(game) => game,
+            Err(e) => {
+                ready_tx.send(Err(e)).ok();
+                return;
+            },
+        };
+        ready_tx.send(Ok(())).ok();
         game.run(
             move || {
                 match event_rx.try_recv() {
@@ -89,8 +119,7 @@ pub fn main(config: config::Config) -> Result<(), Error> {
                     Err(TryRecvError::Empty) => Ok(None),
                     Err(TryRecvError::Disconnected) => Err(()),
                 }
-            },
-            tick_length
+            }
         )
             .unwrap_or_else(|_| {
                 log::error!("Network thread disconnected")
@@ -98,6 +127,15 @@ pub fn main(config: config::Config) -> Result<(), Error> {
         log::info!("Simulation closed");
     });
 
+    // nothing to show without a simulation, so fail before opening the window
+    ready_rx.recv()
+        .map_err(|_| format_err!("Simulation thread exited during startup"))?
+        .map_err(|e| format_err!("Failed to build simulation: {}", e))?;
+
+    log::info!("Creating window...");
+
+    let window = Window::new(&config.display)?;
+
     log::info!("Initializing rendering pipeline...");
 
     let (window, event_loop) = window.split();
@@ -105,5 +143,13 @@ pub fn main(config: config::Config) -> Result<(), Error> {
 
     log::info!("Entering main loop");
     
-    eventloop::run(renderer, event_loop, config, event_tx, main_update_rx, io_channel)
+    eventloop::run(
+        renderer,
+        event_loop,
+        config,
+        user_config,
+        event_tx,
+        main_update_rx,
+        io_channel
+    )
 }
\ No newline at end of file
@@ -0,0 +1,52 @@
+use eternalreckoning_ui::{
+    Component,
+    dimension::{
+        Dimension,
+        Offset,
+        Position,
+    },
+    element::{
+        Element,
+        ElementDisplay,
+    }
+};
+
+/**
+ * Shown over the scene while the player is dead, telling them how to
+ * respawn
+ */
+pub struct DeathScreen {
+    texture: String,
+    width: i32,
+    height: i32,
+}
+
+impl DeathScreen {
+    pub fn new() -> DeathScreen {
+        DeathScreen {
+            texture: "assets/death.png".to_string(),
+            width: 512,
+            height: 256,
+        }
+    }
+}
+
+impl Component for DeathScreen {
+    fn render(&self) -> Element {
+        Element::new(
+            Position {
+                x: Offset::new(0.5, -self.width / 2),
+                y: Offset::new(0.4, -self.height / 2),
+            },
+            Dimension {
+                width: Offset::new(0.0, self.width),
+                height: Offset::new(0.0, self.height),
+            },
+            Some(ElementDisplay::new(
+                self.texture.clone(),
+                [0.0, 0.0],
+                [1.0, 1.0]
+            ))
+        )
+    }
+}
@@ -11,34 +11,66 @@ use eternalreckoning_ui::{
     }
 };
 
+const COOLDOWN_SHADE: &str = "assets/cooldown.png";
+const SWEEP_STEPS: f32 = 32.0;
+
 pub struct Hotbar {
     button_size: i32,
     button_padding: i32,
-    buttons: Vec<String>,
+    buttons: Vec<HotbarSlot>,
+}
+
+/**
+ * 'cooldown' is the fraction of the ability's cooldown left, shown as a
+ * shade over the icon that drains away as the cooldown runs out
+ */
+#[derive(Clone, PartialEq)]
+pub struct HotbarSlot {
+    pub icon: String,
+    pub cooldown: f32,
 }
 
 pub struct HotbarButton {
     icon: String,
+    cooldown: f32,
     size: i32,
     offset: i32,
 }
 
+struct CooldownShade {
+    cooldown: f32,
+    size: i32,
+}
+
 impl Hotbar {
-    pub fn new() -> Hotbar {
+    pub fn new(buttons: Vec<HotbarSlot>) -> Hotbar {
         Hotbar {
             button_size: 64,
             button_padding: 8,
-            buttons: vec![
-                "assets/icon_attack.png".to_string(),
-                "assets/icon_attack.png".to_string(),
-            ],
+            buttons,
+        }
+    }
+}
+
+impl HotbarSlot {
+    pub fn new(icon: String, remaining: f64, cooldown: f64) -> HotbarSlot {
+        let fraction = if cooldown > 0.0 {
+            (remaining / cooldown).max(0.0).min(1.0) as f32
+        } else {
+            0.0
+        };
+
+        // stepped, so that the hotbar is only rebuilt when the sweep moves
+        HotbarSlot {
+            icon,
+            cooldown: (fraction * SWEEP_STEPS).ceil() / SWEEP_STEPS,
         }
     }
 }
 
 impl Component for Hotbar {
     fn render(&self) -> Element {
-        let width = 
+        let width =
             (self.button_padding + self.button_size) *
             self.buttons.len() as i32 +
             self.button_padding;
@@ -57,7 +89,8 @@ impl Component for Hotbar {
 
         for (index, child) in (&self.buttons).iter().enumerate() {
             bar.add_child(Box::new(HotbarButton {
-                icon: child.clone(),
+                icon: child.icon.clone(),
+                cooldown: child.cooldown,
                 size: self.button_size,
                 offset: index as i32 * (self.button_size + self.button_padding),
             }));
@@ -69,7 +102,7 @@ impl Component for Hotbar {
 
 impl Component for HotbarButton {
     fn render(&self) -> Element {
-        Element::new(
+        let mut button = Element::new(
             Position {
                 x: Offset::new(0.0, self.offset),
                 y: Offset::new(0.0, 0),
@@ -83,6 +116,38 @@ impl Component for HotbarButton {
                 [0.0, 0.0],
                 [1.0, 1.0]
             ))
+        );
+
+        if self.cooldown > 0.0 {
+            button.add_child(Box::new(CooldownShade {
+                cooldown: self.cooldown,
+                size: self.size,
+            }));
+        }
+
+        button
+    }
+}
+
+impl Component for CooldownShade {
+    fn render(&self) -> Element {
+        // covers the bottom of the icon, shrinking towards it
+        let height = (self.size as f32 * self.cooldown).round() as i32;
+
+        Element::new(
+            Position {
+                x: Offset::new(0.0, 0),
+                y: Offset::new(0.0, self.size - height),
+            },
+            Dimension {
+                width: Offset::new(0.0, self.size),
+                height: Offset::new(0.0, height),
+            },
+            Some(ElementDisplay::new(
+                COOLDOWN_SHADE.to_string(),
+                [0.0, 1.0 - self.cooldown],
+                [1.0, 1.0]
+            ))
         )
     }
-}
\ No newline at end of file
+}
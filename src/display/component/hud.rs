@@ -0,0 +1,53 @@
+use eternalreckoning_ui::{
+    Component,
+    dimension::{
+        Dimension,
+        Offset,
+        Position,
+    },
+    element::Element,
+};
+
+use super::{
+    Hotbar,
+    TargetFrame,
+    hotbar::HotbarSlot,
+};
+
+/**
+ * Shown while playing: the hotbar, and the target frame when something is
+ * targeted
+ */
+pub struct Hud {
+    hotbar: Vec<HotbarSlot>,
+    target: Option<TargetFrame>,
+}
+
+impl Hud {
+    pub fn new(hotbar: Vec<HotbarSlot>, target: Option<TargetFrame>) -> Hud {
+        Hud { hotbar, target }
+    }
+}
+
+impl Component for Hud {
+    fn render(&self) -> Element {
+        let mut hud = Element::new(
+            Position {
+                x: Offset::new(0.0, 0),
+                y: Offset::new(0.0, 0),
+            },
+            Dimension {
+                width: Offset::new(1.0, 0),
+                height: Offset::new(1.0, 0),
+            },
+            None
+        );
+
+        hud.add_child(Box::new(Hotbar::new(self.hotbar.clone())));
+        if let Some(ref target) = self.target {
+            hud.add_child(Box::new(target.clone()));
+        }
+
+        hud
+    }
+}
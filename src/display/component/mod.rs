@@ -1,5 +1,11 @@
+pub mod deathscreen;
 pub mod hotbar;
+pub mod hud;
 pub mod splash;
+pub mod targetframe;
 
+pub use deathscreen::DeathScreen;
 pub use hotbar::Hotbar;
-pub use splash::Splash;
\ No newline at end of file
+pub use hud::Hud;
+pub use splash::Splash;
+pub use targetframe::TargetFrame;
\ No newline at end of file
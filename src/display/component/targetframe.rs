@@ -0,0 +1,99 @@
+use eternalreckoning_ui::{
+    Component,
+    dimension::{
+        Dimension,
+        Offset,
+        Position,
+    },
+    element::{
+        Element,
+        ElementDisplay,
+    }
+};
+
+/**
+ * Frame at the top of the screen while something is targeted, with a bar
+ * for the target's health if it has any
+ */
+#[derive(Clone, PartialEq)]
+pub struct TargetFrame {
+    health: Option<f32>,
+    width: i32,
+    height: i32,
+    padding: i32,
+}
+
+struct HealthBar {
+    fraction: f32,
+    width: i32,
+    height: i32,
+    padding: i32,
+}
+
+impl TargetFrame {
+    pub fn new(health: Option<(u64, u64)>) -> TargetFrame {
+        TargetFrame {
+            health: health.map(|(current, max)| {
+                if max > 0 {
+                    (current as f32 / max as f32).min(1.0)
+                } else {
+                    0.0
+                }
+            }),
+            width: 256,
+            height: 48,
+            padding: 8,
+        }
+    }
+}
+
+impl Component for TargetFrame {
+    fn render(&self) -> Element {
+        let mut frame = Element::new(
+            Position {
+                x: Offset::new(0.5, -self.width / 2),
+                y: Offset::new(0.05, 0),
+            },
+            Dimension {
+                width: Offset::new(0.0, self.width),
+                height: Offset::new(0.0, self.height),
+            },
+            Some(ElementDisplay::new(
+                "assets/target_frame.png".to_string(),
+                [0.0, 0.0],
+                [1.0, 1.0]
+            ))
+        );
+
+        if let Some(fraction) = self.health {
+            frame.add_child(Box::new(HealthBar {
+                fraction,
+                width: self.width - self.padding * 2,
+                height: self.height - self.padding * 2,
+                padding: self.padding,
+            }));
+        }
+
+        frame
+    }
+}
+
+impl Component for HealthBar {
+    fn render(&self) -> Element {
+        Element::new(
+            Position {
+                x: Offset::new(0.0, self.padding),
+                y: Offset::new(0.0, self.padding),
+            },
+            Dimension {
+                width: Offset::new(0.0, (self.width as f32 * self.fraction).round() as i32),
+                height: Offset::new(0.0, self.height),
+            },
+            Some(ElementDisplay::new(
+                "assets/health_bar.png".to_string(),
+                [0.0, 0.0],
+                [self.fraction, 1.0]
+            ))
+        )
+    }
+}
@@ -4,6 +4,14 @@ pub struct DisplayConfig {
     pub display_mode: DisplayMode,
     pub field_of_view: f32,
     pub forward_interpolate: f32,
+    /**
+     * Seconds taken to fade the view back in after respawning
+     */
+    pub respawn_fade: f32,
+    /**
+     * Seconds the view stays dimmed for after taking damage
+     */
+    pub hit_flash: f32,
 }
 
 #[derive(serde::Serialize, serde::Deserialize)]
@@ -20,6 +28,8 @@ impl Default for DisplayConfig {
             display_mode: DisplayMode::Windowed,
             field_of_view: 68.0,
             forward_interpolate: 0.0,
+            respawn_fade: 0.5,
+            hit_flash: 0.25,
         }
     }
 }
\ No newline at end of file
@@ -0,0 +1,34 @@
+use std::time::{
+    Duration,
+    Instant,
+};
+
+/**
+ * Fades the scene in from black, such as after the view jumps on a respawn
+ */
+pub struct Fade {
+    duration: Duration,
+    started: Option<Instant>,
+}
+
+impl Fade {
+    pub fn new(duration: Duration) -> Fade {
+        Fade { duration, started: None }
+    }
+
+    pub fn start(&mut self) {
+        self.started = Some(Instant::now());
+    }
+
+    /**
+     * Multiplier for scene colours, from 0 at the start of the fade to 1
+     */
+    pub fn brightness(&self) -> f32 {
+        match self.started {
+            Some(started) if self.duration > Duration::from_secs(0) => {
+                (started.elapsed().as_secs_f32() / self.duration.as_secs_f32()).min(1.0)
+            },
+            _ => 1.0,
+        }
+    }
+}
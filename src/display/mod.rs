@@ -1,5 +1,6 @@
 pub mod component;
 pub mod displayconfig;
+pub mod fade;
 pub mod mesh;
 pub mod model;
 pub mod renderer;
@@ -7,12 +8,14 @@ pub mod scene;
 pub mod terrain;
 pub mod texture;
 pub mod ui;
+pub mod water;
 pub mod window;
 
 mod graph;
 mod pipeline;
 
 pub use displayconfig::DisplayConfig;
+pub use fade::Fade;
 pub use graph::RenderGraph;
 pub use mesh::Mesh;
 pub use model::Model;
@@ -4,11 +4,19 @@
 layout(early_fragment_tests) in;
 
 layout(location = 0) in vec2 uv;
+layout(location = 1) in vec4 tint;
 layout(location = 0) out vec4 color;
 
+layout(set = 0, binding = 0) uniform Args {
+    mat4 proj;
+    mat4 view;
+    float brightness;
+};
 layout(set = 0, binding = 1) uniform texture2D colormap;
 layout(set = 0, binding = 2) uniform sampler colorsampler;
 
 void main() {
     color = texture(sampler2D(colormap, colorsampler), uv);
+    color.rgb = mix(color.rgb, tint.rgb, tint.a);
+    color.rgb *= brightness;
 }
\ No newline at end of file
@@ -82,12 +82,14 @@ const fn indirect_offset(index: usize, align: u64, offset: u64) -> u64 {
 struct UniformArgs {
     proj: nalgebra::Matrix4<f32>,
     view: nalgebra::Matrix4<f32>,
+    brightness: f32,
 }
 
 #[derive(Copy, Clone, Debug)]
 #[repr(C, align(16))]
 struct InstanceArgs {
     model: nalgebra::Transform3<f32>,
+    tint: [f32; 4],
 }
 
 #[derive(Debug)]
@@ -150,7 +152,7 @@ where
                 .unwrap()
                 .gfx_vertex_input_desc(hal::pso::VertexInputRate::Vertex),
             SHADER_REFLECTION
-                .attributes_range(2..6)
+                .attributes_range(2..7)
                 .unwrap()
                 .gfx_vertex_input_desc(hal::pso::VertexInputRate::Instance(1)),
         ];
@@ -330,6 +332,7 @@ where
                     &[UniformArgs {
                         proj: scene.camera.proj.to_homogeneous(),
                         view: scene.camera.view.inverse().to_homogeneous(),
+                        brightness: scene.brightness(),
                     }]
                 )
                 .unwrap();
@@ -457,7 +460,8 @@ where
                             model: match model.offset {
                                 Some(offset) => nalgebra::convert(object.position * nalgebra::Translation3::from(offset)),
                                 None => nalgebra::convert(object.position),
-                            }
+                            },
+                            tint: scene.tint(object),
                         }],
                     )
                     .unwrap();
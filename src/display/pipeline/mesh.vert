@@ -5,15 +5,19 @@ layout(location = 0) in vec3 position;
 layout(location = 1) in vec2 uv;
 // vec4[4] is used instead of mat4 due to spirv-cross bug
 layout(location = 2) in vec4 model[4];
+layout(location = 6) in vec4 tint;
 layout(location = 0) out vec2 frag_uv;
+layout(location = 1) out vec4 frag_tint;
 
 layout(set = 0, binding = 0) uniform Args {
     mat4 proj;
     mat4 view;
+    float brightness;
 };
 
 void main() {
     mat4 model_mat = mat4(model[0], model[1], model[2], model[3]);
     frag_uv = uv;
+    frag_tint = tint;
     gl_Position = proj * view * model_mat * vec4(position, 1.0);
 }
\ No newline at end of file
@@ -6,6 +6,7 @@ use rendy::init::AnyWindowedRendy;
 
 use super::{
     DisplayConfig,
+    Fade,
     RenderGraph,
     scene,
     ui::UI,
@@ -47,6 +48,9 @@ impl Renderer {
                     objects: Vec::new(),
                     ticks: [time, time],
                     ui: UI::new(size.width as f64, size.height as f64),
+                    fade: Fade::new(std::time::Duration::from_secs_f32(config.respawn_fade.max(0.0))),
+                    hit_flash: Fade::new(std::time::Duration::from_secs_f32(config.hit_flash.max(0.0))),
+                    highlight: None,
                 };
 
                 let graph = Some(RenderGraph::new(
@@ -1,8 +1,19 @@
 use rendy::hal;
 
 use crate::util::interpolate;
+use super::Fade;
 use super::ui::UI;
 
+/**
+ * How bright the scene is at the start of a hit flash
+ */
+const HIT_FLASH_BRIGHTNESS: f32 = 0.6;
+
+/**
+ * Colour the selected target is tinted with, alpha being how strongly
+ */
+const HIGHLIGHT_TINT: [f32; 4] = [1.0, 0.85, 0.3, 0.35];
+
 #[derive(Debug)]
 pub struct Camera {
     pub view: nalgebra::Projective3<f32>,
@@ -18,6 +29,7 @@ pub struct Object {
     pub texture: Option<usize>,
     pub position: nalgebra::Similarity3<f32>,
     pub ticks: [Option<nalgebra::Point3<f32>>; 2],
+    pub rotation_ticks: [Option<nalgebra::UnitQuaternion<f32>>; 2],
 }
 
 pub struct Scene<B>
@@ -30,6 +42,9 @@ where
     pub textures: Vec<super::Texture<B>>,
     pub ticks: [std::time::Instant; 2],
     pub ui: UI<B>,
+    pub fade: Fade,
+    pub hit_flash: Fade,
+    pub highlight: Option<specs::Entity>,
 }
 
 impl Camera {
@@ -55,6 +70,32 @@ impl Camera {
         self.view = view;
     }
 
+    /**
+     * Ray from the near plane through 'ndc', a point on the screen in
+     * normalized device coordinates, as an origin and a direction
+     */
+    pub fn ray(&self, ndc: &nalgebra::Point2<f32>)
+        -> (nalgebra::Point3<f32>, nalgebra::Vector3<f32>)
+    {
+        let near = self.view * self.proj.unproject_point(&nalgebra::Point3::new(ndc.x, ndc.y, -1.0));
+        let far = self.view * self.proj.unproject_point(&nalgebra::Point3::new(ndc.x, ndc.y, 1.0));
+        (near, far - near)
+    }
+
+    /**
+     * Ray through the cursor at 'cursor', in pixels from the top left of a
+     * window of 'width' by 'height', for picking what is under it
+     */
+    pub fn cursor_ray(&self, cursor: &nalgebra::Point2<f64>, width: f64, height: f64)
+        -> (nalgebra::Point3<f32>, nalgebra::Vector3<f32>)
+    {
+        let ndc = nalgebra::Point2::new(
+            (cursor.x / width * 2.0 - 1.0) as f32,
+            (cursor.y / height * 2.0 - 1.0) as f32,
+        );
+        self.ray(&ndc)
+    }
+
     pub fn set_position(&mut self, position: nalgebra::Point3<f32>, interpolate: bool) {
         if interpolate {
             self.ticks[0] = self.ticks[1];
@@ -81,6 +122,7 @@ impl Object {
             model: None,
             texture: None,
             ticks: [None, None],
+            rotation_ticks: [None, None],
             id,
             position,
         }
@@ -103,22 +145,25 @@ where
         let progress = elapsed as f32 / tick_ms as f32 + forward_interpolate;
 
         for object in &mut self.objects {
-            if object.ticks[0].is_none() || object.ticks[1].is_none() {
-                continue;
-            }
+            let translation = match object.ticks {
+                [Some(ref start), Some(ref stop)] => {
+                    let interp_pos = interpolate::lerp(start, stop, progress);
+                    nalgebra::Translation3::<f32>::new(
+                        interp_pos.x,
+                        interp_pos.y,
+                        interp_pos.z
+                    )
+                },
+                _ => object.position.isometry.translation,
+            };
 
-            let interp_pos = interpolate::lerp(
-                object.ticks[0].as_ref().unwrap(),
-                object.ticks[1].as_ref().unwrap(),
-                progress
-            );
+            let rotation = match object.rotation_ticks {
+                [Some(ref start), Some(ref stop)] => interpolate::slerp(start, stop, progress),
+                _ => object.position.isometry.rotation,
+            };
 
             object.position = nalgebra::Similarity3::<f32>::identity() *
-                nalgebra::Translation3::<f32>::new(
-                    interp_pos.x,
-                    interp_pos.y,
-                    interp_pos.z
-                );
+                nalgebra::Isometry3::from_parts(translation, rotation);
         }
 
         if self.camera.ticks[0].is_some() && self.camera.ticks[1].is_some() {
@@ -136,6 +181,24 @@ where
         }
     }
 
+    /**
+     * Multiplier for scene colours, for fading in and flashing on hits
+     */
+    pub fn brightness(&self) -> f32 {
+        let flash = HIT_FLASH_BRIGHTNESS + (1.0 - HIT_FLASH_BRIGHTNESS) * self.hit_flash.brightness();
+        self.fade.brightness() * flash
+    }
+
+    /**
+     * Colour an object is tinted with, with the strength of the tint as alpha
+     */
+    pub fn tint(&self, object: &Object) -> [f32; 4] {
+        match self.highlight {
+            Some(id) if id == object.id => HIGHLIGHT_TINT,
+            _ => [0.0; 4],
+        }
+    }
+
     pub fn set_model(
         &mut self,
         id: specs::Entity,
@@ -192,6 +255,54 @@ where
         }
     }
 
+    pub fn set_rotation(
+        &mut self,
+        id: specs::Entity,
+        rotation: nalgebra::UnitQuaternion::<f32>,
+    ) -> bool
+    {
+        match self.object_by_id(id) {
+            Some(index) => {
+                let object = self.objects.get_mut(index).unwrap();
+                object.rotation_ticks[0] = object.rotation_ticks[1];
+                object.rotation_ticks[1] = Some(rotation);
+                return true;
+            },
+            _ => false,
+        }
+    }
+
+    /**
+     * Moves an object without interpolating from where it was
+     */
+    pub fn teleport(
+        &mut self,
+        id: specs::Entity,
+        position: nalgebra::Point3::<f32>,
+    ) -> bool
+    {
+        match self.object_by_id(id) {
+            Some(index) => {
+                let object = self.objects.get_mut(index).unwrap();
+                object.ticks = [None, None];
+                object.position.isometry.translation =
+                    nalgebra::Translation3::<f32>::new(position.x, position.y, position.z);
+                return true;
+            },
+            _ => false,
+        }
+    }
+
+    /**
+     * Removes an object that was despawned from the simulation
+     */
+    pub fn remove_object(&mut self, id: specs::Entity) {
+        self.objects.retain(|object| object.id != id);
+        if self.highlight == Some(id) {
+            self.highlight = None;
+        }
+    }
+
     pub fn get_model<'a>(
         &'a self,
         path: &str,
@@ -1,13 +1,27 @@
+#[derive(Clone)]
 pub struct HeightMap {
     pub size: usize,
     scale: f32,
     data: Vec<f32>,
+    min: f32,
+    max: f32,
 }
 
 impl HeightMap {
     pub fn new(data: Vec<f32>, size: usize, scale: f32) -> HeightMap {
         assert_eq!(data.len(), size*size);
-        HeightMap { size, scale, data }
+
+        let min = data.iter().cloned().fold(std::f32::INFINITY, f32::min);
+        let max = data.iter().cloned().fold(std::f32::NEG_INFINITY, f32::max);
+
+        HeightMap { size, scale, data, min, max }
+    }
+
+    /**
+     * Lowest and highest (scaled) heights in the map
+     */
+    pub fn height_range(&self) -> (f32, f32) {
+        (self.min * self.scale, self.max * self.scale)
     }
 
     pub fn len(&self) -> usize {
@@ -22,6 +36,62 @@ impl HeightMap {
         Some(*self.data.get(x + y * self.size).unwrap() * self.scale)
     }
 
+    /**
+     * Bilinearly interpolated (scaled) height at map coordinates 'x' and 'z',
+     * None outside the map
+     */
+    pub fn height_at(&self, x: f32, z: f32) -> Option<f32> {
+        let (x0, z0, fx, fz) = self.cell_at(x, z)?;
+
+        let h00 = self.get(x0, z0)?;
+        let h10 = self.get(x0 + 1, z0)?;
+        let h01 = self.get(x0, z0 + 1)?;
+        let h11 = self.get(x0 + 1, z0 + 1)?;
+
+        Some(
+            h00 * (1.0 - fx) * (1.0 - fz) +
+            h10 * fx * (1.0 - fz) +
+            h01 * (1.0 - fx) * fz +
+            h11 * fx * fz
+        )
+    }
+
+    /**
+     * Normal of the bilinearly interpolated surface at map coordinates 'x'
+     * and 'z', pointing away from the ground (towards negative y), None
+     * outside the map
+     */
+    pub fn normal_at(&self, x: f32, z: f32) -> Option<nalgebra::Unit<nalgebra::Vector3<f32>>> {
+        let (x0, z0, fx, fz) = self.cell_at(x, z)?;
+
+        let h00 = self.get(x0, z0)?;
+        let h10 = self.get(x0 + 1, z0)?;
+        let h01 = self.get(x0, z0 + 1)?;
+        let h11 = self.get(x0 + 1, z0 + 1)?;
+
+        let dx = (h10 - h00) * (1.0 - fz) + (h11 - h01) * fz;
+        let dz = (h01 - h00) * (1.0 - fx) + (h11 - h10) * fx;
+
+        // heights are subtracted from y, so the surface is (x, -h, z)
+        Some(nalgebra::Unit::new_normalize(nalgebra::Vector3::new(-dx, -1.0, -dz)))
+    }
+
+    /**
+     * Grid cell containing map coordinates 'x' and 'z' and the position
+     * within it, points on the far edges belong to the last cell
+     */
+    fn cell_at(&self, x: f32, z: f32) -> Option<(usize, usize, f32, f32)> {
+        let last = (self.size - 1) as f32;
+        if self.size < 2 || !(0.0..=last).contains(&x) || !(0.0..=last).contains(&z) {
+            return None;
+        }
+
+        let x0 = (x.floor() as usize).min(self.size - 2);
+        let z0 = (z.floor() as usize).min(self.size - 2);
+
+        Some((x0, z0, x - x0 as f32, z - z0 as f32))
+    }
+
     pub fn vertices(&self) -> Vec<rendy::mesh::Position> {
         let mut res = Vec::with_capacity(self.data.len());
 
@@ -140,6 +210,50 @@ mod tests {
         assert_eq!(*indices.get(23).unwrap(), 7);
     }
 
+    #[test]
+    fn test_height_at() {
+        let data = vec![
+            0.0, 1.0, 0.0,
+            1.0, 2.0, 1.0,
+            0.0, 1.0, 0.0,
+        ];
+
+        let heightmap = HeightMap::new(data, 3, 2.0);
+
+        assert_eq!(heightmap.height_at(1.0, 1.0), Some(4.0));
+        assert_eq!(heightmap.height_at(0.5, 0.0), Some(1.0));
+        assert_eq!(heightmap.height_at(0.5, 0.5), Some(2.0));
+
+        // far edges and corner
+        assert_eq!(heightmap.height_at(2.0, 1.0), Some(2.0));
+        assert_eq!(heightmap.height_at(1.0, 2.0), Some(2.0));
+        assert_eq!(heightmap.height_at(2.0, 2.0), Some(0.0));
+
+        assert_eq!(heightmap.height_at(-0.1, 1.0), None);
+        assert_eq!(heightmap.height_at(1.0, 2.1), None);
+    }
+
+    #[test]
+    fn test_normal_at() {
+        // rising towards positive x
+        let data = vec![
+            0.0, 1.0,
+            0.0, 1.0,
+        ];
+
+        let heightmap = HeightMap::new(data, 2, 1.0);
+        let normal = heightmap.normal_at(0.5, 0.5).unwrap();
+
+        assert!(normal.y < 0.0);
+        assert!(normal.x < 0.0);
+        assert_eq!(normal.z, 0.0);
+        assert!((normal.x - normal.y).abs() < 1e-6);
+
+        let flat = HeightMap::new(vec![0.0; 4], 2, 1.0);
+        assert_eq!(flat.normal_at(1.0, 1.0).unwrap().y, -1.0);
+        assert!(flat.normal_at(1.5, 1.0).is_none());
+    }
+
     #[test]
     fn test_index_counts() {
         let data = vec![
@@ -0,0 +1,61 @@
+use failure::Error;
+
+use super::mesh::MeshBuilder;
+use super::Mesh;
+
+/**
+ * Size in m of one repeat of the water texture
+ */
+const TEXTURE_SIZE: f32 = 4.0;
+
+/**
+ * Flat quad covering the water surface between the corners 'min' and 'max',
+ * which share their y coordinate. Both sides are drawn, so the surface is
+ * also seen from underwater.
+ */
+pub fn surface_mesh(min: &nalgebra::Point3<f32>, max: &nalgebra::Point3<f32>) -> Result<Mesh, Error> {
+    let vertices: Vec<rendy::mesh::Position> = vec![
+        [min.x, min.y, min.z].into(),
+        [max.x, min.y, min.z].into(),
+        [min.x, min.y, max.z].into(),
+        [max.x, min.y, max.z].into(),
+    ];
+
+    let u = (max.x - min.x) / TEXTURE_SIZE;
+    let v = (max.z - min.z) / TEXTURE_SIZE;
+    let uvs: Vec<rendy::mesh::TexCoord> = vec![
+        [0.0, v].into(),
+        [u, v].into(),
+        [0.0, 0.0].into(),
+        [u, 0.0].into(),
+    ];
+
+    MeshBuilder::new()
+        .with_vertices(&vertices)
+        .with_uvs(&uvs)
+        .with_indices(&[
+            0, 1, 2, 1, 3, 2,
+            0, 2, 1, 1, 2, 3,
+        ])
+        .build()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_surface_mesh() {
+        let mesh = surface_mesh(
+            &nalgebra::Point3::new(-4.0, -1.0, -2.0),
+            &nalgebra::Point3::new(4.0, -1.0, 2.0),
+        ).unwrap();
+
+        assert_eq!(mesh.len(), 4);
+        assert_eq!(mesh.indices.len(), 12);
+        for vertex in &mesh.vertices {
+            assert_eq!(vertex.position.0[1], -1.0);
+        }
+        assert_eq!(mesh.vertices[1].tex_coord.0, [2.0, 1.0]);
+    }
+}
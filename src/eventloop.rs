@@ -1,3 +1,4 @@
+use std::path::PathBuf;
 use std::sync::mpsc::{
     Sender,
     Receiver,
@@ -18,13 +19,17 @@ use crate::{
         },
     },
     simulation::event,
-    util::config,
+    util::{
+        config,
+        configwriter::SettingsWriter,
+    },
 };
 
 pub fn run(
     renderer: Renderer,
     event_loop: winit::event_loop::EventLoop<()>,
-    config: config::Config,
+    mut config: config::Config,
+    user_config: Option<PathBuf>,
     event_tx: Sender<event::Event>,
     update_rx: Receiver<event::Update>,
     io_channel: (Sender<iohandler::Request>, Receiver<iohandler::Response>),
@@ -35,15 +40,34 @@ pub fn run(
     key_map.insert(config.key_map.move_backward, InputTypes::MoveBackward);
     key_map.insert(config.key_map.move_right, InputTypes::MoveRight);
     key_map.insert(config.key_map.move_up, InputTypes::MoveUp);
+    key_map.insert(config.key_map.sprint, InputTypes::Sprint);
+    key_map.insert(config.key_map.crouch, InputTypes::Crouch);
+    key_map.insert(config.key_map.respawn, InputTypes::Respawn);
+    for (slot, key) in config.key_map.abilities.iter().enumerate() {
+        key_map.insert(*key, InputTypes::Ability(slot));
+    }
+    key_map.insert(config.key_map.target_next, InputTypes::TargetNext);
+    key_map.insert(config.key_map.clear_target, InputTypes::ClearTarget);
+
+    let mut settings = match user_config {
+        Some(path) => Some(SettingsWriter::new(&config, path)?),
+        None => None,
+    };
 
     let (io_tx, io_rx) = io_channel;
     let mut renderer = Some(renderer);
 
     let mut loading = 0;
 
-    let mouse_sens = input::MouseSensitivity::new(config.mouse.sensitivity);
+    let mut mouse_sens = input::MouseSensitivity::new(config.mouse.sensitivity);
+    let mut forward_interpolate = config.display.forward_interpolate;
     let mut mouse_euler = input::MouseEuler::default();
     let mut mouse_look = false;
+    let mut cursor = nalgebra::Point2::<f64>::origin();
+    let mut dead = false;
+    let mut hotbar = Vec::new();
+    let mut target = None;
+    let mut target_frame = None;
 
     event_loop.run(move |event, _, control_flow| {
         *control_flow = winit::event_loop::ControlFlow::Poll;
@@ -63,7 +87,11 @@ pub fn run(
                         }
                     );
 
-                    if input.scancode == 1 {
+                    // escape clears the target first, if there is one
+                    if input.scancode == 1
+                        && input.state == winit::event::ElementState::Pressed
+                        && target.is_none()
+                    {
                         *control_flow = winit::event_loop::ControlFlow::Exit;
                     }
 
@@ -92,6 +120,29 @@ pub fn run(
                     if button == winit::event::MouseButton::Right {
                         mouse_look = state == winit::event::ElementState::Pressed;
                     }
+
+                    if button == winit::event::MouseButton::Left
+                        && state == winit::event::ElementState::Pressed
+                    {
+                        if let Some(renderer) = &mut renderer {
+                            // select whatever is under the cursor
+                            let scene = renderer.get_scene();
+                            let (origin, direction) = scene.camera.cursor_ray(
+                                &cursor,
+                                scene.ui.root.width(),
+                                scene.ui.root.height(),
+                            );
+
+                            let event = event::InputEvent::Select {
+                                origin: nalgebra::convert(origin),
+                                direction: nalgebra::convert(direction),
+                            };
+                            event_tx.send(event::Event::InputEvent(event)).unwrap();
+                        }
+                    }
+                },
+                winit::event::WindowEvent::CursorMoved { position, .. } => {
+                    cursor = nalgebra::Point2::new(position.x, position.y);
                 },
                 _ => {},
             },
@@ -187,6 +238,102 @@ pub fn run(
                                                 });
                                         }
                                     },
+                                    event::Update::WaterUpdate(event::WaterUpdate { entity, min, max }) => {
+                                        let path = format!("water/{}", entity.id());
+                                        if scene.get_model(&path[..]).is_none() {
+                                            match display::water::surface_mesh(&min, &max) {
+                                                Ok(mesh) => {
+                                                    scene.set_model(entity, &path, None);
+                                                    water_loaded(scene, &path, mesh);
+                                                },
+                                                Err(err) => log::error!("Failed to build water surface: {}", err),
+                                            }
+                                        }
+                                    },
+                                    event::Update::ConfigUpdate(config_event) => {
+                                        config.apply(&config_event);
+                                        if let Some(ref mut settings) = settings {
+                                            settings.reloaded(&config_event);
+                                        }
+                                        match config_event {
+                                            event::ConfigEvent::MouseSensitivity(sensitivity) => {
+                                                mouse_sens = input::MouseSensitivity::new(sensitivity);
+                                            },
+                                            event::ConfigEvent::ForwardInterpolate(value) => {
+                                                forward_interpolate = value;
+                                            },
+                                            _ => (),
+                                        };
+                                    },
+                                    event::Update::RotationUpdate(event::RotationUpdate { entity, rotation, .. }) => {
+                                        scene.set_rotation(entity, nalgebra::convert(rotation));
+                                    },
+                                    event::Update::RespawnUpdate(event::RespawnUpdate { entity, position, camera }) => {
+                                        let position: nalgebra::Point3<f32> = nalgebra::convert(position);
+                                        scene.teleport(entity, position);
+                                        if camera {
+                                            scene.camera.set_position(position, false);
+                                            scene.fade.start();
+
+                                            if dead {
+                                                dead = false;
+                                                scene.ui.set_root(Box::new(display::component::Hud::new(hotbar.clone(), target_frame.clone())));
+                                            }
+                                        }
+                                    },
+                                    event::Update::DamageUpdate(event::DamageUpdate { amount, health, max_health, camera, .. }) => {
+                                        if camera {
+                                            log::debug!("Took {} damage, {}/{} health left", amount, health, max_health);
+                                            scene.hit_flash.start();
+                                        }
+                                    },
+                                    event::Update::DeathUpdate(event::DeathUpdate { camera, .. }) => {
+                                        if camera {
+                                            dead = true;
+                                            scene.ui.set_root(Box::new(display::component::DeathScreen::new()));
+                                        }
+                                    },
+                                    event::Update::AbilityUpdate(event::AbilityUpdate { abilities, .. }) => {
+                                        let slots = abilities.into_iter()
+                                            .map(|ability| display::component::hotbar::HotbarSlot::new(
+                                                ability.icon,
+                                                ability.remaining,
+                                                ability.cooldown,
+                                            ))
+                                            .collect::<Vec<_>>();
+
+                                        if slots != hotbar {
+                                            hotbar = slots;
+                                            if !dead && loading == 0 {
+                                                scene.ui.set_root(Box::new(display::component::Hud::new(hotbar.clone(), target_frame.clone())));
+                                            }
+                                        }
+                                    },
+                                    event::Update::TargetUpdate(event::TargetUpdate { target: selected, name, health }) => {
+                                        if selected != target {
+                                            log::debug!("Target: {:?} {}", selected, name.unwrap_or_default());
+                                        }
+                                        target = selected;
+                                        target_frame = selected.map(|_| display::component::TargetFrame::new(health));
+                                        scene.highlight = selected;
+
+                                        if !dead && loading == 0 {
+                                            scene.ui.set_root(Box::new(display::component::Hud::new(hotbar.clone(), target_frame.clone())));
+                                        }
+                                    },
+                                    event::Update::DespawnUpdate(event::DespawnUpdate { entity }) => {
+                                        scene.remove_object(entity);
+                                    },
+                                    event::Update::ImpactUpdate(event::ImpactEvent { projectile, entity, point, .. }) => {
+                                        log::debug!("Projectile {:?} hit {:?} at {:?}", projectile, entity, point);
+                                    },
+                                    event::Update::AliveUpdate(event::AliveUpdate { camera, .. }) => {
+                                        if camera && dead {
+                                            dead = false;
+                                            scene.ui.set_root(Box::new(display::component::Hud::new(hotbar.clone(), target_frame.clone())));
+                                        }
+                                    },
+                                    event::Update::AbilityRequest(_) => (),
                                     event::Update::SimulationTick(time) => {
                                         scene.ticks[0] = scene.ticks[1];
                                         scene.ticks[1] = time;
@@ -197,7 +344,7 @@ pub fn run(
                         }
                     }
 
-                    scene.interpolate_objects(config.display.forward_interpolate);
+                    scene.interpolate_objects(forward_interpolate);
 
                     let rotation = nalgebra::Rotation3::from_euler_angles(
                         mouse_euler.pitch as f32,
@@ -248,7 +395,11 @@ pub fn run(
                         if let Some(renderer) = &mut renderer {
                             let scene = renderer.get_scene();
                             // loading splash screen hack
-                            scene.ui.set_root(Box::new(display::component::Hotbar::new()));
+                            if dead {
+                                scene.ui.set_root(Box::new(display::component::DeathScreen::new()));
+                            } else {
+                                scene.ui.set_root(Box::new(display::component::Hud::new(hotbar.clone(), target_frame.clone())));
+                            }
                         }
                     }
                 },
@@ -259,6 +410,10 @@ pub fn run(
         if *control_flow == winit::event_loop::ControlFlow::Exit && renderer.is_some() {
             log::info!("Exiting...");
             renderer.take();
+
+            if let Some(ref settings) = settings {
+                settings.save(&config);
+            }
         }
     });
 }
@@ -317,6 +472,15 @@ fn model_loaded(renderer: &mut Renderer, data: iohandler::ModelLoaded) {
     }
 }
 
+fn water_loaded<B: rendy::hal::Backend>(scene: &mut Scene<B>, path: &str, mesh: display::Mesh) {
+    for model in &mut scene.models {
+        if model.path == path {
+            model.add_mesh(nalgebra::Point3::new(0.0, 0.0, 0.0), mesh);
+            return;
+        }
+    }
+}
+
 fn terrain_loaded(renderer: &mut Renderer, data: iohandler::TerrainLoaded) {
     for model in &mut renderer.get_scene().models {
         if model.path == data.path {
@@ -5,6 +5,15 @@ pub enum InputTypes {
     MoveLeft,
     MoveRight,
     MoveUp,
+    Sprint,
+    Crouch,
+    Respawn,
+    /**
+     * Uses the ability in the given hotbar slot
+     */
+    Ability(usize),
+    TargetNext,
+    ClearTarget,
 }
 
 #[derive(serde::Serialize, serde::Deserialize)]
@@ -15,6 +24,15 @@ pub struct KeyMapConfig {
     pub move_left: u32,
     pub move_right: u32,
     pub move_up: u32,
+    pub sprint: u32,
+    pub crouch: u32,
+    pub respawn: u32,
+    /**
+     * Keys for the hotbar slots, in order
+     */
+    pub abilities: Vec<u32>,
+    pub target_next: u32,
+    pub clear_target: u32,
 }
 
 impl Default for KeyMapConfig {
@@ -25,6 +43,12 @@ impl Default for KeyMapConfig {
             move_left: 30,
             move_right: 32,
             move_up: 57,
+            sprint: 42,
+            crouch: 29,
+            respawn: 19,
+            abilities: vec![2, 3, 4, 5, 6, 7, 8, 9, 10, 11],
+            target_next: 15,
+            clear_target: 1,
         }
     }
 }
\ No newline at end of file
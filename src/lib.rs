@@ -12,44 +12,88 @@ mod client;
 use failure::Error;
 use failure::format_err;
 
-use eternalreckoning_core::util::config::Config;
 use eternalreckoning_core::util::logging;
 
+use util::configloader::{
+    ConfigLoader,
+    LoadedConfig,
+};
+
 pub struct Bootstrap {
     pub args: Vec<String>,
     pub config: Option<String>,
+    pub user_config: Option<String>,
+}
+
+struct Arguments {
+    overrides: Vec<String>,
+    dump_config: bool,
 }
 
 pub fn run(bootstrap: Bootstrap) -> Result<(), Error> {
-    let config = initialize(bootstrap)?;
+    let args = parse_args(&bootstrap.args)?;
+    let config = get_configuration(&bootstrap, &args)?;
+
+    if args.dump_config {
+        print!("{}", config.dump()?);
+        return Ok(());
+    }
+
+    let config = initialize(config)?;
 
     client::main(config)?;
 
     Ok(())
 }
 
-fn initialize(bootstrap: Bootstrap)
-    -> Result<util::config::Config, Error>
+fn initialize(config: LoadedConfig)
+    -> Result<LoadedConfig, Error>
 {
-    let config = get_configuration(bootstrap)?;
-    let config = config.data;
-
-    logging::configure(&config.logging, "eternalreckoning_client")?;
+    logging::configure(&config.data.logging, "eternalreckoning_client")?;
 
     Ok(config)
 }
 
-fn get_configuration(bootstrap: Bootstrap)
-    -> Result<Config<util::config::Config>, Error>
+fn get_configuration(bootstrap: &Bootstrap, args: &Arguments)
+    -> Result<LoadedConfig, Error>
 {
+    let mut loader = ConfigLoader::new();
+
     match bootstrap.config {
-        Some(path) => {
-            Config::<util::config::Config>::from_file(&path)
-                .or_else(|_| {
-                    Config::<util::config::Config>::write_default(&path)
-                })
-                .map_err(|e| { e.into() })
-        },
-        None => Err(format_err!("no configuration file path provided")),
+        Some(ref path) => loader = loader.with_file(path),
+        None => return Err(format_err!("no configuration file path provided")),
+    };
+    if let Some(ref path) = bootstrap.user_config {
+        loader = loader.with_user_file(path);
     }
+
+    loader = loader.with_env("ER_");
+    for key_value in &args.overrides {
+        loader = loader.with_override(key_value);
+    }
+
+    loader.load()
+}
+
+fn parse_args(args: &[String]) -> Result<Arguments, Error> {
+    let mut parsed = Arguments {
+        overrides: Vec::new(),
+        dump_config: false,
+    };
+
+    let mut args = args.iter().skip(1);
+    while let Some(arg) = args.next() {
+        match &arg[..] {
+            "--set" | "-s" => {
+                match args.next() {
+                    Some(key_value) => parsed.overrides.push(key_value.clone()),
+                    None => return Err(format_err!("{} requires a key=value argument", arg)),
+                }
+            },
+            "--dump-config" => parsed.dump_config = true,
+            _ => return Err(format_err!("unrecognized argument: {}", arg)),
+        }
+    }
+
+    Ok(parsed)
 }
\ No newline at end of file
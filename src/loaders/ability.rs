@@ -0,0 +1,35 @@
+use failure::{
+    Error,
+    format_err,
+};
+
+use crate::simulation::resource::AbilityRegistry;
+
+pub fn abilities_from_toml(path: &str) -> Result<AbilityRegistry, Error> {
+    let contents = std::fs::read_to_string(path)
+        .map_err(|_| format_err!("cannot load abilities: {}", path))?;
+
+    let registry: AbilityRegistry = toml::from_str(&contents)
+        .map_err(|e| format_err!("malformed abilities {}: {}", path, e))?;
+
+    for (id, ability) in &registry.abilities {
+        let times = [ability.cooldown, ability.cast_time];
+        if times.iter().any(|time| !time.is_finite() || *time < 0.0) {
+            return Err(format_err!("ability {} in {} has a negative or non-finite duration", id, path));
+        }
+        match ability.range {
+            Some(range) if !range.is_finite() || range < 0.0 => {
+                return Err(format_err!("ability {} in {} has an invalid range: {}", id, path, range));
+            },
+            _ => (),
+        };
+        if let Some(ref projectile) = ability.projectile {
+            let values = [projectile.speed, projectile.gravity, projectile.radius, projectile.lifetime];
+            if values.iter().any(|value| !value.is_finite() || *value < 0.0) {
+                return Err(format_err!("ability {} in {} has an invalid projectile", id, path));
+            }
+        }
+    }
+
+    Ok(registry)
+}
@@ -7,6 +7,10 @@ use crate::display::{
     Mesh,
     mesh::MeshBuilder,
 };
+use crate::simulation::collision::{
+    Triangle,
+    TriMesh,
+};
 
 #[repr(C)]
 struct Header {
@@ -50,7 +54,66 @@ struct UV {
 #[repr(C)]
 struct Index(u64);
 
+/**
+ * Raw contents of a single object in an ERM file
+ */
+struct ErmObject {
+    vertices: Vec<[f64; 3]>,
+    colors: Vec<rendy::mesh::Color>,
+    indices: Vec<u32>,
+    uvs: Vec<rendy::mesh::TexCoord>,
+}
+
 pub fn meshes_from_erm(path: &str) -> Result<Vec<Mesh>, failure::Error> {
+    let mut meshes = Vec::new();
+    let mut index_offset = 0;
+    for object in objects_from_erm(path)? {
+        let mesh = mesh_from_object(object, index_offset)?;
+        index_offset += mesh.len();
+        meshes.push(mesh);
+    }
+
+    Ok(meshes)
+}
+
+/**
+ * Collision geometry for the same model that 'meshes_from_erm' loads for
+ * rendering, 'offset' matches the model offset used when rendering it
+ */
+pub fn trimesh_from_erm(path: &str, offset: &nalgebra::Vector3<f64>)
+    -> Result<TriMesh, failure::Error>
+{
+    let mut triangles = Vec::new();
+    // indices count vertices across all objects in the file
+    let mut index_offset = 0;
+    for object in objects_from_erm(path)? {
+        for face in object.indices.chunks(3) {
+            if face.len() < 3 {
+                break;
+            }
+
+            let mut corners = [nalgebra::Point3::origin(); 3];
+            for (corner, index) in corners.iter_mut().zip(face) {
+                let vertex = (*index as usize).checked_sub(index_offset)
+                    .and_then(|index| object.vertices.get(index))
+                    .ok_or_else(|| format_err!("invalid vertex index in model: {}", path))?;
+                *corner = nalgebra::Point3::from(*vertex) + offset;
+            }
+
+            triangles.push(Triangle::new(corners[0], corners[1], corners[2]));
+        }
+        index_offset += object.vertices.len();
+    }
+
+    let mesh = TriMesh::new(triangles);
+    if mesh.is_empty() {
+        return Err(format_err!("model has no triangles: {}", path));
+    }
+
+    Ok(mesh)
+}
+
+fn objects_from_erm(path: &str) -> Result<Vec<ErmObject>, failure::Error> {
     let mut reader = std::io::BufReader::new(
         std::fs::File::open(path)
             .map_err(|_| format_err!("cannot load model: {}", path))?
@@ -67,22 +130,17 @@ pub fn meshes_from_erm(path: &str) -> Result<Vec<Mesh>, failure::Error> {
         reader.read_exact(header_slice)?;
     }
 
-    let mut meshes = Vec::new();
-    let mut index_offset = 0;
+    let mut objects = Vec::new();
     for _object_index in 0..header.object_count {
-        let mesh = mesh_from_erm(&mut reader, index_offset)?;
-        index_offset += mesh.len();
-        meshes.push(mesh);
+        objects.push(object_from_erm(&mut reader)?);
     }
 
-    Ok(meshes)
+    Ok(objects)
 }
 
-fn mesh_from_erm(reader: &mut std::io::BufReader<std::fs::File>, index_offset: u32)
-    -> Result<Mesh, failure::Error>
+fn object_from_erm(reader: &mut std::io::BufReader<std::fs::File>)
+    -> Result<ErmObject, failure::Error>
 {
-    let mut mesh_builder = MeshBuilder::new();
-
     let mut object_header: ObjectHeader = unsafe { std::mem::zeroed() };
     let object_header_size = std::mem::size_of::<ObjectHeader>();
 
@@ -117,11 +175,7 @@ fn mesh_from_erm(reader: &mut std::io::BufReader<std::fs::File>, index_offset: u
             reader.read_exact(vertex_slice)?;
         }
 
-        vertices.push([
-            vertex.x as f32,
-            vertex.y as f32,
-            vertex.z as f32,
-        ].into());
+        vertices.push([vertex.x, vertex.y, vertex.z]);
     }
 
     if object_header.flags.contains(ObjectFlags::HAS_COLORS) {
@@ -173,12 +227,25 @@ fn mesh_from_erm(reader: &mut std::io::BufReader<std::fs::File>, index_offset: u
         uvs.push(texcoord);
     }
 
-    if object_header.flags.contains(ObjectFlags::HAS_COLORS) {
-        let mut reorder_colors = Vec::with_capacity(object_header.vertex_count as usize);
-        for index in 0..(object_header.vertex_count as u32) {
+    Ok(ErmObject { vertices, colors, indices, uvs })
+}
+
+fn mesh_from_object(object: ErmObject, index_offset: u32)
+    -> Result<Mesh, failure::Error>
+{
+    let mut mesh_builder = MeshBuilder::new();
+
+    let vertices: Vec<rendy::mesh::Position> = object.vertices.iter()
+        .map(|vertex| [vertex[0] as f32, vertex[1] as f32, vertex[2] as f32].into())
+        .collect();
+    let indices = object.indices;
+
+    if !object.colors.is_empty() {
+        let mut reorder_colors = Vec::with_capacity(vertices.len());
+        for index in 0..(vertices.len() as u32) {
             for index_index in 0..indices.len() {
                 if *indices.get(index_index).unwrap() == index + index_offset {
-                    reorder_colors.push(colors.get(index_index).unwrap().clone());
+                    reorder_colors.push(object.colors.get(index_index).unwrap().clone());
                     break;
                 }
             }
@@ -191,7 +258,7 @@ fn mesh_from_erm(reader: &mut std::io::BufReader<std::fs::File>, index_offset: u
     
     mesh_builder = mesh_builder.with_vertices(&vertices);
     mesh_builder = mesh_builder.with_indices(&indices);
-    mesh_builder = mesh_builder.with_uvs(&uvs);
+    mesh_builder = mesh_builder.with_uvs(&object.uvs);
 
     Ok(mesh_builder.build()?)
 }
@@ -208,4 +275,51 @@ fn unroll_verts(
         *vert_index = index as u32;
     }
     (reorder_verts, indices)
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn write_erm(path: &std::path::Path, objects: &[(Vec<[f64; 3]>, Vec<u64>)]) {
+        let mut data = Vec::new();
+        data.extend_from_slice(&(objects.len() as u64).to_ne_bytes());
+        for (vertices, indices) in objects {
+            data.extend_from_slice(&(vertices.len() as u64).to_ne_bytes());
+            data.extend_from_slice(&(indices.len() as u64).to_ne_bytes());
+            data.extend_from_slice(&0u64.to_ne_bytes());
+            for value in vertices.iter().flatten() {
+                data.extend_from_slice(&value.to_ne_bytes());
+            }
+            for index in indices {
+                data.extend_from_slice(&index.to_ne_bytes());
+            }
+            for _ in 0..indices.len() * 2 {
+                data.extend_from_slice(&0.0f64.to_ne_bytes());
+            }
+        }
+        std::fs::write(path, data).unwrap();
+    }
+
+    #[test]
+    fn test_trimesh_from_objects() {
+        let path = std::env::temp_dir().join(format!(
+            "er-client-objects-{}.erm",
+            std::process::id()
+        ));
+        write_erm(&path, &[
+            (vec![[0.0, 0.0, 0.0], [1.0, 0.0, 0.0], [0.0, 0.0, 1.0]], vec![0, 1, 2]),
+            (vec![[10.0, 0.0, 0.0], [11.0, 0.0, 0.0], [10.0, -2.0, 0.0]], vec![3, 4, 5]),
+        ]);
+
+        let mesh = trimesh_from_erm(path.to_str().unwrap(), &nalgebra::Vector3::new(0.0, 1.0, 0.0));
+        std::fs::remove_file(&path).unwrap();
+
+        let mesh = mesh.unwrap();
+        assert_eq!(mesh.len(), 2);
+
+        let bounds = mesh.bounds().unwrap();
+        assert_eq!(bounds.min, nalgebra::Point3::new(0.0, -1.0, 0.0));
+        assert_eq!(bounds.max, nalgebra::Point3::new(11.0, 1.0, 1.0));
+    }
+}
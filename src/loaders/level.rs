@@ -0,0 +1,14 @@
+use failure::{
+    Error,
+    format_err,
+};
+
+use crate::simulation::level::Level;
+
+pub fn level_from_toml(path: &str) -> Result<Level, Error> {
+    let contents = std::fs::read_to_string(path)
+        .map_err(|_| format_err!("cannot load level: {}", path))?;
+
+    toml::from_str(&contents)
+        .map_err(|e| format_err!("malformed level {}: {}", path, e))
+}
\ No newline at end of file
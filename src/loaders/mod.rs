@@ -1,8 +1,17 @@
+mod ability;
 mod erm;
 mod heightmap;
+mod level;
+mod prefab;
 
-pub use erm::meshes_from_erm;
+pub use ability::abilities_from_toml;
+pub use erm::{
+    meshes_from_erm,
+    trimesh_from_erm,
+};
 pub use heightmap::{
     mesh_from_bmp,
     heightmap_from_bmp,
-};
\ No newline at end of file
+};
+pub use level::level_from_toml;
+pub use prefab::prefab_from_toml;
\ No newline at end of file
@@ -0,0 +1,22 @@
+use failure::{
+    Error,
+    format_err,
+};
+
+use crate::simulation::resource::{
+    PrefabData,
+    ServerPrefab,
+};
+
+pub fn prefab_from_toml(path: &str) -> Result<ServerPrefab, Error> {
+    let contents = std::fs::read_to_string(path)
+        .map_err(|_| format_err!("cannot load prefab: {}", path))?;
+
+    let data: PrefabData = toml::from_str(&contents)
+        .map_err(|e| format_err!("malformed prefab {}: {}", path, e))?;
+
+    let prefab = data.build()
+        .map_err(|e| format_err!("invalid prefab {}: {}", path, e))?;
+
+    Ok(ServerPrefab(Some(prefab)))
+}
@@ -7,6 +7,12 @@ fn main() {
     let bootstrap = eternalreckoning_client::Bootstrap {
         args: args,
         config: Some("config/client.toml".to_string()),
+        user_config: dirs::config_dir().map(|dir| {
+            dir.join("eternalreckoning")
+                .join("client.toml")
+                .to_string_lossy()
+                .into_owned()
+        }),
     };
 
     if let Err(ref e) = eternalreckoning_client::run(bootstrap) {
@@ -227,6 +227,22 @@ impl Future for WriteConnection {
                                         }
                                     ))?;
                                 },
+                                // the protocol has no respawn operation, the
+                                // server is sent the spawn position directly
+                                simulation::event::Update::RespawnUpdate(data) => {
+                                    self.send(Operation::ClMoveSetPosition(
+                                        operation::ClMoveSetPosition {
+                                            pos: data.position,
+                                        }
+                                    ))?;
+                                },
+                                // abilities have no operation either, the
+                                // requests are resent on the client's side
+                                // and dropped here until the protocol
+                                // carries them
+                                simulation::event::Update::AbilityRequest(data) => {
+                                    log::debug!("Ability requests are not supported by the server: {}", data.ability);
+                                },
                                 _ => (),
                             }
                         },
@@ -0,0 +1,105 @@
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Aabb {
+    pub min: nalgebra::Point3<f64>,
+    pub max: nalgebra::Point3<f64>,
+}
+
+impl Aabb {
+    pub fn new(min: nalgebra::Point3<f64>, max: nalgebra::Point3<f64>) -> Aabb {
+        Aabb { min, max }
+    }
+
+    pub fn from_center(center: &nalgebra::Point3<f64>, half_extents: &nalgebra::Vector3<f64>) -> Aabb {
+        Aabb {
+            min: center - half_extents,
+            max: center + half_extents,
+        }
+    }
+
+    pub fn infinite() -> Aabb {
+        Aabb {
+            min: nalgebra::Point3::new(
+                f64::NEG_INFINITY,
+                f64::NEG_INFINITY,
+                f64::NEG_INFINITY,
+            ),
+            max: nalgebra::Point3::new(
+                f64::INFINITY,
+                f64::INFINITY,
+                f64::INFINITY,
+            ),
+        }
+    }
+
+    pub fn is_finite(&self) -> bool {
+        self.min.iter().chain(self.max.iter()).all(|value| value.is_finite())
+    }
+
+    pub fn intersects(&self, other: &Aabb) -> bool {
+        self.min.x <= other.max.x && self.max.x >= other.min.x &&
+        self.min.y <= other.max.y && self.max.y >= other.min.y &&
+        self.min.z <= other.max.z && self.max.z >= other.min.z
+    }
+
+    pub fn contains(&self, point: &nalgebra::Point3<f64>) -> bool {
+        point.x >= self.min.x && point.x <= self.max.x &&
+        point.y >= self.min.y && point.y <= self.max.y &&
+        point.z >= self.min.z && point.z <= self.max.z
+    }
+
+    pub fn merged(&self, other: &Aabb) -> Aabb {
+        Aabb {
+            min: nalgebra::Point3::new(
+                self.min.x.min(other.min.x),
+                self.min.y.min(other.min.y),
+                self.min.z.min(other.min.z),
+            ),
+            max: nalgebra::Point3::new(
+                self.max.x.max(other.max.x),
+                self.max.y.max(other.max.y),
+                self.max.z.max(other.max.z),
+            ),
+        }
+    }
+
+    pub fn expanded(&self, margin: f64) -> Aabb {
+        let margin = nalgebra::Vector3::new(margin, margin, margin);
+        Aabb {
+            min: self.min - margin,
+            max: self.max + margin,
+        }
+    }
+
+    /**
+     * Range of distances along the ray from 'origin' in 'direction' that
+     * are within the bounds, the first may be negative if 'origin' is inside
+     */
+    pub fn intersect_ray(
+        &self,
+        origin: &nalgebra::Point3<f64>,
+        direction: &nalgebra::Vector3<f64>,
+    ) -> Option<(f64, f64)>
+    {
+        let mut t_min = f64::NEG_INFINITY;
+        let mut t_max = f64::INFINITY;
+
+        for axis in 0..3 {
+            if direction[axis].abs() < f64::EPSILON {
+                if origin[axis] < self.min[axis] || origin[axis] > self.max[axis] {
+                    return None;
+                }
+                continue;
+            }
+
+            let t1 = (self.min[axis] - origin[axis]) / direction[axis];
+            let t2 = (self.max[axis] - origin[axis]) / direction[axis];
+            t_min = t_min.max(t1.min(t2));
+            t_max = t_max.min(t1.max(t2));
+            if t_min > t_max {
+                return None;
+            }
+        }
+
+        Some((t_min, t_max))
+    }
+}
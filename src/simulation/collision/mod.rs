@@ -0,0 +1,17 @@
+mod aabb;
+mod raycast;
+mod spatialhash;
+mod triangle;
+mod trimesh;
+
+pub use aabb::Aabb;
+pub use raycast::{
+    cast_sphere,
+    CastHit,
+};
+pub use spatialhash::{
+    brute_force_pairs,
+    SpatialHash,
+};
+pub use triangle::Triangle;
+pub use trimesh::TriMesh;
\ No newline at end of file
@@ -0,0 +1,508 @@
+use std::collections::HashSet;
+
+use crate::display::terrain::HeightMap;
+use crate::simulation::component::collider::ColliderType;
+
+use super::{
+    Aabb,
+    Triangle,
+    TriMesh,
+};
+
+/**
+ * Distance travelled along the cast and the surface normal at the hit, which
+ * faces back towards the caster. Casts starting inside a shape hit at zero
+ * distance with the normal facing straight back along the cast.
+ */
+pub type CastHit = (f64, nalgebra::Unit<nalgebra::Vector3<f64>>);
+
+/**
+ * Sweeps a sphere of 'radius' from 'origin' along the unit vector
+ * 'direction' against a collider at 'position', a radius of zero casts a
+ * ray. Hits further than 'max_distance' are ignored.
+ */
+pub fn cast_sphere(
+    collider: &ColliderType,
+    position: &nalgebra::Point3<f64>,
+    origin: &nalgebra::Point3<f64>,
+    direction: &nalgebra::Unit<nalgebra::Vector3<f64>>,
+    radius: f64,
+    max_distance: f64,
+) -> Option<CastHit>
+{
+    let hit = match collider {
+        ColliderType::Sphere(sphere_radius) => {
+            ray_sphere(origin, direction, position, sphere_radius + radius)
+        },
+        ColliderType::Plane(normal) => {
+            sweep_plane(origin, direction, radius, position, normal)
+        },
+        ColliderType::Aabb(half_extents) => {
+            sweep_aabb(origin, direction, radius, &Aabb::from_center(position, half_extents))
+        },
+        ColliderType::Capsule { radius: capsule_radius, half_height } => {
+            let half_height = nalgebra::Vector3::new(0.0, *half_height, 0.0);
+            ray_capsule(
+                origin, direction,
+                &(position - half_height), &(position + half_height),
+                capsule_radius + radius,
+            )
+        },
+        ColliderType::HeightMap(map) => {
+            // cast in map space
+            let origin = origin - position.coords;
+            sweep_heightmap(&origin, direction, radius, max_distance, map)
+        },
+        ColliderType::TriMesh(mesh) => {
+            let origin = origin - position.coords;
+            sweep_trimesh(&origin, direction, radius, max_distance, mesh)
+        },
+    };
+
+    hit.filter(|(distance, _)| *distance <= max_distance)
+}
+
+fn starts_inside(direction: &nalgebra::Unit<nalgebra::Vector3<f64>>) -> Option<CastHit> {
+    Some((0.0, nalgebra::Unit::new_unchecked(-direction.as_ref())))
+}
+
+fn ray_sphere(
+    origin: &nalgebra::Point3<f64>,
+    direction: &nalgebra::Unit<nalgebra::Vector3<f64>>,
+    center: &nalgebra::Point3<f64>,
+    radius: f64,
+) -> Option<CastHit>
+{
+    let offset = origin - center;
+    let c = offset.norm_squared() - radius * radius;
+    if c <= 0.0 {
+        return starts_inside(direction);
+    }
+
+    let b = offset.dot(direction);
+    if b > 0.0 {
+        return None;
+    }
+
+    let discriminant = b * b - c;
+    if discriminant < 0.0 {
+        return None;
+    }
+
+    let distance = -b - discriminant.sqrt();
+    let normal = nalgebra::Unit::try_new(
+        origin + direction.as_ref() * distance - center,
+        f64::EPSILON,
+    )?;
+
+    Some((distance, normal))
+}
+
+/**
+ * Capsule around the segment from 'start' to 'end', in any orientation
+ */
+fn ray_capsule(
+    origin: &nalgebra::Point3<f64>,
+    direction: &nalgebra::Unit<nalgebra::Vector3<f64>>,
+    start: &nalgebra::Point3<f64>,
+    end: &nalgebra::Point3<f64>,
+    radius: f64,
+) -> Option<CastHit>
+{
+    let axis = end - start;
+    let length_sq = axis.norm_squared();
+    if length_sq < f64::EPSILON {
+        return ray_sphere(origin, direction, start, radius);
+    }
+
+    let offset = origin - start;
+    let along = (offset.dot(&axis) / length_sq).clamp(0.0, 1.0);
+    if nalgebra::distance_squared(origin, &(start + axis * along)) <= radius * radius {
+        return starts_inside(direction);
+    }
+
+    // the cylinder around the segment, with both parts projected onto the
+    // plane perpendicular to it
+    let mut best: Option<CastHit> = None;
+    let direction_perp = direction.as_ref() - axis * (direction.dot(&axis) / length_sq);
+    let offset_perp = offset - axis * (offset.dot(&axis) / length_sq);
+    let a = direction_perp.norm_squared();
+    if a > f64::EPSILON {
+        let b = offset_perp.dot(&direction_perp);
+        let c = offset_perp.norm_squared() - radius * radius;
+        let discriminant = b * b - a * c;
+        if discriminant >= 0.0 {
+            let distance = (-b - discriminant.sqrt()) / a;
+            let point = origin + direction.as_ref() * distance;
+            let along = (point - start).dot(&axis) / length_sq;
+            if distance >= 0.0 && (0.0..=1.0).contains(&along) {
+                if let Some(normal) = nalgebra::Unit::try_new(
+                    point - (start + axis * along),
+                    f64::EPSILON,
+                ) {
+                    best = Some((distance, normal));
+                }
+            }
+        }
+    }
+
+    for cap in &[start, end] {
+        if let Some(hit) = ray_sphere(origin, direction, cap, radius) {
+            best = nearest(best, hit);
+        }
+    }
+
+    best
+}
+
+fn sweep_plane(
+    origin: &nalgebra::Point3<f64>,
+    direction: &nalgebra::Unit<nalgebra::Vector3<f64>>,
+    radius: f64,
+    position: &nalgebra::Point3<f64>,
+    normal: &nalgebra::Unit<nalgebra::Vector3<f64>>,
+) -> Option<CastHit>
+{
+    // planes are solid behind their normal
+    let distance = (origin - position).dot(normal);
+    if distance <= radius {
+        return starts_inside(direction);
+    }
+
+    let approach = -direction.dot(normal);
+    if approach <= 0.0 {
+        return None;
+    }
+
+    Some(((distance - radius) / approach, *normal))
+}
+
+fn sweep_aabb(
+    origin: &nalgebra::Point3<f64>,
+    direction: &nalgebra::Unit<nalgebra::Vector3<f64>>,
+    radius: f64,
+    bounds: &Aabb,
+) -> Option<CastHit>
+{
+    let expanded = bounds.expanded(radius);
+    let (enter, exit) = expanded.intersect_ray(origin, direction)?;
+    if exit < 0.0 {
+        return None;
+    }
+
+    let closest = nalgebra::Point3::new(
+        origin.x.max(bounds.min.x).min(bounds.max.x),
+        origin.y.max(bounds.min.y).min(bounds.max.y),
+        origin.z.max(bounds.min.z).min(bounds.max.z),
+    );
+    if nalgebra::distance_squared(origin, &closest) <= radius * radius {
+        return starts_inside(direction);
+    }
+
+    if enter >= 0.0 {
+        // the face entered through is on the axis entered last
+        let mut face = 0;
+        let mut face_enter = f64::NEG_INFINITY;
+        for axis in 0..3 {
+            let d = direction[axis];
+            if d != 0.0 {
+                let side = if d > 0.0 { expanded.min[axis] } else { expanded.max[axis] };
+                let axis_enter = (side - origin[axis]) / d;
+                if axis_enter > face_enter {
+                    face = axis;
+                    face_enter = axis_enter;
+                }
+            }
+        }
+
+        // through the flat part of the face, or anywhere on the box when
+        // casting a ray
+        let point = origin + direction.as_ref() * enter;
+        let on_face = (0..3)
+            .filter(|&axis| axis != face)
+            .all(|axis| point[axis] >= bounds.min[axis] && point[axis] <= bounds.max[axis]);
+
+        if on_face || radius <= 0.0 {
+            let mut normal = nalgebra::Vector3::zeros();
+            normal[face] = if direction[face] > 0.0 { -1.0 } else { 1.0 };
+            return Some((enter, nalgebra::Unit::new_unchecked(normal)));
+        }
+    }
+
+    // otherwise it can only hit the rounded edges and corners
+    let corner = |x: usize, y: usize, z: usize| {
+        nalgebra::Point3::new(
+            if x == 0 { bounds.min.x } else { bounds.max.x },
+            if y == 0 { bounds.min.y } else { bounds.max.y },
+            if z == 0 { bounds.min.z } else { bounds.max.z },
+        )
+    };
+
+    let mut best = None;
+    for i in 0..2 {
+        for j in 0..2 {
+            let edges = [
+                (corner(0, i, j), corner(1, i, j)),
+                (corner(i, 0, j), corner(i, 1, j)),
+                (corner(i, j, 0), corner(i, j, 1)),
+            ];
+            for (start, end) in &edges {
+                if let Some(hit) = ray_capsule(origin, direction, start, end, radius) {
+                    best = nearest(best, hit);
+                }
+            }
+        }
+    }
+
+    best
+}
+
+fn sweep_triangle(
+    origin: &nalgebra::Point3<f64>,
+    direction: &nalgebra::Unit<nalgebra::Vector3<f64>>,
+    radius: f64,
+    triangle: &Triangle,
+) -> Option<CastHit>
+{
+    let normal = triangle.normal()?;
+    let distance = (origin - triangle.a).dot(&normal);
+
+    // triangles are hit from either side
+    let (normal, distance) = if distance < 0.0 {
+        (nalgebra::Unit::new_unchecked(-normal.as_ref()), -distance)
+    } else {
+        (normal, distance)
+    };
+
+    let is_on_face = |point: &nalgebra::Point3<f64>| {
+        nalgebra::distance_squared(point, &triangle.closest_point(point)) <= 1e-12
+    };
+
+    let mut best = None;
+    if distance <= radius {
+        if is_on_face(&(origin - normal.as_ref() * distance)) {
+            return starts_inside(direction);
+        }
+    } else {
+        let approach = -direction.dot(&normal);
+        if approach > 0.0 {
+            let travel = (distance - radius) / approach;
+            let contact = origin + direction.as_ref() * travel - normal.as_ref() * radius;
+            if is_on_face(&contact) {
+                best = Some((travel, normal));
+            }
+        }
+    }
+
+    if radius > 0.0 {
+        for (start, end) in &[(triangle.a, triangle.b), (triangle.b, triangle.c), (triangle.c, triangle.a)] {
+            if let Some(hit) = ray_capsule(origin, direction, start, end, radius) {
+                best = nearest(best, hit);
+            }
+        }
+    }
+
+    best
+}
+
+fn sweep_trimesh(
+    origin: &nalgebra::Point3<f64>,
+    direction: &nalgebra::Unit<nalgebra::Vector3<f64>>,
+    radius: f64,
+    max_distance: f64,
+    mesh: &TriMesh,
+) -> Option<CastHit>
+{
+    let mut best = None;
+    mesh.query_ray(origin, direction, radius, max_distance, |triangle| {
+        let hit = sweep_triangle(origin, direction, radius, triangle)?;
+        best = nearest(best, hit);
+        Some(hit.0)
+    });
+
+    best
+}
+
+/**
+ * Walks the grid cells under the cast, testing the triangles of each cell
+ * and those within 'radius' of it
+ */
+fn sweep_heightmap(
+    origin: &nalgebra::Point3<f64>,
+    direction: &nalgebra::Unit<nalgebra::Vector3<f64>>,
+    radius: f64,
+    max_distance: f64,
+    map: &HeightMap,
+) -> Option<CastHit>
+{
+    if map.size < 2 {
+        return None;
+    }
+
+    let last_cell = map.size as i64 - 2;
+    let extent = (map.size - 1) as f64;
+    let (min, max) = map.height_range();
+    let footprint = Aabb::new(
+        nalgebra::Point3::new(0.0, -max as f64, 0.0),
+        nalgebra::Point3::new(extent, -min as f64, extent),
+    );
+
+    let (enter, exit) = footprint.expanded(radius).intersect_ray(origin, direction)?;
+    let mut t = enter.max(0.0);
+    let end = exit.min(max_distance);
+    if t > end {
+        return None;
+    }
+
+    let reach = radius.ceil() as i64;
+    let start = origin + direction.as_ref() * t;
+    let mut cell = [start.x.floor() as i64, start.z.floor() as i64];
+    let mut step = [0i64; 2];
+    let mut t_next = [f64::INFINITY; 2];
+    let mut t_delta = [f64::INFINITY; 2];
+    for (i, axis) in [0, 2].iter().enumerate() {
+        let d = direction[*axis];
+        if d > 0.0 {
+            step[i] = 1;
+            t_next[i] = t + ((cell[i] + 1) as f64 - start[*axis]) / d;
+            t_delta[i] = 1.0 / d;
+        } else if d < 0.0 {
+            step[i] = -1;
+            t_next[i] = t + (cell[i] as f64 - start[*axis]) / d;
+            t_delta[i] = -1.0 / d;
+        }
+    }
+
+    let mut best: Option<CastHit> = None;
+    let mut visited = HashSet::new();
+    loop {
+        if t > end || matches!(best, Some((distance, _)) if distance < t) {
+            break;
+        }
+
+        for z in (cell[1] - reach)..=(cell[1] + reach) {
+            for x in (cell[0] - reach)..=(cell[0] + reach) {
+                if x < 0 || z < 0 || x > last_cell || z > last_cell || !visited.insert((x, z)) {
+                    continue;
+                }
+                for triangle in &Triangle::from_heightmap_cell(map, x as usize, z as usize) {
+                    if let Some(hit) = sweep_triangle(origin, direction, radius, triangle) {
+                        best = nearest(best, hit);
+                    }
+                }
+            }
+        }
+
+        let axis = if t_next[0] <= t_next[1] { 0 } else { 1 };
+        if t_next[axis].is_infinite() {
+            break;
+        }
+        t = t_next[axis];
+        cell[axis] += step[axis];
+        t_next[axis] += t_delta[axis];
+    }
+
+    best
+}
+
+fn nearest(best: Option<CastHit>, hit: CastHit) -> Option<CastHit> {
+    match best {
+        Some(ref current) if current.0 <= hit.0 => best,
+        _ => Some(hit),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn down() -> nalgebra::Unit<nalgebra::Vector3<f64>> {
+        nalgebra::Unit::new_normalize(nalgebra::Vector3::new(0.0, 1.0, 0.0))
+    }
+
+    fn assert_hit(hit: Option<CastHit>, distance: f64, normal: [f64; 3]) {
+        let (hit_distance, hit_normal) = hit.unwrap();
+        assert!((hit_distance - distance).abs() < 1e-9, "distance {} != {}", hit_distance, distance);
+        for axis in 0..3 {
+            assert!((hit_normal[axis] - normal[axis]).abs() < 1e-9, "normal {:?} != {:?}", hit_normal, normal);
+        }
+    }
+
+    #[test]
+    fn test_cast_sphere_shapes() {
+        let origin = nalgebra::Point3::new(0.0, -10.0, 0.0);
+        let position = nalgebra::Point3::new(0.0, 0.0, 0.0);
+
+        let sphere = ColliderType::Sphere(1.0);
+        assert_hit(cast_sphere(&sphere, &position, &origin, &down(), 0.0, 100.0), 9.0, [0.0, -1.0, 0.0]);
+        assert_hit(cast_sphere(&sphere, &position, &origin, &down(), 0.5, 100.0), 8.5, [0.0, -1.0, 0.0]);
+        assert!(cast_sphere(&sphere, &position, &origin, &down(), 0.0, 5.0).is_none());
+
+        let plane = ColliderType::Plane(nalgebra::Unit::new_normalize(
+            nalgebra::Vector3::new(0.0, -1.0, 0.0)
+        ));
+        assert_hit(cast_sphere(&plane, &position, &origin, &down(), 0.5, 100.0), 9.5, [0.0, -1.0, 0.0]);
+
+        let aabb = ColliderType::Aabb(nalgebra::Vector3::new(1.0, 2.0, 1.0));
+        assert_hit(cast_sphere(&aabb, &position, &origin, &down(), 0.0, 100.0), 8.0, [0.0, -1.0, 0.0]);
+        assert_hit(cast_sphere(&aabb, &position, &origin, &down(), 0.5, 100.0), 7.5, [0.0, -1.0, 0.0]);
+
+        let capsule = ColliderType::Capsule { radius: 0.5, half_height: 1.0 };
+        assert_hit(cast_sphere(&capsule, &position, &origin, &down(), 0.5, 100.0), 8.0, [0.0, -1.0, 0.0]);
+
+        // starting inside
+        assert_hit(cast_sphere(&sphere, &position, &position, &down(), 0.0, 100.0), 0.0, [0.0, -1.0, 0.0]);
+    }
+
+    #[test]
+    fn test_cast_sphere_rounded_corners() {
+        let position = nalgebra::Point3::new(0.0, 0.0, 0.0);
+        let aabb = ColliderType::Aabb(nalgebra::Vector3::new(1.0, 1.0, 1.0));
+
+        // passes the box's edge diagonally: the expanded box would be hit,
+        // but not the rounded edge
+        let origin = nalgebra::Point3::new(1.45, -10.0, 1.45);
+        assert!(cast_sphere(&aabb, &position, &origin, &down(), 0.5, 100.0).is_none());
+
+        // straight down onto the vertical edge's top corner
+        let origin = nalgebra::Point3::new(1.25, -10.0, 1.0);
+        let (distance, normal) = cast_sphere(&aabb, &position, &origin, &down(), 0.5, 100.0).unwrap();
+        assert!(distance > 8.5 && distance < 9.0);
+        assert!(normal.x > 0.0 && normal.y < 0.0);
+    }
+
+    #[test]
+    fn test_cast_sphere_terrain() {
+        let position = nalgebra::Point3::new(-2.0, 0.0, -2.0);
+        let heightmap = ColliderType::HeightMap(HeightMap::new(vec![
+            0.0, 0.0, 0.0, 0.0,
+            0.0, 0.0, 0.0, 0.0,
+            0.0, 0.0, 0.0, 0.0,
+            0.0, 0.0, 0.0, 2.0,
+        ], 4, 1.0));
+
+        let origin = nalgebra::Point3::new(-1.5, -10.0, -1.5);
+        assert_hit(cast_sphere(&heightmap, &position, &origin, &down(), 0.0, 100.0), 10.0, [0.0, -1.0, 0.0]);
+        assert_hit(cast_sphere(&heightmap, &position, &origin, &down(), 0.5, 100.0), 9.5, [0.0, -1.0, 0.0]);
+
+        // a shallow ray across the map hits the raised corner
+        let origin = nalgebra::Point3::new(-2.5, -1.5, -2.5);
+        let direction = nalgebra::Unit::new_normalize(nalgebra::Vector3::new(1.0, 0.0, 1.0));
+        let (distance, normal) = cast_sphere(&heightmap, &position, &origin, &direction, 0.0, 100.0).unwrap();
+        assert!(distance > 3.5 && distance < 4.95);
+        assert!(normal.y < 0.0);
+
+        assert!(cast_sphere(&heightmap, &position, &origin, &direction, 0.0, 3.0).is_none());
+
+        let mesh = ColliderType::TriMesh(TriMesh::new(vec![
+            Triangle::new(
+                nalgebra::Point3::new(0.0, 0.0, 0.0),
+                nalgebra::Point3::new(4.0, 0.0, 0.0),
+                nalgebra::Point3::new(0.0, 0.0, 4.0),
+            ),
+        ]));
+        let origin = nalgebra::Point3::new(-1.0, -10.0, -1.0);
+        assert_hit(cast_sphere(&mesh, &position, &origin, &down(), 0.0, 100.0), 10.0, [0.0, -1.0, 0.0]);
+        assert_hit(cast_sphere(&mesh, &position, &origin, &down(), 1.0, 100.0), 9.0, [0.0, -1.0, 0.0]);
+    }
+}
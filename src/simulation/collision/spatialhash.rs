@@ -0,0 +1,385 @@
+use std::collections::{
+    HashMap,
+    HashSet,
+};
+
+use super::Aabb;
+
+/**
+ * Bounds spanning more cells than this along any axis are not inserted into
+ * the grid, but tested against everything instead
+ */
+const MAX_CELL_SPAN: i64 = 8;
+
+type Cell = (i64, i64, i64);
+
+/**
+ * Uniform grid broadphase. Bounds are inserted into every cell they overlap
+ * and candidate pairs are produced from objects sharing a cell, each pair
+ * exactly once.
+ */
+pub struct SpatialHash {
+    cell_size: f64,
+    cells: HashMap<Cell, Vec<usize>>,
+    large: Vec<usize>,
+    bounds: Vec<Aabb>,
+    // bounds of everything in the grid
+    extent: Option<Aabb>,
+}
+
+impl SpatialHash {
+    pub fn new(cell_size: f64) -> SpatialHash {
+        assert!(cell_size > 0.0);
+
+        SpatialHash {
+            cell_size,
+            cells: HashMap::new(),
+            large: Vec::new(),
+            bounds: Vec::new(),
+            extent: None,
+        }
+    }
+
+    pub fn clear(&mut self) {
+        // keep the allocations of cells that were in use around for the
+        // next tick, but drop the ones left behind by moving objects
+        self.cells.retain(|_, cell| !cell.is_empty());
+        for cell in self.cells.values_mut() {
+            cell.clear();
+        }
+        self.large.clear();
+        self.bounds.clear();
+        self.extent = None;
+    }
+
+    pub fn cell_size(&self) -> f64 {
+        self.cell_size
+    }
+
+    pub fn len(&self) -> usize {
+        self.bounds.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.bounds.is_empty()
+    }
+
+    pub fn bounds(&self, index: usize) -> &Aabb {
+        &self.bounds[index]
+    }
+
+    /**
+     * Returns the index of the inserted bounds, indices are assigned in
+     * insertion order starting from zero
+     */
+    pub fn insert(&mut self, bounds: Aabb) -> usize {
+        let index = self.bounds.len();
+        self.bounds.push(bounds);
+
+        if !bounds.is_finite() {
+            self.large.push(index);
+            return index;
+        }
+
+        let min = self.cell(&bounds.min);
+        let max = self.cell(&bounds.max);
+        if max.0 - min.0 >= MAX_CELL_SPAN ||
+            max.1 - min.1 >= MAX_CELL_SPAN ||
+            max.2 - min.2 >= MAX_CELL_SPAN
+        {
+            self.large.push(index);
+            return index;
+        }
+
+        self.extent = Some(match self.extent {
+            Some(extent) => extent.merged(&bounds),
+            None => bounds,
+        });
+
+        for x in min.0..=max.0 {
+            for y in min.1..=max.1 {
+                for z in min.2..=max.2 {
+                    self.cells.entry((x, y, z))
+                        .or_default()
+                        .push(index);
+                }
+            }
+        }
+
+        index
+    }
+
+    /**
+     * Pairs of indices with overlapping bounds, each pair is listed once with
+     * the lower index first. Pairs are sorted, so that collisions are resolved
+     * in the same order from run to run.
+     */
+    pub fn pairs(&self) -> Vec<(usize, usize)> {
+        let mut pairs = Vec::new();
+
+        for (cell, indices) in &self.cells {
+            for (offset, &a) in indices.iter().enumerate() {
+                for &b in &indices[offset + 1..] {
+                    let (a_bounds, b_bounds) = (&self.bounds[a], &self.bounds[b]);
+                    if !a_bounds.intersects(b_bounds) {
+                        continue;
+                    }
+
+                    // only report the pair from the cell containing the
+                    // minimum corner of the overlap, so that pairs sharing
+                    // several cells are not reported more than once
+                    let overlap_min = nalgebra::Point3::new(
+                        a_bounds.min.x.max(b_bounds.min.x),
+                        a_bounds.min.y.max(b_bounds.min.y),
+                        a_bounds.min.z.max(b_bounds.min.z),
+                    );
+                    if self.cell(&overlap_min) != *cell {
+                        continue;
+                    }
+
+                    pairs.push((a.min(b), a.max(b)));
+                }
+            }
+        }
+
+        for &large in &self.large {
+            for index in 0..self.bounds.len() {
+                if index == large {
+                    continue;
+                }
+                // pairs of two large objects are reported by the lower index
+                if index < large && self.large.contains(&index) {
+                    continue;
+                }
+                if self.bounds[large].intersects(&self.bounds[index]) {
+                    pairs.push((large.min(index), large.max(index)));
+                }
+            }
+        }
+
+        pairs.sort_unstable();
+        pairs
+    }
+
+    /**
+     * Walks the cells along the ray from 'origin' along the unit vector
+     * 'direction', calling 'visit' once for every object in or within
+     * 'radius' of them, and for every large object. 'visit' returns the
+     * distance of a hit if any, and the walk ends once it has passed the
+     * nearest hit.
+     */
+    pub fn cast<F>(
+        &self,
+        origin: &nalgebra::Point3<f64>,
+        direction: &nalgebra::Vector3<f64>,
+        radius: f64,
+        max_distance: f64,
+        mut visit: F,
+    )
+    where
+        F: FnMut(usize) -> Option<f64>,
+    {
+        let mut best = max_distance;
+        for &index in &self.large {
+            if let Some(distance) = visit(index) {
+                best = best.min(distance);
+            }
+        }
+
+        // only walk the part of the ray that passes the grid's contents
+        let (enter, exit) = match self.extent {
+            Some(extent) => match extent.expanded(radius).intersect_ray(origin, direction) {
+                Some(interval) => interval,
+                None => return,
+            },
+            None => return,
+        };
+        let mut t = enter.max(0.0);
+        let end = exit.min(best);
+        if t > end {
+            return;
+        }
+
+        let reach = (radius / self.cell_size).ceil() as i64;
+        let start = origin + direction * t;
+        let mut cell = [0i64; 3];
+        let mut step = [0i64; 3];
+        let mut t_next = [f64::INFINITY; 3];
+        let mut t_delta = [f64::INFINITY; 3];
+        for axis in 0..3 {
+            cell[axis] = (start[axis] / self.cell_size).floor() as i64;
+            if direction[axis] > 0.0 {
+                step[axis] = 1;
+                t_next[axis] = t + ((cell[axis] + 1) as f64 * self.cell_size - start[axis]) / direction[axis];
+                t_delta[axis] = self.cell_size / direction[axis];
+            } else if direction[axis] < 0.0 {
+                step[axis] = -1;
+                t_next[axis] = t + (cell[axis] as f64 * self.cell_size - start[axis]) / direction[axis];
+                t_delta[axis] = -self.cell_size / direction[axis];
+            }
+        }
+
+        let mut visited = HashSet::new();
+        while t <= end.min(best) {
+            for x in -reach..=reach {
+                for y in -reach..=reach {
+                    for z in -reach..=reach {
+                        let key = (cell[0] + x, cell[1] + y, cell[2] + z);
+                        let indices = match self.cells.get(&key) {
+                            Some(indices) => indices,
+                            None => continue,
+                        };
+                        for &index in indices {
+                            if !visited.insert(index) {
+                                continue;
+                            }
+                            if let Some(distance) = visit(index) {
+                                best = best.min(distance);
+                            }
+                        }
+                    }
+                }
+            }
+
+            let axis = if t_next[0] <= t_next[1] && t_next[0] <= t_next[2] {
+                0
+            } else if t_next[1] <= t_next[2] {
+                1
+            } else {
+                2
+            };
+            if t_next[axis].is_infinite() {
+                break;
+            }
+            t = t_next[axis];
+            cell[axis] += step[axis];
+            t_next[axis] += t_delta[axis];
+        }
+    }
+
+    fn cell(&self, point: &nalgebra::Point3<f64>) -> Cell {
+        (
+            (point.x / self.cell_size).floor() as i64,
+            (point.y / self.cell_size).floor() as i64,
+            (point.z / self.cell_size).floor() as i64,
+        )
+    }
+}
+
+/**
+ * Reference all-pairs implementation, for testing
+ */
+pub fn brute_force_pairs(bounds: &[Aabb]) -> Vec<(usize, usize)> {
+    let mut pairs = Vec::new();
+
+    for a in 0..bounds.len() {
+        for b in (a + 1)..bounds.len() {
+            if bounds[a].intersects(&bounds[b]) {
+                pairs.push((a, b));
+            }
+        }
+    }
+
+    pairs
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn random_bounds(count: usize, seed: u64) -> Vec<Aabb> {
+        let mut state = seed;
+        let mut next = move || {
+            state = state.wrapping_mul(6364136223846793005).wrapping_add(1442695040888963407);
+            (state >> 33) as f64 / (1u64 << 31) as f64
+        };
+
+        (0..count)
+            .map(|_| {
+                let center = nalgebra::Point3::new(
+                    next() * 50.0 - 25.0,
+                    next() * 10.0 - 5.0,
+                    next() * 50.0 - 25.0,
+                );
+                let radius = 0.25 + next() * 2.0;
+                Aabb::from_center(&center, &nalgebra::Vector3::new(radius, radius, radius))
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_pairs_match_brute_force() {
+        let mut bounds = random_bounds(300, 7);
+        bounds.push(Aabb::infinite());
+        bounds.push(Aabb::new(
+            nalgebra::Point3::new(-64.0, 0.0, -64.0),
+            nalgebra::Point3::new(64.0, 1.0, 64.0),
+        ));
+        bounds.push(Aabb::infinite());
+
+        let mut grid = SpatialHash::new(4.0);
+        for aabb in &bounds {
+            grid.insert(*aabb);
+        }
+
+        let pairs = grid.pairs();
+
+        let mut expected = brute_force_pairs(&bounds);
+        expected.sort();
+
+        assert!(!expected.is_empty());
+        assert_eq!(pairs, expected);
+    }
+
+    #[test]
+    fn test_pairs_reported_once() {
+        let mut grid = SpatialHash::new(1.0);
+        // both span several shared cells
+        grid.insert(Aabb::new(
+            nalgebra::Point3::new(0.0, 0.0, 0.0),
+            nalgebra::Point3::new(3.0, 3.0, 3.0),
+        ));
+        grid.insert(Aabb::new(
+            nalgebra::Point3::new(0.5, 0.5, 0.5),
+            nalgebra::Point3::new(2.5, 2.5, 2.5),
+        ));
+
+        assert_eq!(grid.pairs(), vec![(0, 1)]);
+
+        grid.clear();
+        assert!(grid.pairs().is_empty());
+    }
+
+    #[test]
+    fn test_cast_visits_overlapping() {
+        let bounds = random_bounds(300, 11);
+        let mut grid = SpatialHash::new(4.0);
+        for aabb in &bounds {
+            grid.insert(*aabb);
+        }
+
+        let origin = nalgebra::Point3::new(-30.0, 0.5, -20.0);
+        let direction = nalgebra::Vector3::new(1.0, 0.0, 0.75).normalize();
+        let radius = 1.5;
+
+        let mut visited = Vec::new();
+        grid.cast(&origin, &direction, radius, 100.0, |index| {
+            visited.push(index);
+            None
+        });
+
+        // everything the swept ray touches has to be visited, exactly once
+        for (index, aabb) in bounds.iter().enumerate() {
+            if let Some((enter, exit)) = aabb.expanded(radius).intersect_ray(&origin, &direction) {
+                if exit >= 0.0 && enter <= 100.0 {
+                    assert!(visited.contains(&index));
+                }
+            }
+        }
+        let count = visited.len();
+        visited.sort();
+        visited.dedup();
+        assert_eq!(visited.len(), count);
+        assert!(count < bounds.len());
+    }
+}
@@ -0,0 +1,245 @@
+use crate::display::terrain::HeightMap;
+
+use super::Aabb;
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Triangle {
+    pub a: nalgebra::Point3<f64>,
+    pub b: nalgebra::Point3<f64>,
+    pub c: nalgebra::Point3<f64>,
+}
+
+impl Triangle {
+    pub fn new(
+        a: nalgebra::Point3<f64>,
+        b: nalgebra::Point3<f64>,
+        c: nalgebra::Point3<f64>,
+    ) -> Triangle {
+        Triangle { a, b, c }
+    }
+
+    /**
+     * The two triangles of a heightmap grid cell in map space, split along
+     * the same diagonal as the rendered terrain mesh
+     */
+    pub fn from_heightmap_cell(map: &HeightMap, x: usize, z: usize) -> [Triangle; 2] {
+        let corner = |x: usize, z: usize| {
+            let height = map.get(x, z).unwrap_or(0.0) as f64;
+            nalgebra::Point3::new(x as f64, -height, z as f64)
+        };
+
+        [
+            Triangle::new(corner(x, z), corner(x + 1, z), corner(x, z + 1)),
+            Triangle::new(corner(x + 1, z), corner(x + 1, z + 1), corner(x, z + 1)),
+        ]
+    }
+
+    pub fn bounds(&self) -> Aabb {
+        Aabb::new(
+            nalgebra::Point3::new(
+                self.a.x.min(self.b.x).min(self.c.x),
+                self.a.y.min(self.b.y).min(self.c.y),
+                self.a.z.min(self.b.z).min(self.c.z),
+            ),
+            nalgebra::Point3::new(
+                self.a.x.max(self.b.x).max(self.c.x),
+                self.a.y.max(self.b.y).max(self.c.y),
+                self.a.z.max(self.b.z).max(self.c.z),
+            ),
+        )
+    }
+
+    pub fn centroid(&self) -> nalgebra::Point3<f64> {
+        nalgebra::Point3::from((self.a.coords + self.b.coords + self.c.coords) / 3.0)
+    }
+
+    /**
+     * Follows the winding order, None for degenerate triangles
+     */
+    pub fn normal(&self) -> Option<nalgebra::Unit<nalgebra::Vector3<f64>>> {
+        nalgebra::Unit::try_new((self.b - self.a).cross(&(self.c - self.a)), f64::EPSILON)
+    }
+
+    pub fn closest_point(&self, point: &nalgebra::Point3<f64>) -> nalgebra::Point3<f64> {
+        // Voronoi region tests from Ericson, Real-Time Collision Detection
+        let ab = self.b - self.a;
+        let ac = self.c - self.a;
+
+        let ap = point - self.a;
+        let d1 = ab.dot(&ap);
+        let d2 = ac.dot(&ap);
+        if d1 <= 0.0 && d2 <= 0.0 {
+            return self.a;
+        }
+
+        let bp = point - self.b;
+        let d3 = ab.dot(&bp);
+        let d4 = ac.dot(&bp);
+        if d3 >= 0.0 && d4 <= d3 {
+            return self.b;
+        }
+
+        let vc = d1 * d4 - d3 * d2;
+        if vc <= 0.0 && d1 >= 0.0 && d3 <= 0.0 {
+            return self.a + ab * (d1 / (d1 - d3));
+        }
+
+        let cp = point - self.c;
+        let d5 = ab.dot(&cp);
+        let d6 = ac.dot(&cp);
+        if d6 >= 0.0 && d5 <= d6 {
+            return self.c;
+        }
+
+        let vb = d5 * d2 - d1 * d6;
+        if vb <= 0.0 && d2 >= 0.0 && d6 <= 0.0 {
+            return self.a + ac * (d2 / (d2 - d6));
+        }
+
+        let va = d3 * d6 - d5 * d4;
+        if va <= 0.0 && (d4 - d3) >= 0.0 && (d5 - d6) >= 0.0 {
+            return self.b + (self.c - self.b) * ((d4 - d3) / ((d4 - d3) + (d5 - d6)));
+        }
+
+        let denom = 1.0 / (va + vb + vc);
+        self.a + ab * (vb * denom) + ac * (vc * denom)
+    }
+
+    /**
+     * Closest points between the segment from 'start' to 'end' and the
+     * triangle, returned as (point on segment, point on triangle)
+     */
+    pub fn closest_to_segment(
+        &self,
+        start: &nalgebra::Point3<f64>,
+        end: &nalgebra::Point3<f64>,
+    ) -> (nalgebra::Point3<f64>, nalgebra::Point3<f64>)
+    {
+        if let Some(point) = self.segment_intersection(start, end) {
+            return (point, point);
+        }
+
+        // without an intersection, the closest points are on the boundary
+        // of either the segment or the triangle
+        let mut candidates = vec![
+            (*start, self.closest_point(start)),
+            (*end, self.closest_point(end)),
+        ];
+        for (edge_start, edge_end) in &[(self.a, self.b), (self.b, self.c), (self.c, self.a)] {
+            candidates.push(closest_between_segments(start, end, edge_start, edge_end));
+        }
+
+        candidates.into_iter()
+            .min_by(|(s1, t1), (s2, t2)| {
+                nalgebra::distance_squared(s1, t1)
+                    .partial_cmp(&nalgebra::distance_squared(s2, t2))
+                    .unwrap_or(std::cmp::Ordering::Equal)
+            })
+            .unwrap()
+    }
+
+    fn segment_intersection(
+        &self,
+        start: &nalgebra::Point3<f64>,
+        end: &nalgebra::Point3<f64>,
+    ) -> Option<nalgebra::Point3<f64>>
+    {
+        let normal = self.normal()?;
+        let d_start = (start - self.a).dot(&normal);
+        let d_end = (end - self.a).dot(&normal);
+        if d_start * d_end > 0.0 || d_start == d_end {
+            return None;
+        }
+
+        let point = start + (end - start) * (d_start / (d_start - d_end));
+        if nalgebra::distance_squared(&point, &self.closest_point(&point)) <= f64::EPSILON {
+            Some(point)
+        } else {
+            None
+        }
+    }
+}
+
+/**
+ * Closest points between segments p1-q1 and p2-q2, from Ericson, Real-Time
+ * Collision Detection
+ */
+fn closest_between_segments(
+    p1: &nalgebra::Point3<f64>, q1: &nalgebra::Point3<f64>,
+    p2: &nalgebra::Point3<f64>, q2: &nalgebra::Point3<f64>,
+) -> (nalgebra::Point3<f64>, nalgebra::Point3<f64>)
+{
+    let d1 = q1 - p1;
+    let d2 = q2 - p2;
+    let r = p1 - p2;
+    let a = d1.norm_squared();
+    let e = d2.norm_squared();
+    let f = d2.dot(&r);
+
+    let clamp = |value: f64| value.clamp(0.0, 1.0);
+
+    let (s, t) = if a <= f64::EPSILON && e <= f64::EPSILON {
+        (0.0, 0.0)
+    } else if a <= f64::EPSILON {
+        (0.0, clamp(f / e))
+    } else {
+        let c = d1.dot(&r);
+        if e <= f64::EPSILON {
+            (clamp(-c / a), 0.0)
+        } else {
+            let b = d1.dot(&d2);
+            let denom = a * e - b * b;
+            let s = if denom != 0.0 { clamp((b * f - c * e) / denom) } else { 0.0 };
+            let t = (b * s + f) / e;
+            if t < 0.0 {
+                (clamp(-c / a), 0.0)
+            } else if t > 1.0 {
+                (clamp((b - c) / a), 1.0)
+            } else {
+                (s, t)
+            }
+        }
+    };
+
+    (p1 + d1 * s, p2 + d2 * t)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_closest_to_segment() {
+        // floor triangle, facing negative y
+        let triangle = Triangle::new(
+            nalgebra::Point3::new(0.0, 0.0, 0.0),
+            nalgebra::Point3::new(2.0, 0.0, 0.0),
+            nalgebra::Point3::new(0.0, 0.0, 2.0),
+        );
+        assert_eq!(triangle.normal().unwrap().y, -1.0);
+
+        // above the face
+        let (on_segment, on_triangle) = triangle.closest_to_segment(
+            &nalgebra::Point3::new(0.5, -2.0, 0.5),
+            &nalgebra::Point3::new(0.5, -1.0, 0.5),
+        );
+        assert_eq!(on_segment, nalgebra::Point3::new(0.5, -1.0, 0.5));
+        assert_eq!(on_triangle, nalgebra::Point3::new(0.5, 0.0, 0.5));
+
+        // through the face
+        let (on_segment, on_triangle) = triangle.closest_to_segment(
+            &nalgebra::Point3::new(0.5, -1.0, 0.5),
+            &nalgebra::Point3::new(0.5, 1.0, 0.5),
+        );
+        assert_eq!(on_segment, on_triangle);
+        assert_eq!(on_triangle, nalgebra::Point3::new(0.5, 0.0, 0.5));
+
+        // beside the hypotenuse, closest to the edge
+        let (on_segment, on_triangle) = triangle.closest_to_segment(
+            &nalgebra::Point3::new(2.0, -1.0, 2.0),
+            &nalgebra::Point3::new(2.0, 1.0, 2.0),
+        );
+        assert_eq!(on_segment, nalgebra::Point3::new(2.0, 0.0, 2.0));
+        assert_eq!(on_triangle, nalgebra::Point3::new(1.0, 0.0, 1.0));
+    }
+}
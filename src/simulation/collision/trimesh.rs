@@ -0,0 +1,265 @@
+use super::{
+    Aabb,
+    Triangle,
+};
+
+/**
+ * Triangles per BVH leaf
+ */
+const MAX_LEAF_SIZE: usize = 4;
+
+/**
+ * Static triangle soup with a bounding volume hierarchy for overlap queries.
+ * The hierarchy is built once, top-down, splitting each node at the median
+ * centroid along its longest axis.
+ */
+#[derive(Clone)]
+pub struct TriMesh {
+    triangles: Vec<Triangle>,
+    nodes: Vec<Node>,
+}
+
+#[derive(Clone)]
+struct Node {
+    bounds: Aabb,
+    kind: NodeKind,
+}
+
+#[derive(Clone)]
+enum NodeKind {
+    Branch { left: usize, right: usize },
+    Leaf { first: usize, count: usize },
+}
+
+impl TriMesh {
+    /**
+     * Degenerate triangles are dropped
+     */
+    pub fn new(triangles: Vec<Triangle>) -> TriMesh {
+        let mut triangles: Vec<Triangle> = triangles.into_iter()
+            .filter(|triangle| triangle.normal().is_some())
+            .collect();
+
+        let mut nodes = Vec::new();
+        if !triangles.is_empty() {
+            let count = triangles.len();
+            build(&mut triangles, 0, count, &mut nodes);
+        }
+
+        TriMesh { triangles, nodes }
+    }
+
+    pub fn len(&self) -> usize {
+        self.triangles.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.triangles.is_empty()
+    }
+
+    /**
+     * Bounds of the whole mesh, in mesh space
+     */
+    pub fn bounds(&self) -> Option<Aabb> {
+        self.nodes.first().map(|node| node.bounds)
+    }
+
+    /**
+     * Calls 'visit' for every triangle whose bounds overlap 'bounds'
+     */
+    pub fn query<F>(&self, bounds: &Aabb, mut visit: F)
+    where
+        F: FnMut(&Triangle),
+    {
+        if self.nodes.is_empty() {
+            return;
+        }
+
+        let mut stack = vec![0];
+        while let Some(index) = stack.pop() {
+            let node = &self.nodes[index];
+            if !node.bounds.intersects(bounds) {
+                continue;
+            }
+
+            match node.kind {
+                NodeKind::Branch { left, right } => {
+                    stack.push(left);
+                    stack.push(right);
+                },
+                NodeKind::Leaf { first, count } => {
+                    for triangle in &self.triangles[first..first + count] {
+                        if triangle.bounds().intersects(bounds) {
+                            visit(triangle);
+                        }
+                    }
+                },
+            }
+        }
+    }
+
+    /**
+     * Calls 'visit' for triangles near the ray from 'origin' along the unit
+     * vector 'direction', nearest nodes first. 'margin' widens the ray, for
+     * sweeping spheres. 'visit' returns the distance of a hit if any, and
+     * nodes further away than the nearest hit are skipped.
+     */
+    pub fn query_ray<F>(
+        &self,
+        origin: &nalgebra::Point3<f64>,
+        direction: &nalgebra::Vector3<f64>,
+        margin: f64,
+        max_distance: f64,
+        mut visit: F,
+    )
+    where
+        F: FnMut(&Triangle) -> Option<f64>,
+    {
+        let mut best = max_distance;
+        let mut stack = Vec::new();
+        if let Some(root) = self.nodes.first() {
+            if let Some((enter, _)) = self.node_interval(root, origin, direction, margin, best) {
+                stack.push((0, enter));
+            }
+        }
+
+        while let Some((index, enter)) = stack.pop() {
+            if enter > best {
+                continue;
+            }
+
+            match self.nodes[index].kind {
+                NodeKind::Branch { left, right } => {
+                    let mut children: Vec<(usize, f64)> = [left, right].iter()
+                        .filter_map(|&child| {
+                            self.node_interval(&self.nodes[child], origin, direction, margin, best)
+                                .map(|(enter, _)| (child, enter))
+                        })
+                        .collect();
+                    // pushed furthest first, so the nearest is popped next
+                    children.sort_by(|(_, e1), (_, e2)| {
+                        e2.partial_cmp(e1).unwrap_or(std::cmp::Ordering::Equal)
+                    });
+                    stack.extend(children);
+                },
+                NodeKind::Leaf { first, count } => {
+                    for triangle in &self.triangles[first..first + count] {
+                        if let Some(distance) = visit(triangle) {
+                            best = best.min(distance);
+                        }
+                    }
+                },
+            }
+        }
+    }
+
+    fn node_interval(
+        &self,
+        node: &Node,
+        origin: &nalgebra::Point3<f64>,
+        direction: &nalgebra::Vector3<f64>,
+        margin: f64,
+        max_distance: f64,
+    ) -> Option<(f64, f64)>
+    {
+        let (enter, exit) = node.bounds.expanded(margin).intersect_ray(origin, direction)?;
+        if exit < 0.0 || enter > max_distance {
+            None
+        } else {
+            Some((enter.max(0.0), exit))
+        }
+    }
+}
+
+/**
+ * Builds the subtree for triangles[first..first + count], reordering them
+ * so that every node covers a contiguous range. Returns the node's index.
+ */
+fn build(triangles: &mut [Triangle], first: usize, count: usize, nodes: &mut Vec<Node>) -> usize {
+    let range = &mut triangles[first..first + count];
+    let bounds = range.iter()
+        .map(|triangle| triangle.bounds())
+        .fold(range[0].bounds(), |acc, bounds| acc.merged(&bounds));
+
+    let index = nodes.len();
+    if count <= MAX_LEAF_SIZE {
+        nodes.push(Node { bounds, kind: NodeKind::Leaf { first, count } });
+        return index;
+    }
+
+    let extent = bounds.max - bounds.min;
+    let axis = extent.imax();
+    range.sort_by(|t1, t2| {
+        t1.centroid()[axis]
+            .partial_cmp(&t2.centroid()[axis])
+            .unwrap_or(std::cmp::Ordering::Equal)
+    });
+
+    // reserve the slot, children are pushed after their parent
+    nodes.push(Node { bounds, kind: NodeKind::Leaf { first, count } });
+
+    let half = count / 2;
+    let left = build(triangles, first, half, nodes);
+    let right = build(triangles, first + half, count - half, nodes);
+    nodes[index].kind = NodeKind::Branch { left, right };
+
+    index
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_query_matches_brute_force() {
+        // a grid of floor quads
+        let mut triangles = Vec::new();
+        for x in 0..16 {
+            for z in 0..16 {
+                let (x, z) = (x as f64, z as f64);
+                triangles.push(Triangle::new(
+                    nalgebra::Point3::new(x, 0.0, z),
+                    nalgebra::Point3::new(x + 1.0, 0.0, z),
+                    nalgebra::Point3::new(x, 0.0, z + 1.0),
+                ));
+                triangles.push(Triangle::new(
+                    nalgebra::Point3::new(x + 1.0, 0.0, z),
+                    nalgebra::Point3::new(x + 1.0, 0.0, z + 1.0),
+                    nalgebra::Point3::new(x, 0.0, z + 1.0),
+                ));
+            }
+        }
+        // degenerate
+        triangles.push(Triangle::new(
+            nalgebra::Point3::new(0.0, 0.0, 0.0),
+            nalgebra::Point3::new(1.0, 0.0, 0.0),
+            nalgebra::Point3::new(2.0, 0.0, 0.0),
+        ));
+
+        let mesh = TriMesh::new(triangles.clone());
+        assert_eq!(mesh.len(), 16 * 16 * 2);
+        assert_eq!(mesh.bounds().unwrap(), Aabb::new(
+            nalgebra::Point3::new(0.0, 0.0, 0.0),
+            nalgebra::Point3::new(16.0, 0.0, 16.0),
+        ));
+
+        let query = Aabb::new(
+            nalgebra::Point3::new(3.5, -1.0, 7.25),
+            nalgebra::Point3::new(5.25, 1.0, 8.5),
+        );
+
+        let mut found = Vec::new();
+        mesh.query(&query, |triangle| found.push(triangle.clone()));
+
+        let expected: Vec<_> = triangles.iter()
+            .filter(|triangle| triangle.normal().is_some())
+            .filter(|triangle| triangle.bounds().intersects(&query))
+            .collect();
+
+        assert!(!expected.is_empty());
+        assert_eq!(found.len(), expected.len());
+        for triangle in expected {
+            assert!(found.contains(triangle));
+        }
+    }
+}
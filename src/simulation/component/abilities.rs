@@ -0,0 +1,46 @@
+use specs::prelude::*;
+
+/**
+ * Abilities a character can use, in hotbar order
+ */
+pub struct Abilities {
+    pub slots: Vec<AbilitySlot>,
+    pub casting: Option<Cast>,
+}
+
+pub struct AbilitySlot {
+    pub ability: String,
+    /**
+     * Seconds until the ability can be used again
+     */
+    pub cooldown: f64,
+}
+
+/**
+ * Ability being cast from 'slot' at 'target', taking effect in 'remaining'
+ * seconds. Other abilities cannot be used meanwhile.
+ */
+pub struct Cast {
+    pub slot: usize,
+    pub ability: String,
+    pub target: Option<Entity>,
+    pub remaining: f64,
+}
+
+impl Abilities {
+    pub fn new(abilities: &[String]) -> Abilities {
+        Abilities {
+            slots: abilities.iter()
+                .map(|ability| AbilitySlot {
+                    ability: ability.clone(),
+                    cooldown: 0.0,
+                })
+                .collect(),
+            casting: None,
+        }
+    }
+}
+
+impl Component for Abilities {
+    type Storage = VecStorage<Self>;
+}
@@ -1,31 +1,134 @@
 use specs::prelude::*;
 
 use crate::display::terrain::HeightMap;
+use crate::simulation::collision::{
+    Aabb,
+    TriMesh,
+};
 
+/**
+ * Layer colliders are placed on unless told otherwise
+ */
+pub const DEFAULT_LAYER: u32 = 1;
+
+/**
+ * Mask matching colliders on any layer
+ */
+pub const ALL_LAYERS: u32 = !0;
+
+/**
+ * Two colliders only interact if each one's layer is in the other's mask.
+ * Triggers are not solid: their contacts are reported as trigger events
+ * instead of collisions.
+ */
+#[derive(Clone)]
 pub struct Collider {
     pub collider: ColliderType,
+    /**
+     * Bit set of the layers the collider is on, matched against masks
+     */
+    pub layer: u32,
+    /**
+     * Bit set of the layers the collider interacts with
+     */
+    pub mask: u32,
+    pub trigger: bool,
     pub collisions: Vec<Collision>,
 }
 
+#[derive(Clone)]
 pub struct Collision {
     pub with: Entity,
     pub depth: nalgebra::Vector3<f64>,
     pub normal: nalgebra::Unit<nalgebra::Vector3<f64>>,
 }
 
+#[derive(Clone)]
 pub enum ColliderType {
     Plane(nalgebra::Unit<nalgebra::Vector3<f64>>),
     Sphere(f64),
     HeightMap(HeightMap),
+    /**
+     * Axis-aligned box given by its half extents
+     */
+    Aabb(nalgebra::Vector3<f64>),
+    /**
+     * Vertical capsule, 'half_height' is the distance from the centre to
+     * the centre of either cap
+     */
+    Capsule { radius: f64, half_height: f64 },
+    /**
+     * Static triangle mesh, with vertices relative to the entity position
+     */
+    TriMesh(TriMesh),
 }
 
 impl Collider {
     pub fn new(collider: ColliderType) -> Collider {
         Collider {
             collider,
+            layer: DEFAULT_LAYER,
+            mask: ALL_LAYERS,
+            trigger: false,
             collisions: Vec::new(),
         }
     }
+
+    pub fn interacts_with(&self, other: &Collider) -> bool {
+        self.layer & other.mask != 0 && other.layer & self.mask != 0
+    }
+}
+
+impl ColliderType {
+    /**
+     * Radius of the largest sphere around the position that fits inside the
+     * collider, None for level geometry
+     */
+    pub fn inner_radius(&self) -> Option<f64> {
+        match self {
+            ColliderType::Sphere(radius) => Some(*radius),
+            ColliderType::Capsule { radius, .. } => Some(*radius),
+            ColliderType::Aabb(half_extents) => Some(half_extents.min()),
+            ColliderType::Plane(_) | ColliderType::HeightMap(_) | ColliderType::TriMesh(_) => None,
+        }
+    }
+
+    pub fn bounds(&self, position: &nalgebra::Point3<f64>) -> Aabb {
+        match self {
+            ColliderType::Plane(_) => Aabb::infinite(),
+            ColliderType::Sphere(radius) => {
+                Aabb::from_center(position, &nalgebra::Vector3::new(*radius, *radius, *radius))
+            },
+            ColliderType::Aabb(half_extents) => Aabb::from_center(position, half_extents),
+            ColliderType::Capsule { radius, half_height } => {
+                Aabb::from_center(
+                    position,
+                    &nalgebra::Vector3::new(*radius, half_height + radius, *radius),
+                )
+            },
+            ColliderType::TriMesh(mesh) => {
+                match mesh.bounds() {
+                    Some(bounds) => Aabb::new(
+                        position + bounds.min.coords,
+                        position + bounds.max.coords,
+                    ),
+                    None => Aabb::new(*position, *position),
+                }
+            },
+            ColliderType::HeightMap(map) => {
+                // heights grow towards negative y
+                let (min, max) = map.height_range();
+                Aabb::new(
+                    nalgebra::Point3::new(position.x, position.y - max as f64, position.z),
+                    nalgebra::Point3::new(
+                        position.x + map.size as f64,
+                        position.y - min as f64,
+                        position.z + map.size as f64,
+                    ),
+                )
+            },
+        }
+    }
 }
 
 impl Component for Collider {
@@ -0,0 +1,15 @@
+use specs::prelude::*;
+
+/**
+ * Flags a fast moving entity, whose motion each tick is swept against other
+ * colliders instead of only being tested where it ends up. 'start' is where
+ * the entity moved from during the current tick.
+ */
+#[derive(Default)]
+pub struct ContinuousCollision {
+    pub start: Option<nalgebra::Point3<f64>>,
+}
+
+impl Component for ContinuousCollision {
+    type Storage = VecStorage<Self>;
+}
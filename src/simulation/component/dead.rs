@@ -0,0 +1,12 @@
+use specs::prelude::*;
+
+/**
+ * Marks entities whose health ran out. Dead characters do not move or act
+ * until they respawn.
+ */
+#[derive(Default)]
+pub struct Dead;
+
+impl Component for Dead {
+    type Storage = NullStorage<Self>;
+}
@@ -1,7 +1,16 @@
 use specs::prelude::*;
 
-pub struct Health(pub u64);
+pub struct Health {
+    pub current: u64,
+    pub max: u64,
+}
+
+impl Health {
+    pub fn new(max: u64) -> Health {
+        Health { current: max, max }
+    }
+}
 
 impl Component for Health {
     type Storage = VecStorage<Self>;
-}
\ No newline at end of file
+}
@@ -0,0 +1,11 @@
+use specs::prelude::*;
+
+/**
+ * Marks entities the active character can tab through as targets
+ */
+#[derive(Default)]
+pub struct Hostile;
+
+impl Component for Hostile {
+    type Storage = NullStorage<Self>;
+}
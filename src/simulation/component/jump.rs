@@ -1,9 +1,36 @@
 use specs::prelude::*;
 
+/**
+ * 'force' is the upward velocity in m/s given by a jump. Jumps are still
+ * allowed for 'coyote_time' seconds after walking off a ledge, and a jump
+ * pressed up to 'buffer_time' seconds before landing happens on landing.
+ */
 pub struct Jump {
     pub force: f64,
+    pub coyote_time: f64,
+    pub buffer_time: f64,
+    /**
+     * Seconds left on a buffered jump
+     */
+    pub buffered: f64,
+    /**
+     * Set when jumping, until the next landing
+     */
+    pub airborne: bool,
+}
+
+impl Jump {
+    pub fn new(force: f64) -> Jump {
+        Jump {
+            force,
+            coyote_time: 0.0,
+            buffer_time: 0.0,
+            buffered: 0.0,
+            airborne: false,
+        }
+    }
 }
 
 impl Component for Jump {
     type Storage = VecStorage<Self>;
-}
\ No newline at end of file
+}
@@ -0,0 +1,23 @@
+use specs::prelude::*;
+
+/**
+ * Offset and rotation of an entity relative to its `Parent`, from which its
+ * `Position` and `Rotation` are worked out every tick
+ */
+pub struct LocalTransform {
+    pub translation: nalgebra::Vector3<f64>,
+    pub rotation: nalgebra::UnitQuaternion<f64>,
+}
+
+impl LocalTransform {
+    pub fn new(translation: nalgebra::Vector3<f64>) -> LocalTransform {
+        LocalTransform {
+            translation,
+            rotation: nalgebra::UnitQuaternion::identity(),
+        }
+    }
+}
+
+impl Component for LocalTransform {
+    type Storage = VecStorage<Self>;
+}
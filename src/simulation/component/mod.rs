@@ -1,23 +1,58 @@
+mod abilities;
 pub mod collider;
+mod continuouscollision;
+mod dead;
 mod health;
+mod hostile;
 mod jump;
+mod localtransform;
 mod model;
 mod movement;
+mod movingplatform;
 mod name;
+mod navagent;
+mod parent;
 mod position;
+mod projectile;
+mod rigidbody;
+mod rotation;
 mod serverid;
+mod spawnpoint;
+mod submersion;
 mod terrain;
 mod texture;
 mod velocity;
+mod water;
 
+pub use abilities::{
+    Abilities,
+    AbilitySlot,
+    Cast,
+};
 pub use collider::Collider;
+pub use continuouscollision::ContinuousCollision;
+pub use dead::Dead;
 pub use health::Health;
+pub use hostile::Hostile;
 pub use jump::Jump;
+pub use localtransform::LocalTransform;
 pub use model::Model;
 pub use movement::Movement;
+pub use movingplatform::{
+    MovingPlatform,
+    PathMode,
+};
 pub use name::Name;
+pub use navagent::NavAgent;
+pub use parent::Parent;
 pub use position::Position;
+pub use projectile::Projectile;
+pub use rigidbody::RigidBody;
+pub use rotation::Rotation;
 pub use serverid::ServerID;
+pub use spawnpoint::SpawnPoint;
+pub use submersion::Submersion;
 pub use terrain::Terrain;
 pub use texture::Texture;
-pub use velocity::Velocity;
\ No newline at end of file
+pub use velocity::Velocity;
+pub use water::Water;
\ No newline at end of file
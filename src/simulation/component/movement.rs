@@ -1,10 +1,69 @@
 use specs::prelude::*;
 
+/**
+ * Character controller state. Speeds are given in m/s, 'air_control' is the
+ * share of the walking speed available while airborne and 'step_height' the
+ * highest ledge walked up without jumping, in m.
+ */
 pub struct Movement {
     pub speed: f64,
+    pub sprint_speed: f64,
+    pub crouch_speed: f64,
+    pub swim_speed: f64,
+    pub air_control: f64,
+    pub step_height: f64,
     pub on_ground: bool,
+    /**
+     * What the character last stood on
+     */
+    pub ground: Option<Entity>,
+    pub sprinting: bool,
+    pub crouching: bool,
+    pub swimming: bool,
+    /**
+     * Seconds since the character last stood on the ground
+     */
+    pub air_time: f64,
+}
+
+impl Movement {
+    pub fn new(speed: f64) -> Movement {
+        Movement {
+            speed,
+            sprint_speed: speed,
+            crouch_speed: speed,
+            swim_speed: speed,
+            air_control: 1.0,
+            step_height: 0.0,
+            on_ground: true,
+            ground: None,
+            sprinting: false,
+            crouching: false,
+            swimming: false,
+            air_time: 0.0,
+        }
+    }
+
+    /**
+     * Horizontal speed for the current state
+     */
+    pub fn current_speed(&self) -> f64 {
+        let speed = if self.crouching {
+            self.crouch_speed
+        } else if self.sprinting {
+            self.sprint_speed
+        } else {
+            self.speed
+        };
+
+        if self.on_ground {
+            speed
+        } else {
+            speed * self.air_control
+        }
+    }
 }
 
 impl Component for Movement {
     type Storage = VecStorage<Self>;
-}
\ No newline at end of file
+}
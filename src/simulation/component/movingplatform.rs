@@ -0,0 +1,120 @@
+use specs::prelude::*;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum PathMode {
+    /**
+     * Returns from the last waypoint straight to the first one
+     */
+    Loop,
+    /**
+     * Travels the waypoints backwards after reaching the last one
+     */
+    PingPong,
+}
+
+/**
+ * Kinematic entity following a path through 'waypoints' at 'speed' m/s,
+ * unaffected by collisions. Its position only depends on the tick number,
+ * see `TickNumber` for when that matches another machine's.
+ */
+pub struct MovingPlatform {
+    pub waypoints: Vec<nalgebra::Point3<f64>>,
+    pub speed: f64,
+    pub mode: PathMode,
+}
+
+impl MovingPlatform {
+    pub fn new(waypoints: Vec<nalgebra::Point3<f64>>, speed: f64, mode: PathMode) -> MovingPlatform {
+        MovingPlatform {
+            waypoints,
+            speed,
+            mode,
+        }
+    }
+
+    /**
+     * Where the platform is 'time' seconds after starting from the first
+     * waypoint
+     */
+    pub fn position_at(&self, time: f64) -> nalgebra::Point3<f64> {
+        let first = match self.waypoints.first() {
+            Some(first) => *first,
+            None => return nalgebra::Point3::origin(),
+        };
+
+        let length: f64 = self.segments().map(|(start, end)| (end - start).norm()).sum();
+        if length <= 0.0 || !(time * self.speed).is_finite() {
+            return first;
+        }
+
+        let mut distance = (time * self.speed).rem_euclid(match self.mode {
+            PathMode::Loop => length,
+            PathMode::PingPong => length * 2.0,
+        });
+        if distance > length {
+            distance = length * 2.0 - distance;
+        }
+
+        let mut last = first;
+        for (start, end) in self.segments() {
+            let span = end - start;
+            let span_length = span.norm();
+            if span_length > 0.0 && distance <= span_length {
+                return start + span * (distance / span_length);
+            }
+            distance -= span_length;
+            last = end;
+        }
+
+        last
+    }
+
+    fn segments(&self) -> impl Iterator<Item = (nalgebra::Point3<f64>, nalgebra::Point3<f64>)> + '_ {
+        let closing = match (self.mode, self.waypoints.first(), self.waypoints.last()) {
+            (PathMode::Loop, Some(first), Some(last)) => Some((*last, *first)),
+            _ => None,
+        };
+
+        self.waypoints.windows(2)
+            .map(|segment| (segment[0], segment[1]))
+            .chain(closing)
+    }
+}
+
+impl Component for MovingPlatform {
+    type Storage = VecStorage<Self>;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_near(a: nalgebra::Point3<f64>, b: [f64; 3]) {
+        assert!((a - nalgebra::Point3::from(b)).norm() < 1e-9, "{} != {:?}", a, b);
+    }
+
+    #[test]
+    fn test_paths() {
+        let waypoints = vec![
+            nalgebra::Point3::new(0.0, 0.0, 0.0),
+            nalgebra::Point3::new(4.0, 0.0, 0.0),
+            nalgebra::Point3::new(4.0, 0.0, 3.0),
+        ];
+
+        // 12m around, returning along the diagonal
+        let platform = MovingPlatform::new(waypoints.clone(), 2.0, PathMode::Loop);
+        assert_near(platform.position_at(0.0), [0.0, 0.0, 0.0]);
+        assert_near(platform.position_at(1.0), [2.0, 0.0, 0.0]);
+        assert_near(platform.position_at(3.0), [4.0, 0.0, 2.0]);
+        assert_near(platform.position_at(4.5), [2.4, 0.0, 1.8]);
+        assert_near(platform.position_at(7.0), [2.0, 0.0, 0.0]);
+
+        // 7m each way
+        let platform = MovingPlatform::new(waypoints, 2.0, PathMode::PingPong);
+        assert_near(platform.position_at(3.0), [4.0, 0.0, 2.0]);
+        assert_near(platform.position_at(3.5), [4.0, 0.0, 3.0]);
+        assert_near(platform.position_at(4.0), [4.0, 0.0, 2.0]);
+        assert_near(platform.position_at(6.0), [2.0, 0.0, 0.0]);
+        assert_near(platform.position_at(7.5), [1.0, 0.0, 0.0]);
+    }
+}
@@ -0,0 +1,45 @@
+use specs::prelude::*;
+
+/**
+ * Walks the entity along a path on the NavGrid to 'destination' at 'speed'
+ * m/s. Idle agents walk to the points of their 'patrol' in turn.
+ */
+pub struct NavAgent {
+    pub speed: f64,
+    pub destination: Option<nalgebra::Point3<f64>>,
+    /**
+     * Points still to be walked to, nearest first
+     */
+    pub path: Vec<nalgebra::Point3<f64>>,
+    pub patrol: Vec<nalgebra::Point3<f64>>,
+    pub next_patrol: usize,
+}
+
+impl NavAgent {
+    pub fn new(speed: f64) -> NavAgent {
+        NavAgent {
+            speed,
+            destination: None,
+            path: Vec::new(),
+            patrol: Vec::new(),
+            next_patrol: 0,
+        }
+    }
+
+    /**
+     * Sets off towards 'destination', the path is found on the next tick
+     */
+    pub fn move_to(&mut self, destination: nalgebra::Point3<f64>) {
+        self.destination = Some(destination);
+        self.path.clear();
+    }
+
+    pub fn stop(&mut self) {
+        self.destination = None;
+        self.path.clear();
+    }
+}
+
+impl Component for NavAgent {
+    type Storage = VecStorage<Self>;
+}
\ No newline at end of file
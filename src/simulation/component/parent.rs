@@ -0,0 +1,11 @@
+use specs::prelude::*;
+
+/**
+ * Attaches the entity to another one, which it then moves and turns with.
+ * Its `LocalTransform` is relative to the parent's position and rotation.
+ */
+pub struct Parent(pub Entity);
+
+impl Component for Parent {
+    type Storage = VecStorage<Self>;
+}
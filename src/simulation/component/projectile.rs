@@ -0,0 +1,29 @@
+use specs::prelude::*;
+
+/**
+ * Moves the entity along 'velocity' every tick, sweeping a sphere of
+ * 'radius' ahead of it so that it cannot pass through colliders between
+ * ticks. Projectiles are removed when they hit something other than their
+ * 'owner', or once 'lifetime' runs out.
+ *
+ * Projectiles are only spawned locally, by abilities. Protocol v0.2.1 has no
+ * operation for the server to spawn them with, so there are no authoritative
+ * projectiles to match predicted ones against or correct them by.
+ */
+pub struct Projectile {
+    pub owner: Option<Entity>,
+    pub velocity: nalgebra::Vector3<f64>,
+    /**
+     * Multiplier for gravity, zero for projectiles that fly straight
+     */
+    pub gravity: f64,
+    pub radius: f64,
+    /**
+     * Seconds left before the projectile is removed
+     */
+    pub lifetime: f64,
+}
+
+impl Component for Projectile {
+    type Storage = VecStorage<Self>;
+}
@@ -0,0 +1,59 @@
+use specs::prelude::*;
+
+use super::Velocity;
+
+/**
+ * Dynamic body moved by collision impulses. 'mass' is in kg, 'restitution'
+ * is the share of the approach speed kept when bouncing off something and
+ * 'friction' the Coulomb friction coefficient. Bodies need a `Velocity`.
+ */
+pub struct RigidBody {
+    pub mass: f64,
+    pub restitution: f64,
+    pub friction: f64,
+    pub sleeping: bool,
+    /**
+     * Seconds spent below the sleep speed
+     */
+    pub idle_time: f64,
+}
+
+impl RigidBody {
+    pub fn new(mass: f64, restitution: f64, friction: f64) -> RigidBody {
+        RigidBody {
+            mass,
+            restitution,
+            friction,
+            sleeping: false,
+            idle_time: 0.0,
+        }
+    }
+
+    /**
+     * Zero for bodies of infinite or invalid mass
+     */
+    pub fn inverse_mass(&self) -> f64 {
+        if self.mass > 0.0 && self.mass.is_finite() {
+            1.0 / self.mass
+        } else {
+            0.0
+        }
+    }
+
+    pub fn wake(&mut self) {
+        self.sleeping = false;
+        self.idle_time = 0.0;
+    }
+
+    /**
+     * Changes the body's momentum by 'impulse', in kg m/s, waking it up
+     */
+    pub fn apply_impulse(&mut self, velocity: &mut Velocity, impulse: &nalgebra::Vector3<f64>) {
+        self.wake();
+        velocity.0 += impulse * self.inverse_mass();
+    }
+}
+
+impl Component for RigidBody {
+    type Storage = VecStorage<Self>;
+}
@@ -0,0 +1,29 @@
+use specs::prelude::*;
+
+/**
+ * Orientation of the entity, facing -z when it is the identity
+ */
+pub struct Rotation(pub nalgebra::UnitQuaternion<f64>);
+
+impl Rotation {
+    /**
+     * Rotation about the vertical axis by 'yaw' radians, matching the
+     * camera's yaw
+     */
+    pub fn from_yaw(yaw: f64) -> Rotation {
+        Rotation(nalgebra::UnitQuaternion::from_axis_angle(
+            &nalgebra::Vector3::y_axis(),
+            yaw
+        ))
+    }
+}
+
+impl Default for Rotation {
+    fn default() -> Rotation {
+        Rotation(nalgebra::UnitQuaternion::identity())
+    }
+}
+
+impl Component for Rotation {
+    type Storage = VecStorage<Self>;
+}
@@ -0,0 +1,10 @@
+use specs::prelude::*;
+
+/**
+ * Where the entity is moved back to after leaving the world bounds
+ */
+pub struct SpawnPoint(pub nalgebra::Point3<f64>);
+
+impl Component for SpawnPoint {
+    type Storage = VecStorage<Self>;
+}
@@ -0,0 +1,16 @@
+use specs::prelude::*;
+
+/**
+ * How deep a moving entity is in water, 'depth' ranges from 0 when touching
+ * the surface to 1 when fully submerged. Buoyancy and drag are taken from
+ * the water it is in.
+ */
+pub struct Submersion {
+    pub depth: f64,
+    pub buoyancy: f64,
+    pub drag: f64,
+}
+
+impl Component for Submersion {
+    type Storage = VecStorage<Self>;
+}
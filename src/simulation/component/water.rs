@@ -0,0 +1,17 @@
+use specs::prelude::*;
+
+/**
+ * Body of water filling the entity's (trigger) collider up to 'surface',
+ * the world height of the water surface. 'buoyancy' is the upward force on
+ * something fully submerged relative to gravity, 'drag' replaces the
+ * physics drag coefficients underwater.
+ */
+pub struct Water {
+    pub surface: f64,
+    pub buoyancy: f64,
+    pub drag: f64,
+}
+
+impl Component for Water {
+    type Storage = VecStorage<Self>;
+}
@@ -1,12 +1,23 @@
 use uuid::Uuid;
 use eternalreckoning_core::net::operation::Operation;
 
+use super::PhysicsConfig;
+
 pub enum Event {
+    ConfigEvent(ConfigEvent),
     ConnectionEvent(ConnectionEvent),
     InputEvent(InputEvent),
     NetworkEvent(Operation),
 }
 
+#[derive(Clone)]
+pub enum ConfigEvent {
+    Physics(PhysicsConfig),
+    MovementSpeed(f64),
+    MouseSensitivity(f64),
+    ForwardInterpolate(f32),
+}
+
 #[derive(Debug)]
 pub enum ConnectionEvent {
     Connected(Uuid),
@@ -18,16 +29,88 @@ pub enum InputEvent {
     KeyUp(crate::input::InputTypes),
     KeyDown(crate::input::InputTypes),
     CameraAngle(crate::input::MouseEuler),
+    /**
+     * Selects the first entity hit by the ray from 'origin' along
+     * 'direction', such as when clicking on it
+     */
+    Select {
+        origin: nalgebra::Point3<f64>,
+        direction: nalgebra::Vector3<f64>,
+    },
+}
+
+/**
+ * Damage dealt to 'target', applied by the `Damage` system
+ */
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct DamageEvent {
+    pub target: specs::Entity,
+    pub amount: u64,
+    pub source: DamageSource,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum DamageSource {
+    /**
+     * Health lowered by a world update from the server
+     */
+    Server,
+    /**
+     * Dealt by another entity, such as with an ability or a projectile
+     */
+    Entity(specs::Entity),
+}
+
+/**
+ * Raised when a projectile hits 'entity', with the point of contact and the
+ * surface normal there
+ */
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ImpactEvent {
+    pub projectile: specs::Entity,
+    pub owner: Option<specs::Entity>,
+    pub entity: specs::Entity,
+    pub point: nalgebra::Point3<f64>,
+    pub normal: nalgebra::Vector3<f64>,
+}
+
+/**
+ * Raised by collision detection when a collider starts touching a trigger,
+ * on every tick it keeps touching it, and when it stops
+ */
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum TriggerEvent {
+    Enter(TriggerContact),
+    Stay(TriggerContact),
+    Exit(TriggerContact),
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct TriggerContact {
+    pub trigger: specs::Entity,
+    pub other: specs::Entity,
 }
 
 #[derive(Clone)]
 pub enum Update {
     SimulationTick(std::time::Instant),
+    AbilityRequest(AbilityRequest),
+    AbilityUpdate(AbilityUpdate),
+    AliveUpdate(AliveUpdate),
     CameraUpdate(CameraUpdate),
+    ConfigUpdate(ConfigEvent),
+    DamageUpdate(DamageUpdate),
+    DeathUpdate(DeathUpdate),
+    DespawnUpdate(DespawnUpdate),
+    ImpactUpdate(ImpactEvent),
     ModelUpdate(ModelUpdate),
     PositionUpdate(PositionUpdate),
+    RespawnUpdate(RespawnUpdate),
+    RotationUpdate(RotationUpdate),
+    TargetUpdate(TargetUpdate),
     TerrainUpdate(TerrainUpdate),
     TextureUpdate(TextureUpdate),
+    WaterUpdate(WaterUpdate),
 }
 
 #[derive(Clone)]
@@ -54,9 +137,120 @@ pub struct TextureUpdate {
     pub wrap_mode: rendy::resource::WrapMode,
 }
 
+/**
+ * Water surface of a water volume, between the corners 'min' and 'max'
+ * relative to the entity's position
+ */
+#[derive(Clone)]
+pub struct WaterUpdate {
+    pub entity: specs::Entity,
+    pub min: nalgebra::Point3<f32>,
+    pub max: nalgebra::Point3<f32>,
+}
+
 #[derive(Clone)]
 pub struct PositionUpdate {
     pub entity: specs::Entity,
     pub uuid: Option<Uuid>,
     pub position: nalgebra::Point3<f64>,
-}
\ No newline at end of file
+}
+
+#[derive(Clone)]
+pub struct RotationUpdate {
+    pub entity: specs::Entity,
+    pub uuid: Option<Uuid>,
+    pub rotation: nalgebra::UnitQuaternion<f64>,
+}
+
+/**
+ * Sent when an entity is moved back to its spawn point, 'camera' is set if
+ * the view follows it
+ */
+#[derive(Clone)]
+pub struct RespawnUpdate {
+    pub entity: specs::Entity,
+    pub position: nalgebra::Point3<f64>,
+    pub camera: bool,
+}
+
+/**
+ * Sent for every hit taken, with the health left after it. 'camera' is set
+ * if the view follows the entity.
+ */
+#[derive(Clone)]
+pub struct DamageUpdate {
+    pub entity: specs::Entity,
+    pub amount: u64,
+    pub source: DamageSource,
+    pub health: u64,
+    pub max_health: u64,
+    pub camera: bool,
+}
+
+/**
+ * Sent when an entity is removed from the simulation
+ */
+#[derive(Clone)]
+pub struct DespawnUpdate {
+    pub entity: specs::Entity,
+}
+
+#[derive(Clone)]
+pub struct DeathUpdate {
+    pub entity: specs::Entity,
+    pub camera: bool,
+}
+
+/**
+ * Sent when a dead entity comes back without respawning, such as when the
+ * server restores its health
+ */
+#[derive(Clone)]
+pub struct AliveUpdate {
+    pub entity: specs::Entity,
+    pub camera: bool,
+}
+
+/**
+ * Cooldown state of the active character's abilities, in hotbar order
+ */
+#[derive(Clone)]
+pub struct AbilityUpdate {
+    pub entity: specs::Entity,
+    pub abilities: Vec<AbilityState>,
+}
+
+#[derive(Clone)]
+pub struct AbilityState {
+    pub id: String,
+    pub name: String,
+    pub icon: String,
+    pub cooldown: f64,
+    pub remaining: f64,
+}
+
+/**
+ * Asks the server to use 'ability' on behalf of the active character. A
+ * request is sent more than once, always with the same 'sequence', so the
+ * server can tell a resent request from a new one.
+ */
+#[derive(Clone)]
+pub struct AbilityRequest {
+    pub sequence: u64,
+    pub entity: specs::Entity,
+    pub uuid: Option<Uuid>,
+    pub ability: String,
+    pub target: Option<specs::Entity>,
+    pub target_uuid: Option<Uuid>,
+}
+
+/**
+ * Sent when the active character's target changes, or its health does.
+ * 'health' is the current and maximum health of targets that have it.
+ */
+#[derive(Clone)]
+pub struct TargetUpdate {
+    pub target: Option<specs::Entity>,
+    pub name: Option<String>,
+    pub health: Option<(u64, u64)>,
+}
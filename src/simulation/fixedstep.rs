@@ -0,0 +1,116 @@
+use std::time::{
+    Duration,
+    Instant,
+    SystemTime,
+};
+
+use specs::{
+    Dispatcher,
+    World,
+    WorldExt,
+};
+
+use eternalreckoning_core::simulation::TickTime;
+
+use super::event::Event;
+use super::resource::{
+    EventQueue,
+    TickNumber,
+};
+
+/**
+ * Runs the simulation on a fixed timestep: real elapsed time is accumulated
+ * and consumed in steps of exactly 'timestep', so every tick advances the
+ * world by the same amount of simulated time regardless of scheduling jitter.
+ *
+ * If the simulation falls behind, at most 'max_catch_up' ticks are run before
+ * the remaining backlog is dropped, rather than spiralling further behind.
+ */
+pub struct FixedStepSimulation<'a, 'b> {
+    dispatcher: Dispatcher<'a, 'b>,
+    world: World,
+    timestep: Duration,
+    max_catch_up: u32,
+}
+
+impl<'a, 'b> FixedStepSimulation<'a, 'b> {
+    pub fn new(
+        dispatcher: Dispatcher<'a, 'b>,
+        mut world: World,
+        timestep: Duration,
+        max_catch_up: u32,
+    ) -> FixedStepSimulation<'a, 'b>
+    {
+        world.insert(EventQueue::new());
+        world.insert(TickTime(Instant::now()));
+        world.insert(TickNumber::at(SystemTime::now(), timestep));
+
+        FixedStepSimulation {
+            dispatcher,
+            world,
+            timestep,
+            max_catch_up: max_catch_up.max(1),
+        }
+    }
+
+    pub fn run<F>(&mut self, mut poll_event: F) -> Result<(), ()>
+    where
+        F: FnMut() -> Result<Option<Event>, ()>,
+    {
+        let mut accumulator = Duration::from_secs(0);
+        let mut previous = Instant::now();
+
+        loop {
+            let now = Instant::now();
+            accumulator += now - previous;
+            previous = now;
+
+            let mut steps = 0;
+            while accumulator >= self.timestep {
+                if steps >= self.max_catch_up {
+                    log::warn!(
+                        "Simulation falling behind, skipping {} ms",
+                        accumulator.as_millis()
+                    );
+                    // the skipped ticks still pass, to keep in step
+                    // with other machines
+                    let skipped = accumulator.as_nanos() / self.timestep.as_nanos();
+                    self.world.write_resource::<TickNumber>().0 += skipped as u64;
+                    accumulator = Duration::from_nanos(
+                        (accumulator.as_nanos() % self.timestep.as_nanos()) as u64
+                    );
+                    break;
+                }
+
+                self.tick(&mut poll_event)?;
+                accumulator -= self.timestep;
+                steps += 1;
+            }
+
+            if accumulator < self.timestep {
+                std::thread::sleep(self.timestep - accumulator);
+            }
+        }
+    }
+
+    fn tick<F>(&mut self, poll_event: &mut F) -> Result<(), ()>
+    where
+        F: FnMut() -> Result<Option<Event>, ()>,
+    {
+        {
+            let mut events = self.world.write_resource::<EventQueue>();
+            events.clear();
+            while let Some(event) = poll_event()? {
+                events.push(event);
+            }
+        }
+
+        *self.world.write_resource::<TickTime>() = TickTime(Instant::now());
+        self.world.write_resource::<TickNumber>().0 += 1;
+
+        self.dispatcher.dispatch(&self.world);
+        self.world.maintain();
+
+        Ok(())
+    }
+}
@@ -0,0 +1,848 @@
+use failure::{
+    format_err,
+    Error,
+};
+use serde::Deserialize;
+use specs::{
+    Entity,
+    World,
+    WorldExt,
+    world::Builder,
+};
+
+use crate::loaders::{
+    heightmap_from_bmp,
+    trimesh_from_erm,
+};
+use super::component::{
+    collider::{self, Collider},
+    Abilities,
+    ContinuousCollision,
+    PathMode,
+    Health,
+    Hostile,
+    Jump,
+    LocalTransform,
+    Model,
+    Movement,
+    MovingPlatform,
+    Name,
+    NavAgent,
+    Parent,
+    Position,
+    RigidBody,
+    Rotation,
+    SpawnPoint,
+    Terrain,
+    Texture,
+    Velocity,
+    Water,
+};
+use super::collision::Aabb;
+use super::resource::{
+    AbilityRegistry,
+    WorldBounds,
+};
+use super::SimulationConfig;
+
+/**
+ * A level lists its entities as `[[entity]]` tables, each of which may
+ * contain any of the optional components below. The entity flagged with
+ * `player = true` becomes the active character and camera.
+ */
+#[derive(Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct Level {
+    #[serde(default)]
+    pub bounds: BoundsData,
+    #[serde(default, rename = "entity")]
+    pub entities: Vec<EntityData>,
+}
+
+/**
+ * Moving entities leaving the box between 'min' and 'max', or falling below
+ * 'kill-height', are respawned. The player respawns at 'spawn', others where
+ * they started.
+ */
+#[derive(Clone, Default, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct BoundsData {
+    pub min: Option<[f64; 3]>,
+    pub max: Option<[f64; 3]>,
+    pub kill_height: Option<f64>,
+    pub spawn: Option<[f64; 3]>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct EntityData {
+    #[serde(default)]
+    pub player: bool,
+    pub name: Option<String>,
+    /**
+     * Name of the entity this one is attached to, 'position' is then
+     * relative to it
+     */
+    pub parent: Option<String>,
+    pub health: Option<u64>,
+    /**
+     * Lets the player tab to the entity as a target
+     */
+    #[serde(default)]
+    pub hostile: bool,
+    pub position: Option<[f64; 3]>,
+    pub model: Option<ModelData>,
+    pub texture: Option<TextureData>,
+    pub collider: Option<ColliderData>,
+    pub terrain: Option<TerrainData>,
+    /**
+     * Sweeps the entity's motion against other colliders every tick, for
+     * things moving fast enough to pass through them
+     */
+    #[serde(default)]
+    pub fast: bool,
+    pub rigid_body: Option<RigidBodyData>,
+    pub water: Option<WaterData>,
+    pub platform: Option<PlatformData>,
+    pub nav_agent: Option<NavAgentData>,
+    /**
+     * Ids of the abilities on the entity's hotbar, in order
+     */
+    pub abilities: Option<Vec<String>>,
+}
+
+#[derive(Clone, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct ModelData {
+    pub path: String,
+    pub offset: Option<[f32; 3]>,
+}
+
+#[derive(Clone, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct TextureData {
+    pub path: String,
+    #[serde(default)]
+    pub wrap_mode: WrapMode,
+}
+
+#[derive(Clone, Copy, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum WrapMode {
+    Tile,
+    Mirror,
+    Clamp,
+    Border,
+}
+
+/**
+ * 'layer' is the index of the layer the collider is on, 'mask' lists the
+ * indices of the layers it interacts with, all of them if not given
+ */
+#[derive(Clone, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct ColliderData {
+    #[serde(flatten)]
+    pub shape: ShapeData,
+    #[serde(default)]
+    pub layer: u32,
+    pub mask: Option<Vec<u32>>,
+    #[serde(default)]
+    pub trigger: bool,
+}
+
+#[derive(Clone, Deserialize)]
+#[serde(tag = "type", rename_all = "kebab-case")]
+pub enum ShapeData {
+    Sphere { radius: f64 },
+    Plane { normal: [f64; 3] },
+    #[serde(rename = "heightmap")]
+    HeightMap { path: String, scale: f32 },
+    Aabb { half_extents: [f64; 3] },
+    Capsule { radius: f64, half_height: f64 },
+    /**
+     * Triangle mesh of an .erm model, 'offset' should match the model's
+     */
+    Mesh { path: String, offset: Option<[f64; 3]> },
+}
+
+#[derive(Clone, Deserialize)]
+#[serde(default, rename_all = "kebab-case")]
+pub struct RigidBodyData {
+    pub mass: f64,
+    pub restitution: f64,
+    pub friction: f64,
+}
+
+/**
+ * Turns the entity's collider into a body of water, filled up to the world
+ * height 'surface', or the top of the collider if not given
+ */
+#[derive(Clone, Deserialize)]
+#[serde(default, rename_all = "kebab-case")]
+pub struct WaterData {
+    pub surface: Option<f64>,
+    pub buoyancy: f64,
+    pub drag: f64,
+}
+
+/**
+ * Moves the entity through 'waypoints', given relative to its position, at
+ * 'speed' m/s
+ */
+#[derive(Clone, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct PlatformData {
+    pub waypoints: Vec<[f64; 3]>,
+    pub speed: f64,
+    #[serde(default)]
+    pub mode: PathModeData,
+}
+
+/**
+ * Walks the entity around the level at 'speed' m/s, visiting the 'patrol'
+ * points, given relative to its position, in turn
+ */
+#[derive(Clone, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct NavAgentData {
+    pub speed: f64,
+    #[serde(default)]
+    pub patrol: Vec<[f64; 3]>,
+}
+
+#[derive(Clone, Copy, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum PathModeData {
+    Loop,
+    PingPong,
+}
+
+#[derive(Clone, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct TerrainData {
+    pub path: String,
+    pub scale: f32,
+}
+
+impl Level {
+    /**
+     * Creates the level's entities, returning the player entity if any
+     */
+    pub fn spawn(&self, world: &mut World, config: &SimulationConfig)
+        -> Result<Option<Entity>, Error>
+    {
+        let mut player = None;
+        let mut names = std::collections::HashMap::new();
+        let mut children = Vec::new();
+
+        world.insert(self.bounds.to_resource());
+
+        for data in &self.entities {
+            let entity = data.spawn(world, config, self.bounds.spawn)?;
+
+            if let Some(ref name) = data.name {
+                names.insert(name.clone(), entity);
+            }
+            if let Some(ref parent) = data.parent {
+                children.push((entity, parent, data.position.unwrap_or([0.0, 0.0, 0.0])));
+            }
+
+            if data.player {
+                if player.is_some() {
+                    log::warn!("Level has more than one player entity, using the first");
+                } else {
+                    player = Some(entity);
+                }
+            }
+        }
+
+        // parents may come after their children in the level
+        for (entity, parent, offset) in children {
+            let parent = *names.get(parent)
+                .ok_or_else(|| format_err!("no entity named {} to attach to", parent))?;
+
+            world.write_storage::<Parent>().insert(entity, Parent(parent))?;
+            world.write_storage::<LocalTransform>().insert(entity, LocalTransform::new(offset.into()))?;
+        }
+
+        Ok(player)
+    }
+}
+
+impl EntityData {
+    fn spawn(&self, world: &mut World, config: &SimulationConfig, player_spawn: Option<[f64; 3]>)
+        -> Result<Entity, Error>
+    {
+        // colliders are built first, as loading a heightmap may fail
+        let mut collider = match self.collider {
+            Some(ref collider) => Some(collider.to_component()?),
+            None => None,
+        };
+
+        let water = match self.water {
+            Some(ref water) => {
+                let collider = collider.as_mut()
+                    .ok_or_else(|| format_err!("water volume without a collider"))?;
+                collider.trigger = true;
+
+                let position = self.position.unwrap_or([0.0, 0.0, 0.0]).into();
+                Some(water.to_component(collider, &position)?)
+            },
+            None => None,
+        };
+
+        let platform = match self.platform {
+            Some(ref platform) => {
+                let position = self.position.unwrap_or([0.0, 0.0, 0.0]).into();
+                Some(platform.to_component(&position)?)
+            },
+            None => None,
+        };
+
+        let agent = match self.nav_agent {
+            Some(ref agent) => {
+                let position = self.position.unwrap_or([0.0, 0.0, 0.0]).into();
+                Some(agent.to_component(&position)?)
+            },
+            None => None,
+        };
+
+        if let (Some(abilities), Some(registry)) = (&self.abilities, world.try_fetch::<AbilityRegistry>()) {
+            if let Some(unknown) = abilities.iter().find(|id| registry.get(id).is_none()) {
+                return Err(format_err!("unknown ability: {}", unknown));
+            }
+        }
+
+        let mut builder = world.create_entity();
+
+        if let Some(ref name) = self.name {
+            builder = builder.with(Name(name.clone()));
+        }
+        if let Some(health) = self.health {
+            builder = builder.with(Health::new(health));
+        }
+        if self.hostile {
+            builder = builder.with(Hostile);
+        }
+        if let Some(ref abilities) = self.abilities {
+            builder = builder.with(Abilities::new(abilities));
+        }
+        // platforms start from their first waypoint
+        match platform {
+            Some(ref platform) => builder = builder.with(Position(platform.position_at(0.0))),
+            None => if let Some(position) = self.position {
+                builder = builder.with(Position(position.into()));
+            },
+        }
+        if let Some(ref model) = self.model {
+            builder = builder.with(model.to_component());
+        }
+        if let Some(ref texture) = self.texture {
+            builder = builder.with(texture.to_component());
+        }
+        if let Some(collider) = collider {
+            builder = builder.with(collider);
+        }
+        if let Some(ref terrain) = self.terrain {
+            builder = builder.with(Terrain::new(&terrain.path, terrain.scale));
+        }
+        if let Some(water) = water {
+            builder = builder.with(water);
+        }
+        if let Some(platform) = platform {
+            builder = builder.with(platform);
+        }
+        if let Some(agent) = agent {
+            builder = builder.with(agent);
+            // players already fall and turn
+            if !self.player && self.rigid_body.is_none() {
+                builder = builder
+                    .with(Velocity(nalgebra::Vector3::new(0.0, 0.0, 0.0)))
+                    .with(Rotation::default());
+            }
+        }
+        if self.fast {
+            builder = builder.with(ContinuousCollision::default());
+        }
+
+        // moving entities are respawned after leaving the world bounds
+        if let Some(position) = self.position {
+            if self.player || self.rigid_body.is_some() {
+                let spawn = match player_spawn {
+                    Some(spawn) if self.player => spawn,
+                    _ => position,
+                };
+                builder = builder.with(SpawnPoint(spawn.into()));
+            }
+        }
+
+        if let Some(ref body) = self.rigid_body {
+            builder = builder.with(body.to_component());
+            if !self.player {
+                builder = builder.with(Velocity(nalgebra::Vector3::new(0.0, 0.0, 0.0)));
+            }
+        }
+
+        if self.player {
+            builder = builder
+                .with(Velocity(nalgebra::Vector3::new(0.0, 0.0, 0.0)))
+                .with(Rotation::default())
+                .with(Movement {
+                    sprint_speed: config.sprint_speed,
+                    crouch_speed: config.crouch_speed,
+                    swim_speed: config.swim_speed,
+                    air_control: config.air_control,
+                    step_height: config.step_height,
+                    ..Movement::new(config.movement_speed)
+                })
+                .with(Jump {
+                    coyote_time: config.coyote_time,
+                    buffer_time: config.jump_buffer_time,
+                    ..Jump::new(config.jump_force)
+                });
+        }
+
+        Ok(builder.build())
+    }
+}
+
+impl BoundsData {
+    pub fn to_resource(&self) -> WorldBounds {
+        let mut bounds = Aabb::infinite();
+        if let Some(min) = self.min {
+            bounds.min = min.into();
+        }
+        if let Some(max) = self.max {
+            bounds.max = max.into();
+        }
+        if let Some(kill_height) = self.kill_height {
+            // down is +y
+            bounds.max.y = bounds.max.y.min(kill_height);
+        }
+
+        WorldBounds(bounds)
+    }
+}
+
+impl ModelData {
+    pub fn to_component(&self) -> Model {
+        Model {
+            path: self.path.clone(),
+            offset: self.offset.map(|offset| offset.into()),
+        }
+    }
+}
+
+impl TextureData {
+    pub fn to_component(&self) -> Texture {
+        Texture {
+            path: self.path.clone(),
+            wrap_mode: match self.wrap_mode {
+                WrapMode::Tile => rendy::resource::WrapMode::Tile,
+                WrapMode::Mirror => rendy::resource::WrapMode::Mirror,
+                WrapMode::Clamp => rendy::resource::WrapMode::Clamp,
+                WrapMode::Border => rendy::resource::WrapMode::Border,
+            },
+        }
+    }
+}
+
+impl RigidBodyData {
+    pub fn to_component(&self) -> RigidBody {
+        RigidBody::new(self.mass, self.restitution, self.friction)
+    }
+}
+
+impl WaterData {
+    pub fn to_component(&self, collider: &Collider, position: &nalgebra::Point3<f64>)
+        -> Result<Water, Error>
+    {
+        // up is -y, so the top of the collider is its minimum
+        let surface = match self.surface {
+            Some(surface) => surface,
+            None => collider.collider.bounds(position).min.y,
+        };
+        if !surface.is_finite() {
+            return Err(format_err!("water volume needs a surface height"));
+        }
+
+        Ok(Water {
+            surface,
+            buoyancy: self.buoyancy,
+            drag: self.drag,
+        })
+    }
+}
+
+impl PlatformData {
+    pub fn to_component(&self, position: &nalgebra::Point3<f64>) -> Result<MovingPlatform, Error> {
+        if self.waypoints.len() < 2 {
+            return Err(format_err!("moving platform needs at least two waypoints"));
+        }
+        if !self.speed.is_finite() || self.speed < 0.0 {
+            return Err(format_err!("invalid moving platform speed: {}", self.speed));
+        }
+
+        let waypoints = self.waypoints.iter()
+            .map(|waypoint| position + nalgebra::Vector3::from(*waypoint))
+            .collect();
+        let mode = match self.mode {
+            PathModeData::Loop => PathMode::Loop,
+            PathModeData::PingPong => PathMode::PingPong,
+        };
+
+        Ok(MovingPlatform::new(waypoints, self.speed, mode))
+    }
+}
+
+impl NavAgentData {
+    pub fn to_component(&self, position: &nalgebra::Point3<f64>) -> Result<NavAgent, Error> {
+        if !self.speed.is_finite() || self.speed < 0.0 {
+            return Err(format_err!("invalid nav agent speed: {}", self.speed));
+        }
+
+        Ok(NavAgent {
+            patrol: self.patrol.iter()
+                .map(|point| position + nalgebra::Vector3::from(*point))
+                .collect(),
+            ..NavAgent::new(self.speed)
+        })
+    }
+}
+
+impl Default for PathModeData {
+    fn default() -> PathModeData {
+        PathModeData::Loop
+    }
+}
+
+impl Default for WaterData {
+    fn default() -> WaterData {
+        WaterData {
+            surface: None,
+            buoyancy: 1.2,
+            drag: 3.0,
+        }
+    }
+}
+
+impl Default for RigidBodyData {
+    fn default() -> RigidBodyData {
+        RigidBodyData {
+            mass: 1.0,
+            restitution: 0.2,
+            friction: 0.5,
+        }
+    }
+}
+
+impl Default for WrapMode {
+    fn default() -> WrapMode {
+        WrapMode::Clamp
+    }
+}
+
+impl ColliderData {
+    pub fn to_component(&self) -> Result<Collider, Error> {
+        let shape = match &self.shape {
+            ShapeData::Sphere { radius } => {
+                collider::ColliderType::Sphere(*radius)
+            },
+            ShapeData::Plane { normal } => {
+                collider::ColliderType::Plane(nalgebra::Unit::new_normalize(
+                    (*normal).into()
+                ))
+            },
+            ShapeData::HeightMap { path, scale } => {
+                collider::ColliderType::HeightMap(heightmap_from_bmp(path, *scale)?)
+            },
+            ShapeData::Aabb { half_extents } => {
+                collider::ColliderType::Aabb((*half_extents).into())
+            },
+            ShapeData::Capsule { radius, half_height } => {
+                collider::ColliderType::Capsule {
+                    radius: *radius,
+                    half_height: *half_height,
+                }
+            },
+            ShapeData::Mesh { path, offset } => {
+                let offset = offset.unwrap_or([0.0, 0.0, 0.0]);
+                collider::ColliderType::TriMesh(trimesh_from_erm(path, &offset.into())?)
+            },
+        };
+
+        if self.layer >= 32 {
+            return Err(format_err!("collider layer {} out of range", self.layer));
+        }
+
+        let mut collider = Collider::new(shape);
+        collider.layer = 1 << self.layer;
+        if let Some(ref mask) = self.mask {
+            collider.mask = 0;
+            for &layer in mask {
+                if layer >= 32 {
+                    return Err(format_err!("collider mask layer {} out of range", layer));
+                }
+                collider.mask |= 1 << layer;
+            }
+        }
+        collider.trigger = self.trigger;
+
+        Ok(collider)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use specs::Join;
+
+    use super::*;
+    use crate::simulation::register_components;
+
+    fn spawn(level: &str) -> (World, Option<Entity>) {
+        let level: Level = toml::from_str(level).unwrap();
+
+        let mut world = World::new();
+        register_components(&mut world);
+        let player = level.spawn(&mut world, &SimulationConfig::default()).unwrap();
+
+        (world, player)
+    }
+
+    #[test]
+    fn test_spawn_level() {
+        let (world, player) = spawn(r#"
+            [[entity]]
+            name = "Player"
+            player = true
+            health = 100
+            position = [0.0, -1.0, 0.0]
+            collider = { type = "sphere", radius = 1.0 }
+            model = { path = "assets/marker.erm", offset = [0.0, 1.0, 0.0] }
+
+            [[entity]]
+            position = [-8.0, -1.1, 16.0]
+            collider = { type = "plane", normal = [0.0, -2.0, 0.0] }
+            texture = { path = "assets/pillar.png", wrap-mode = "tile" }
+        "#);
+        let player = player.unwrap();
+
+        assert_eq!(world.read_storage::<Health>().get(player).unwrap().current, 100);
+        assert!(world.read_storage::<Movement>().get(player).is_some());
+        assert!(world.read_storage::<Rotation>().get(player).is_some());
+        assert_eq!(
+            world.read_storage::<Model>().get(player).unwrap().offset,
+            Some(nalgebra::Vector3::new(0.0, 1.0, 0.0))
+        );
+
+        assert_eq!(world.read_storage::<Position>().join().count(), 2);
+        assert_eq!(world.read_storage::<Movement>().join().count(), 1);
+
+        let textures = world.read_storage::<Texture>();
+        let texture = textures.join().next().unwrap();
+        assert_eq!(texture.wrap_mode, rendy::resource::WrapMode::Tile);
+
+        let colliders = world.read_storage::<Collider>();
+        for collider in colliders.join() {
+            if let collider::ColliderType::Plane(normal) = &collider.collider {
+                assert_eq!(normal.y, -1.0);
+            }
+        }
+    }
+
+    #[test]
+    fn test_parent() {
+        // attached to the player, which comes after it
+        let (world, player) = spawn(r#"
+            [[entity]]
+            parent = "Player"
+            position = [0.5, -1.0, 0.0]
+
+            [[entity]]
+            name = "Player"
+            player = true
+            position = [0.0, -1.0, 0.0]
+        "#);
+
+        let parents = world.read_storage::<Parent>();
+        let (child, parent) = (&world.entities(), &parents).join().next().unwrap();
+        assert_eq!(Some(parent.0), player);
+        assert_eq!(
+            world.read_storage::<LocalTransform>().get(child).unwrap().translation,
+            nalgebra::Vector3::new(0.5, -1.0, 0.0)
+        );
+    }
+
+    #[test]
+    fn test_collider_layers() {
+        let (world, _) = spawn(r#"
+            [[entity]]
+            position = [0.0, 0.0, 8.0]
+            collider = { type = "sphere", radius = 4.0, trigger = true, layer = 2, mask = [0, 1] }
+
+            [[entity]]
+            collider = { type = "sphere", radius = 1.0 }
+        "#);
+
+        let colliders = world.read_storage::<Collider>();
+        let mut colliders = colliders.join().collect::<Vec<_>>();
+        colliders.sort_by_key(|collider| !collider.trigger);
+
+        assert_eq!(colliders[0].layer, 0b100);
+        assert_eq!(colliders[0].mask, 0b11);
+        assert!(!colliders[1].trigger);
+        assert_eq!(colliders[1].layer, collider::DEFAULT_LAYER);
+        assert_eq!(colliders[1].mask, collider::ALL_LAYERS);
+    }
+
+    #[test]
+    fn test_rigid_body() {
+        let (world, _) = spawn(r#"
+            [[entity]]
+            position = [4.0, -1.0, 4.0]
+            collider = { type = "aabb", half_extents = [0.5, 0.5, 0.5] }
+            rigid-body = { mass = 20.0 }
+        "#);
+
+        let bodies = world.read_storage::<RigidBody>();
+        let (ent, body) = (&world.entities(), &bodies).join().next().unwrap();
+        assert_eq!(body.mass, 20.0);
+        assert_eq!(body.friction, 0.5);
+        assert!(world.read_storage::<Velocity>().contains(ent));
+        assert_eq!(
+            world.read_storage::<SpawnPoint>().get(ent).unwrap().0,
+            nalgebra::Point3::new(4.0, -1.0, 4.0)
+        );
+    }
+
+    #[test]
+    fn test_water() {
+        let (world, _) = spawn(r#"
+            [[entity]]
+            position = [0.0, 3.0, 0.0]
+            collider = { type = "aabb", half_extents = [20.0, 2.0, 20.0] }
+            water = { buoyancy = 1.5 }
+        "#);
+
+        let water = world.read_storage::<Water>();
+        let water = water.join().next().unwrap();
+        assert_eq!(water.surface, 1.0);
+        assert_eq!(water.buoyancy, 1.5);
+        assert_eq!(water.drag, 3.0);
+
+        let colliders = world.read_storage::<Collider>();
+        assert!(colliders.join().next().unwrap().trigger);
+    }
+
+    #[test]
+    fn test_platform() {
+        let (world, _) = spawn(r#"
+            [[entity]]
+            position = [10.0, 0.0, 0.0]
+            platform = { waypoints = [[0.0, -1.0, 0.0], [5.0, -1.0, 0.0]], speed = 2.0, mode = "ping-pong" }
+        "#);
+
+        let platforms = world.read_storage::<MovingPlatform>();
+        let positions = world.read_storage::<Position>();
+        let (platform, position) = (&platforms, &positions).join().next().unwrap();
+        assert_eq!(platform.mode, PathMode::PingPong);
+        assert_eq!(platform.waypoints[1], nalgebra::Point3::new(15.0, -1.0, 0.0));
+        assert_eq!(position.0, nalgebra::Point3::new(10.0, -1.0, 0.0));
+    }
+
+    #[test]
+    fn test_nav_agent() {
+        let (world, _) = spawn(r#"
+            [[entity]]
+            position = [-6.0, -1.0, 0.0]
+            collider = { type = "sphere", radius = 1.0 }
+            nav-agent = { speed = 3.0, patrol = [[0.0, 0.0, 0.0], [0.0, 0.0, 10.0]] }
+        "#);
+
+        let agents = world.read_storage::<NavAgent>();
+        let (ent, agent) = (&world.entities(), &agents).join().next().unwrap();
+        assert_eq!(agent.speed, 3.0);
+        assert_eq!(agent.patrol[1], nalgebra::Point3::new(-6.0, -1.0, 10.0));
+        assert!(agent.destination.is_none());
+        assert!(world.read_storage::<Velocity>().contains(ent));
+        assert!(world.read_storage::<Rotation>().contains(ent));
+    }
+
+    #[test]
+    fn test_abilities() {
+        let level: Level = toml::from_str(r#"
+            [[entity]]
+            abilities = ["attack"]
+        "#).unwrap();
+
+        let registry: AbilityRegistry = toml::from_str(r#"
+            [ability.attack]
+            name = "Attack"
+            icon = "assets/icon_attack.png"
+        "#).unwrap();
+
+        let mut world = World::new();
+        register_components(&mut world);
+        world.insert(registry);
+        level.spawn(&mut world, &SimulationConfig::default()).unwrap();
+
+        let abilities = world.read_storage::<Abilities>();
+        let abilities = abilities.join().next().unwrap();
+        assert_eq!(abilities.slots.len(), 1);
+        assert_eq!(abilities.slots[0].ability, "attack");
+    }
+
+    #[test]
+    fn test_bounds() {
+        let (world, player) = spawn(r#"
+            [bounds]
+            kill-height = 50.0
+            spawn = [0.0, -2.0, 0.0]
+
+            [[entity]]
+            player = true
+            position = [0.0, -1.0, 0.0]
+        "#);
+
+        let bounds = world.read_resource::<WorldBounds>();
+        assert_eq!(bounds.0.max.y, 50.0);
+        assert_eq!(bounds.0.min.y, f64::NEG_INFINITY);
+
+        let spawns = world.read_storage::<SpawnPoint>();
+        assert_eq!(spawns.get(player.unwrap()).unwrap().0, nalgebra::Point3::new(0.0, -2.0, 0.0));
+    }
+
+    #[test]
+    fn test_unknown_parent() {
+        let level: Level = toml::from_str(r#"
+            [[entity]]
+            parent = "Nobody"
+        "#).unwrap();
+
+        let mut world = World::new();
+        register_components(&mut world);
+
+        assert!(level.spawn(&mut world, &SimulationConfig::default()).is_err());
+    }
+
+    #[test]
+    fn test_unknown_ability() {
+        let level: Level = toml::from_str(r#"
+            [[entity]]
+            abilities = ["attack", "fireball"]
+        "#).unwrap();
+
+        let registry: AbilityRegistry = toml::from_str(r#"
+            [ability.attack]
+            name = "Attack"
+            icon = "assets/icon_attack.png"
+        "#).unwrap();
+
+        let mut world = World::new();
+        register_components(&mut world);
+        world.insert(registry);
+
+        assert!(level.spawn(&mut world, &SimulationConfig::default()).is_err());
+    }
+}
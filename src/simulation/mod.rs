@@ -1,12 +1,18 @@
+pub mod collision;
 pub mod component;
 pub mod event;
+pub mod level;
+pub mod navigation;
 pub mod resource;
 pub mod system;
+mod fixedstep;
 mod simulation;
 mod physicsconfig;
 
 pub use simulation::{
     build_simulation,
+    register_components,
     SimulationConfig,
 };
+pub use fixedstep::FixedStepSimulation;
 pub use physicsconfig::PhysicsConfig;
\ No newline at end of file
@@ -0,0 +1,8 @@
+mod navgrid;
+mod pathfinding;
+
+pub use navgrid::NavGrid;
+pub use pathfinding::{
+    find_path,
+    smooth_path,
+};
\ No newline at end of file
@@ -0,0 +1,300 @@
+use specs::{
+    Join,
+    World,
+    WorldExt,
+};
+
+use crate::display::terrain::HeightMap;
+use crate::simulation::{
+    collision::Aabb,
+    component::{
+        collider::ColliderType,
+        Collider,
+        MovingPlatform,
+        Position,
+        Velocity,
+    },
+};
+
+/**
+ * Clearance kept from obstacles and the headroom needed under them, in m
+ */
+const AGENT_RADIUS: f64 = 1.0;
+const AGENT_HEIGHT: f64 = 2.0;
+
+/**
+ * How finely straight lines are checked for walkability, in cells
+ */
+const LINE_STEP: f64 = 0.25;
+
+/**
+ * Walkable cells of the level's terrain, one per heightmap cell. Cells are
+ * walkable if their slope can be stood on and no static collider is in the
+ * way of an agent standing in them.
+ */
+pub struct NavGrid {
+    origin: nalgebra::Point3<f64>,
+    size: usize,
+    /**
+     * Ground height of each walkable cell, None for blocked ones
+     */
+    cells: Vec<Option<f64>>,
+}
+
+impl NavGrid {
+    /**
+     * Grid over the heightmap at 'position'. 'min_ground_y' is the smallest
+     * upwards component of a walkable ground normal, obstacles lower than
+     * 'step_height' are stepped over.
+     */
+    pub fn new<I>(
+        position: &nalgebra::Point3<f64>,
+        map: &HeightMap,
+        min_ground_y: f64,
+        step_height: f64,
+        obstacles: I,
+    ) -> NavGrid
+    where
+        I: IntoIterator<Item = Aabb>,
+    {
+        let size = map.size.saturating_sub(1);
+        let mut cells = Vec::with_capacity(size * size);
+
+        for z in 0..size {
+            for x in 0..size {
+                let (cx, cz) = (x as f32 + 0.5, z as f32 + 0.5);
+                // heights grow towards negative y, as does the normal
+                let cell = match (map.height_at(cx, cz), map.normal_at(cx, cz)) {
+                    (Some(height), Some(normal)) if -normal.y as f64 >= min_ground_y => {
+                        Some(position.y - height as f64)
+                    },
+                    _ => None,
+                };
+                cells.push(cell);
+            }
+        }
+
+        let mut grid = NavGrid {
+            origin: *position,
+            size,
+            cells,
+        };
+
+        for obstacle in obstacles {
+            grid.block(&obstacle, step_height);
+        }
+
+        grid
+    }
+
+    /**
+     * Grid over the first heightmap collider in 'world', blocked by the
+     * static colliders. Empty if the level has no terrain.
+     */
+    pub fn from_world(world: &World, max_ground_slope: f64, step_height: f64) -> NavGrid {
+        let positions = world.read_storage::<Position>();
+        let colliders = world.read_storage::<Collider>();
+        let velocities = world.read_storage::<Velocity>();
+        let platforms = world.read_storage::<MovingPlatform>();
+
+        let mut terrain = (&positions, &colliders).join()
+            .filter_map(|(position, collider)| match collider.collider {
+                ColliderType::HeightMap(ref map) => Some((position, map)),
+                _ => None,
+            });
+
+        let (position, map) = match terrain.next() {
+            Some(terrain) => terrain,
+            None => {
+                log::info!("Level has no terrain to navigate");
+                return NavGrid::default();
+            },
+        };
+        if terrain.next().is_some() {
+            log::warn!("Level has more than one heightmap, navigating the first");
+        }
+
+        // anything that moves is steered around as it goes instead
+        let obstacles = (&positions, &colliders, !&velocities, !&platforms).join()
+            .filter(|(_, collider, ..)| !collider.trigger)
+            .filter(|(_, collider, ..)| {
+                !matches!(collider.collider, ColliderType::Plane(_) | ColliderType::HeightMap(_))
+            })
+            .map(|(position, collider, ..)| collider.collider.bounds(&position.0))
+            .filter(Aabb::is_finite);
+
+        NavGrid::new(&position.0, map, 1.0 - max_ground_slope, step_height, obstacles)
+    }
+
+    /**
+     * Cell containing the point, None outside the grid
+     */
+    pub fn cell_at(&self, point: &nalgebra::Point3<f64>) -> Option<(usize, usize)> {
+        let x = (point.x - self.origin.x).floor();
+        let z = (point.z - self.origin.z).floor();
+        let size = self.size as f64;
+        if !(0.0..size).contains(&x) || !(0.0..size).contains(&z) {
+            return None;
+        }
+
+        Some((x as usize, z as usize))
+    }
+
+    /**
+     * Ground height of the cell, None if it is blocked or outside the grid
+     */
+    pub fn ground(&self, x: usize, z: usize) -> Option<f64> {
+        if x >= self.size || z >= self.size {
+            return None;
+        }
+
+        self.cells[x + z * self.size]
+    }
+
+    pub fn is_walkable(&self, x: usize, z: usize) -> bool {
+        self.ground(x, z).is_some()
+    }
+
+    /**
+     * Centre of the cell on the ground, None if it is blocked
+     */
+    pub fn center(&self, x: usize, z: usize) -> Option<nalgebra::Point3<f64>> {
+        self.ground(x, z).map(|y| nalgebra::Point3::new(
+            self.origin.x + x as f64 + 0.5,
+            y,
+            self.origin.z + z as f64 + 0.5,
+        ))
+    }
+
+    pub fn size(&self) -> usize {
+        self.size
+    }
+
+    /**
+     * Whether the straight line from 'from' to 'to' only crosses walkable
+     * cells, not counting the cell 'from' is in
+     */
+    pub fn line_walkable(&self, from: &nalgebra::Point3<f64>, to: &nalgebra::Point3<f64>) -> bool {
+        let start = self.cell_at(from);
+        let offset = nalgebra::Vector3::new(to.x - from.x, 0.0, to.z - from.z);
+        let steps = (offset.norm() / LINE_STEP).ceil().max(1.0) as usize;
+
+        (1..=steps).all(|step| {
+            let point = from + offset * (step as f64 / steps as f64);
+            match self.cell_at(&point) {
+                Some(cell) if Some(cell) == start => true,
+                Some((x, z)) => self.is_walkable(x, z),
+                None => false,
+            }
+        })
+    }
+
+    /**
+     * Blocks the cells where an agent standing on the ground would overlap
+     * 'obstacle'
+     */
+    fn block(&mut self, obstacle: &Aabb, step_height: f64) {
+        let min_x = obstacle.min.x - AGENT_RADIUS - self.origin.x;
+        let max_x = obstacle.max.x + AGENT_RADIUS - self.origin.x;
+        let min_z = obstacle.min.z - AGENT_RADIUS - self.origin.z;
+        let max_z = obstacle.max.z + AGENT_RADIUS - self.origin.z;
+
+        let last = self.size as f64 - 1.0;
+        if self.size == 0 || max_x < 0.0 || max_z < 0.0 || min_x > last + 1.0 || min_z > last + 1.0 {
+            return;
+        }
+
+        // cells whose centre is within the inflated bounds
+        let first_x = (min_x - 0.5).ceil().max(0.0) as usize;
+        let last_x = (max_x - 0.5).floor().min(last);
+        let first_z = (min_z - 0.5).ceil().max(0.0) as usize;
+        let last_z = (max_z - 0.5).floor().min(last);
+        if last_x < 0.0 || last_z < 0.0 {
+            return;
+        }
+
+        for z in first_z..=last_z as usize {
+            for x in first_x..=last_x as usize {
+                let cell = &mut self.cells[x + z * self.size];
+
+                // up is -y: the agent takes up the space from the ground to
+                // AGENT_HEIGHT above it, and steps over what is below the
+                // step height
+                let blocked = match cell {
+                    Some(ground) => {
+                        obstacle.min.y < *ground - step_height &&
+                        obstacle.max.y > *ground - AGENT_HEIGHT
+                    },
+                    None => false,
+                };
+                if blocked {
+                    *cell = None;
+                }
+            }
+        }
+    }
+}
+
+impl Default for NavGrid {
+    fn default() -> NavGrid {
+        NavGrid {
+            origin: nalgebra::Point3::origin(),
+            size: 0,
+            cells: Vec::new(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_slopes_and_obstacles() {
+        // flat apart from a steep ridge along x = 6
+        let mut data = vec![0.0; 100];
+        for z in 0..10 {
+            data[6 + z * 10] = 5.0;
+        }
+        let map = HeightMap::new(data, 10, 1.0);
+
+        let position = nalgebra::Point3::new(-5.0, 2.0, -5.0);
+        let pillar = Aabb::from_center(
+            &nalgebra::Point3::new(-3.5, 1.0, -3.5),
+            &nalgebra::Vector3::new(0.5, 2.0, 0.5),
+        );
+        let pebble = Aabb::from_center(
+            &nalgebra::Point3::new(-1.5, 1.9, 1.5),
+            &nalgebra::Vector3::new(0.5, 0.1, 0.5),
+        );
+        let grid = NavGrid::new(&position, &map, 0.8, 0.5, vec![pillar, pebble]);
+
+        assert_eq!(grid.size(), 9);
+        assert_eq!(grid.ground(4, 4), Some(2.0));
+        assert_eq!(grid.cell_at(&nalgebra::Point3::new(-4.5, 0.0, 3.9)), Some((0, 8)));
+        assert_eq!(grid.cell_at(&nalgebra::Point3::new(4.5, 0.0, 0.0)), None);
+
+        // both sides of the ridge are too steep
+        assert!(!grid.is_walkable(5, 4));
+        assert!(!grid.is_walkable(6, 4));
+        assert!(grid.is_walkable(4, 4));
+        assert!(grid.is_walkable(7, 4));
+
+        // the pillar and the agent's radius around it are blocked
+        for (x, z) in &[(0, 0), (1, 1), (2, 2), (0, 2)] {
+            assert!(!grid.is_walkable(*x, *z), "{}, {}", x, z);
+        }
+        assert!(grid.is_walkable(3, 3));
+        // the pebble is stepped over
+        assert!(grid.is_walkable(3, 6));
+
+        assert!(grid.line_walkable(
+            &nalgebra::Point3::new(-4.5, 2.0, 3.5),
+            &nalgebra::Point3::new(-0.5, 2.0, 3.5),
+        ));
+        assert!(!grid.line_walkable(
+            &nalgebra::Point3::new(-4.5, 2.0, 3.5),
+            &nalgebra::Point3::new(3.5, 2.0, 3.5),
+        ));
+    }
+}
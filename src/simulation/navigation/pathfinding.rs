@@ -0,0 +1,222 @@
+use std::cmp::Ordering;
+use std::collections::BinaryHeap;
+
+use super::NavGrid;
+
+const NEIGHBOURS: [(isize, isize); 8] = [
+    (1, 0),
+    (-1, 0),
+    (0, 1),
+    (0, -1),
+    (1, 1),
+    (1, -1),
+    (-1, 1),
+    (-1, -1),
+];
+
+/**
+ * Cell on the A* open list, ordered so that the lowest estimated total cost
+ * comes out of the heap first
+ */
+struct OpenCell {
+    estimate: f64,
+    cell: usize,
+}
+
+impl PartialEq for OpenCell {
+    fn eq(&self, other: &OpenCell) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for OpenCell {}
+
+impl PartialOrd for OpenCell {
+    fn partial_cmp(&self, other: &OpenCell) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for OpenCell {
+    fn cmp(&self, other: &OpenCell) -> Ordering {
+        other.estimate.partial_cmp(&self.estimate)
+            .unwrap_or(Ordering::Equal)
+            .then_with(|| other.cell.cmp(&self.cell))
+    }
+}
+
+/**
+ * Shortest path over walkable cells from 'from' to 'to', smoothed into as
+ * few straight lines as the grid allows. The path leaves out 'from' and ends
+ * at 'to' on the ground. None if 'to' cannot be reached.
+ */
+pub fn find_path(grid: &NavGrid, from: &nalgebra::Point3<f64>, to: &nalgebra::Point3<f64>)
+    -> Option<Vec<nalgebra::Point3<f64>>>
+{
+    let (start_x, start_z) = grid.cell_at(from)?;
+    let (goal_x, goal_z) = grid.cell_at(to)?;
+    let goal_ground = grid.ground(goal_x, goal_z)?;
+
+    let size = grid.size();
+    let index = |x: usize, z: usize| x + z * size;
+    let start = index(start_x, start_z);
+    let goal = index(goal_x, goal_z);
+
+    // octile distance, exact on an open grid
+    let heuristic = |cell: usize| {
+        let dx = (cell % size) as f64 - goal_x as f64;
+        let dz = (cell / size) as f64 - goal_z as f64;
+        let (dx, dz) = (dx.abs(), dz.abs());
+        dx.max(dz) + (std::f64::consts::SQRT_2 - 1.0) * dx.min(dz)
+    };
+
+    let mut costs = vec![f64::INFINITY; size * size];
+    let mut came_from = vec![None; size * size];
+    let mut open = BinaryHeap::new();
+
+    // agents may start in a blocked cell, having been pushed into it
+    costs[start] = 0.0;
+    open.push(OpenCell { estimate: heuristic(start), cell: start });
+
+    while let Some(OpenCell { estimate, cell }) = open.pop() {
+        if cell == goal {
+            break;
+        }
+        if estimate > costs[cell] + heuristic(cell) {
+            continue;
+        }
+
+        let (x, z) = ((cell % size) as isize, (cell / size) as isize);
+        for (dx, dz) in &NEIGHBOURS {
+            let (nx, nz) = (x + dx, z + dz);
+            if nx < 0 || nz < 0 || !grid.is_walkable(nx as usize, nz as usize) {
+                continue;
+            }
+            // diagonals do not cut corners
+            if *dx != 0 && *dz != 0 && (
+                !grid.is_walkable(nx as usize, z as usize) ||
+                !grid.is_walkable(x as usize, nz as usize)
+            ) {
+                continue;
+            }
+
+            let next = index(nx as usize, nz as usize);
+            let step = if *dx != 0 && *dz != 0 { std::f64::consts::SQRT_2 } else { 1.0 };
+            let cost = costs[cell] + step;
+            if cost < costs[next] {
+                costs[next] = cost;
+                came_from[next] = Some(cell);
+                open.push(OpenCell { estimate: cost + heuristic(next), cell: next });
+            }
+        }
+    }
+
+    if !costs[goal].is_finite() {
+        return None;
+    }
+
+    let mut cells = vec![goal];
+    while let Some(previous) = came_from[*cells.last().unwrap()] {
+        cells.push(previous);
+    }
+    cells.reverse();
+
+    // the ends are the points themselves rather than their cells' centres
+    let mut points = vec![*from];
+    points.extend(
+        cells.iter()
+            .skip(1)
+            .take(cells.len().saturating_sub(2))
+            .filter_map(|cell| grid.center(cell % size, cell / size))
+    );
+    points.push(nalgebra::Point3::new(to.x, goal_ground, to.z));
+
+    let mut path = smooth_path(grid, &points);
+    path.remove(0);
+    Some(path)
+}
+
+/**
+ * Drops the points that can be skipped by walking straight to a later one
+ */
+pub fn smooth_path(grid: &NavGrid, points: &[nalgebra::Point3<f64>]) -> Vec<nalgebra::Point3<f64>> {
+    if points.is_empty() {
+        return Vec::new();
+    }
+
+    let mut smoothed = vec![points[0]];
+    let mut anchor = 0;
+
+    while anchor + 1 < points.len() {
+        let next = (anchor + 2..points.len())
+            .rev()
+            .find(|next| grid.line_walkable(&points[anchor], &points[*next]))
+            .unwrap_or(anchor + 1);
+
+        smoothed.push(points[next]);
+        anchor = next;
+    }
+
+    smoothed
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::display::terrain::HeightMap;
+    use crate::simulation::collision::Aabb;
+
+    #[test]
+    fn test_find_path() {
+        // wall across the middle with a gap at the far end
+        let map = HeightMap::new(vec![0.0; 21 * 21], 21, 1.0);
+        let wall = Aabb::new(
+            nalgebra::Point3::new(9.5, -3.0, -1.0),
+            nalgebra::Point3::new(10.5, 0.0, 14.0),
+        );
+        let grid = NavGrid::new(&nalgebra::Point3::origin(), &map, 0.8, 0.5, vec![wall]);
+
+        let from = nalgebra::Point3::new(2.5, 0.0, 2.5);
+        let to = nalgebra::Point3::new(17.25, 0.0, 2.5);
+        let path = find_path(&grid, &from, &to).unwrap();
+
+        // around the end of the wall and back, in a few straight lines
+        assert_eq!(*path.last().unwrap(), to);
+        assert!(path.len() >= 2 && path.len() <= 4, "{:?}", path);
+        assert!(path.iter().any(|point| point.z > 15.0));
+
+        let mut last = from;
+        for point in &path {
+            assert!(grid.line_walkable(&last, point));
+            last = *point;
+        }
+
+        // the direct line is clear on the near side of the wall
+        let path = find_path(&grid, &from, &nalgebra::Point3::new(6.5, 0.0, 12.5)).unwrap();
+        assert_eq!(path, vec![nalgebra::Point3::new(6.5, 0.0, 12.5)]);
+
+        // within the same cell
+        let near = nalgebra::Point3::new(2.75, 0.0, 2.25);
+        assert_eq!(find_path(&grid, &from, &near).unwrap(), vec![near]);
+
+        // inside the wall and off the grid
+        assert!(find_path(&grid, &from, &nalgebra::Point3::new(10.0, 0.0, 5.0)).is_none());
+        assert!(find_path(&grid, &from, &nalgebra::Point3::new(30.0, 0.0, 5.0)).is_none());
+    }
+
+    #[test]
+    fn test_unreachable() {
+        let map = HeightMap::new(vec![0.0; 11 * 11], 11, 1.0);
+        let wall = Aabb::new(
+            nalgebra::Point3::new(4.5, -3.0, -1.0),
+            nalgebra::Point3::new(5.5, 0.0, 11.0),
+        );
+        let grid = NavGrid::new(&nalgebra::Point3::origin(), &map, 0.8, 0.5, vec![wall]);
+
+        assert!(find_path(
+            &grid,
+            &nalgebra::Point3::new(1.5, 0.0, 1.5),
+            &nalgebra::Point3::new(8.5, 0.0, 1.5),
+        ).is_none());
+    }
+}
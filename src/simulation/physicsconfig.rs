@@ -1,3 +1,8 @@
+/**
+ * All values are in SI units: gravity in m/s^2 and the drag values as
+ * exponential damping rates per second, so the simulation behaves the same
+ * regardless of the tick rate
+ */
 #[derive(Clone, serde::Serialize, serde::Deserialize)]
 #[serde(default, rename_all = "kebab-case")]
 pub struct PhysicsConfig {
@@ -6,16 +11,26 @@ pub struct PhysicsConfig {
     pub max_ground_slope: f64,
     pub horisontal_drag: f64,
     pub vertical_drag: f64,
+    pub broadphase_cell_size: f64,
+    /**
+     * Rigid bodies slower than 'sleep_speed' for 'sleep_time' seconds stop
+     * being simulated until something hits them
+     */
+    pub sleep_speed: f64,
+    pub sleep_time: f64,
 }
 
 impl Default for PhysicsConfig {
     fn default() -> PhysicsConfig {
         PhysicsConfig {
-            gravity: 0.48,
+            gravity: 30.0,
             min_collision_depth: 0.001,
             max_ground_slope: 0.2,
-            horisontal_drag: 0.25,
+            horisontal_drag: 18.0,
             vertical_drag: 0.0,
+            broadphase_cell_size: 4.0,
+            sleep_speed: 0.2,
+            sleep_time: 0.5,
         }
     }
 }
\ No newline at end of file
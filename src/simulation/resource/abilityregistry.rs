@@ -0,0 +1,141 @@
+use std::collections::HashMap;
+
+use serde::Deserialize;
+use specs::Entity;
+
+use crate::simulation::{
+    component::Projectile,
+    level::{
+        ModelData,
+        TextureData,
+    },
+};
+
+/**
+ * Ability definitions, keyed by the id characters refer to them by
+ */
+#[derive(Default, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct AbilityRegistry {
+    #[serde(default, rename = "ability")]
+    pub abilities: HashMap<String, Ability>,
+}
+
+/**
+ * 'cooldown' and 'cast-time' are in seconds. An ability can only be used on
+ * a target within 'range' m of the caster, any distance if not given.
+ */
+#[derive(Clone, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct Ability {
+    pub name: String,
+    pub icon: String,
+    #[serde(default)]
+    pub cooldown: f64,
+    #[serde(default)]
+    pub cast_time: f64,
+    pub range: Option<f64>,
+    #[serde(default)]
+    pub target: TargetRequirement,
+    pub projectile: Option<ProjectileData>,
+}
+
+/**
+ * Projectile launched towards the target when the ability is used, or along
+ * the view without one. 'speed' is in m/s and 'lifetime' in seconds.
+ */
+#[derive(Clone, Deserialize)]
+#[serde(default, rename_all = "kebab-case")]
+pub struct ProjectileData {
+    pub speed: f64,
+    pub gravity: f64,
+    pub radius: f64,
+    pub lifetime: f64,
+    pub model: Option<ModelData>,
+    pub texture: Option<TextureData>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum TargetRequirement {
+    /**
+     * Used without a target, such as on the caster itself
+     */
+    None,
+    /**
+     * Needs a living target entity
+     */
+    Entity,
+}
+
+impl Default for TargetRequirement {
+    fn default() -> TargetRequirement {
+        TargetRequirement::None
+    }
+}
+
+impl Default for ProjectileData {
+    fn default() -> ProjectileData {
+        ProjectileData {
+            speed: 20.0,
+            gravity: 0.0,
+            radius: 0.1,
+            lifetime: 5.0,
+            model: None,
+            texture: None,
+        }
+    }
+}
+
+impl ProjectileData {
+    pub fn to_component(&self, owner: Option<Entity>, direction: &nalgebra::Unit<nalgebra::Vector3<f64>>)
+        -> Projectile
+    {
+        Projectile {
+            owner,
+            velocity: direction.as_ref() * self.speed,
+            gravity: self.gravity,
+            radius: self.radius,
+            lifetime: self.lifetime,
+        }
+    }
+}
+
+impl AbilityRegistry {
+    pub fn get(&self, id: &str) -> Option<&Ability> {
+        self.abilities.get(id)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_ability_defaults() {
+        let registry: AbilityRegistry = toml::from_str(r#"
+            [ability.attack]
+            name = "Attack"
+            icon = "assets/icon_attack.png"
+            cooldown = 1.5
+            range = 3.0
+            target = "entity"
+
+            [ability.rest]
+            name = "Rest"
+            icon = "assets/icon_rest.png"
+        "#).unwrap();
+
+        let attack = registry.get("attack").unwrap();
+        assert_eq!(attack.cooldown, 1.5);
+        assert_eq!(attack.range, Some(3.0));
+        assert_eq!(attack.target, TargetRequirement::Entity);
+
+        let rest = registry.get("rest").unwrap();
+        assert_eq!(rest.cast_time, 0.0);
+        assert_eq!(rest.range, None);
+        assert_eq!(rest.target, TargetRequirement::None);
+
+        assert!(registry.get("fireball").is_none());
+    }
+}
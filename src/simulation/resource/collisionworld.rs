@@ -0,0 +1,248 @@
+use std::ops::Deref;
+
+use specs::prelude::*;
+use specs::storage::MaskedStorage;
+
+use crate::simulation::{
+    collision::{
+        cast_sphere,
+        CastHit,
+        SpatialHash,
+    },
+    component::{
+        Collider,
+        Position,
+    },
+    PhysicsConfig,
+};
+
+#[derive(Clone, Debug)]
+pub struct RayHit {
+    pub entity: Entity,
+    pub distance: f64,
+    /**
+     * Point of contact on the surface that was hit
+     */
+    pub point: nalgebra::Point3<f64>,
+    pub normal: nalgebra::Unit<nalgebra::Vector3<f64>>,
+}
+
+/**
+ * Broadphase of every collider as of the last collision detection pass, for
+ * ray and shape queries against the world
+ */
+pub struct CollisionWorld {
+    broadphase: SpatialHash,
+    entities: Vec<Entity>,
+}
+
+impl Default for CollisionWorld {
+    fn default() -> CollisionWorld {
+        CollisionWorld::new(PhysicsConfig::default().broadphase_cell_size)
+    }
+}
+
+impl CollisionWorld {
+    pub fn new(cell_size: f64) -> CollisionWorld {
+        CollisionWorld {
+            broadphase: SpatialHash::new(cell_size),
+            entities: Vec::new(),
+        }
+    }
+
+    pub fn clear(&mut self) {
+        self.broadphase.clear();
+        self.entities.clear();
+    }
+
+    /**
+     * Changing the cell size drops everything inserted
+     */
+    pub fn set_cell_size(&mut self, cell_size: f64) {
+        if cell_size != self.broadphase.cell_size() {
+            *self = CollisionWorld::new(cell_size);
+        }
+    }
+
+    pub fn insert(&mut self, entity: Entity, position: &Position, collider: &Collider) -> usize {
+        self.entities.push(entity);
+        self.broadphase.insert(collider.collider.bounds(&position.0))
+    }
+
+    pub fn entity(&self, index: usize) -> Entity {
+        self.entities[index]
+    }
+
+    pub fn broadphase(&self) -> &SpatialHash {
+        &self.broadphase
+    }
+
+    /**
+     * Nearest solid collider on a layer in 'mask' hit by the ray from 'origin'
+     * along 'direction', within 'max_distance'
+     */
+    pub fn raycast<'e, P, C>(
+        &self,
+        positions: &Storage<'e, Position, P>,
+        colliders: &Storage<'e, Collider, C>,
+        origin: &nalgebra::Point3<f64>,
+        direction: &nalgebra::Vector3<f64>,
+        max_distance: f64,
+        mask: u32,
+    ) -> Option<RayHit>
+    where
+        P: Deref<Target = MaskedStorage<Position>>,
+        C: Deref<Target = MaskedStorage<Collider>>,
+    {
+        self.sphere_cast(positions, colliders, origin, direction, 0.0, max_distance, mask)
+    }
+
+    /**
+     * Nearest solid collider on a layer in 'mask' hit by a sphere of
+     * 'radius' swept from 'origin' along 'direction', within 'max_distance'.
+     * The hit distance is how far the sphere's centre travelled.
+     */
+    #[allow(clippy::too_many_arguments)]
+    pub fn sphere_cast<'e, P, C>(
+        &self,
+        positions: &Storage<'e, Position, P>,
+        colliders: &Storage<'e, Collider, C>,
+        origin: &nalgebra::Point3<f64>,
+        direction: &nalgebra::Vector3<f64>,
+        radius: f64,
+        max_distance: f64,
+        mask: u32,
+    ) -> Option<RayHit>
+    where
+        P: Deref<Target = MaskedStorage<Position>>,
+        C: Deref<Target = MaskedStorage<Collider>>,
+    {
+        self.cast_with(
+            positions, colliders,
+            origin, direction,
+            radius, max_distance,
+            |_, collider, _| !collider.trigger && collider.layer & mask != 0,
+        )
+    }
+
+    /**
+     * Sphere cast keeping only the hits 'accept' returns true for
+     */
+    #[allow(clippy::too_many_arguments)]
+    pub fn cast_with<'e, P, C, F>(
+        &self,
+        positions: &Storage<'e, Position, P>,
+        colliders: &Storage<'e, Collider, C>,
+        origin: &nalgebra::Point3<f64>,
+        direction: &nalgebra::Vector3<f64>,
+        radius: f64,
+        max_distance: f64,
+        mut accept: F,
+    ) -> Option<RayHit>
+    where
+        P: Deref<Target = MaskedStorage<Position>>,
+        C: Deref<Target = MaskedStorage<Collider>>,
+        F: FnMut(Entity, &Collider, &CastHit) -> bool,
+    {
+        let direction = nalgebra::Unit::try_new(*direction, f64::EPSILON)?;
+
+        let mut best: Option<RayHit> = None;
+        self.broadphase.cast(origin, &direction, radius, max_distance, |index| {
+            let entity = self.entities[index];
+            let (position, collider) = match (positions.get(entity), colliders.get(entity)) {
+                (Some(position), Some(collider)) => (position, collider),
+                _ => return None,
+            };
+
+            let hit = cast_sphere(
+                &collider.collider, &position.0,
+                origin, &direction,
+                radius, max_distance,
+            )?;
+            if !accept(entity, collider, &hit) {
+                return None;
+            }
+
+            let (distance, normal) = hit;
+            let closer = match &best {
+                Some(hit) => distance < hit.distance,
+                None => true,
+            };
+            if closer {
+                best = Some(RayHit {
+                    entity,
+                    distance,
+                    point: origin + direction.as_ref() * distance - normal.as_ref() * radius,
+                    normal,
+                });
+            }
+
+            Some(distance)
+        });
+
+        best
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::simulation::component::collider::ColliderType;
+
+    #[test]
+    fn test_raycast() {
+        let mut world = World::new();
+        world.register::<Position>();
+        world.register::<Collider>();
+
+        let floor = world.create_entity()
+            .with(Position(nalgebra::Point3::new(0.0, 0.0, 0.0)))
+            .with(Collider::new(ColliderType::Plane(
+                nalgebra::Unit::new_normalize(nalgebra::Vector3::new(0.0, -1.0, 0.0))
+            )))
+            .build();
+        let near = world.create_entity()
+            .with(Position(nalgebra::Point3::new(0.0, -5.0, 20.0)))
+            .with(Collider::new(ColliderType::Sphere(1.0)))
+            .build();
+        let far = world.create_entity()
+            .with(Position(nalgebra::Point3::new(0.0, -5.0, 40.0)))
+            .with(Collider::new(ColliderType::Sphere(1.0)))
+            .build();
+
+        let mut collision_world = CollisionWorld::new(4.0);
+        {
+            let positions = world.read_storage::<Position>();
+            let colliders = world.read_storage::<Collider>();
+            for (entity, position, collider) in (&world.entities(), &positions, &colliders).join() {
+                collision_world.insert(entity, position, collider);
+            }
+        }
+
+        let positions = world.read_storage::<Position>();
+        let mut colliders = world.write_storage::<Collider>();
+
+        let origin = nalgebra::Point3::new(0.0, -5.0, 0.0);
+        let forward = nalgebra::Vector3::new(0.0, 0.0, 1.0);
+
+        let hit = collision_world.raycast(&positions, &colliders, &origin, &forward, 100.0, !0).unwrap();
+        assert_eq!(hit.entity, near);
+        assert!((hit.distance - 19.0).abs() < 1e-9);
+        assert!((hit.point - nalgebra::Point3::new(0.0, -5.0, 19.0)).norm() < 1e-9);
+        assert!((hit.normal.z + 1.0).abs() < 1e-9);
+
+        assert!(collision_world.raycast(&positions, &colliders, &origin, &forward, 10.0, !0).is_none());
+
+        // the near sphere is masked out
+        colliders.get_mut(near).unwrap().layer = 2;
+        let hit = collision_world.raycast(&positions, &colliders, &origin, &forward, 100.0, 1).unwrap();
+        assert_eq!(hit.entity, far);
+
+        // a sphere cast downwards stops at the floor
+        let down = nalgebra::Vector3::new(0.0, 1.0, 0.0);
+        let hit = collision_world.sphere_cast(&positions, &colliders, &origin, &down, 0.5, 100.0, !0).unwrap();
+        assert_eq!(hit.entity, floor);
+        assert!((hit.distance - 4.5).abs() < 1e-9);
+        assert!(hit.point.y.abs() < 1e-9);
+    }
+}
@@ -7,6 +7,15 @@ pub struct InputMap {
     pub move_left: bool,
     pub move_right: bool,
     pub move_up: bool,
+    pub sprint: bool,
+    pub crouch: bool,
+    pub respawn: bool,
+    pub target_next: bool,
+    pub clear_target: bool,
+    /**
+     * Held state of the hotbar slot keys, grown as they are first pressed
+     */
+    pub abilities: Vec<bool>,
 }
 
 impl InputMap {
@@ -18,6 +27,17 @@ impl InputMap {
             InputTypes::MoveLeft => &mut self.move_left,
             InputTypes::MoveRight => &mut self.move_right,
             InputTypes::MoveUp => &mut self.move_up,
+            InputTypes::Sprint => &mut self.sprint,
+            InputTypes::Crouch => &mut self.crouch,
+            InputTypes::Respawn => &mut self.respawn,
+            InputTypes::TargetNext => &mut self.target_next,
+            InputTypes::ClearTarget => &mut self.clear_target,
+            InputTypes::Ability(slot) => {
+                if self.abilities.len() <= slot {
+                    self.abilities.resize(slot + 1, false);
+                }
+                &mut self.abilities[slot]
+            },
         };
         *field = value;
     }
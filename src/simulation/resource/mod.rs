@@ -1,13 +1,57 @@
+mod abilityregistry;
 mod activecamera;
 mod activecharacter;
+mod collisionworld;
 mod inputmap;
+mod serverprefab;
+mod target;
 mod ticklength;
+mod ticknumber;
+mod worldbounds;
 
-use super::event::Event;
+use super::event::{
+    DamageEvent,
+    Event,
+    ImpactEvent,
+    TriggerEvent,
+};
 
+pub use abilityregistry::{
+    Ability,
+    AbilityRegistry,
+    ProjectileData,
+    TargetRequirement,
+};
 pub use activecamera::ActiveCamera;
 pub use activecharacter::ActiveCharacter;
+pub use collisionworld::{
+    CollisionWorld,
+    RayHit,
+};
 pub use inputmap::InputMap;
+pub use serverprefab::{
+    Prefab,
+    PrefabData,
+    ServerPrefab,
+};
+pub use target::Target;
 pub use ticklength::TickLength;
+pub use ticknumber::TickNumber;
+pub use worldbounds::WorldBounds;
 
-pub type EventQueue = Vec<Event>;
\ No newline at end of file
+pub type EventQueue = Vec<Event>;
+
+/**
+ * Trigger events of the latest collision detection pass
+ */
+pub type TriggerQueue = Vec<TriggerEvent>;
+
+/**
+ * Damage dealt during the current tick
+ */
+pub type DamageQueue = Vec<DamageEvent>;
+
+/**
+ * Projectile impacts of the current tick
+ */
+pub type ImpactQueue = Vec<ImpactEvent>;
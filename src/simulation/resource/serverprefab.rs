@@ -0,0 +1,114 @@
+use failure::Error;
+use serde::Deserialize;
+use specs::{
+    Entity,
+    LazyUpdate,
+};
+
+use crate::simulation::{
+    component::{
+        Collider,
+        Hostile,
+        Name,
+    },
+    level::{
+        ColliderData,
+        ModelData,
+        TextureData,
+    },
+};
+
+/**
+ * Prefab that entities spawned by the server are built from. World updates
+ * in protocol v0.2.1 carry no entity type to pick a prefab by, so every
+ * server entity gets the same one.
+ */
+#[derive(Default)]
+pub struct ServerPrefab(pub Option<Prefab>);
+
+/**
+ * Component bundle with its collider built up front, so that bad assets are
+ * reported when it is loaded rather than when an entity is first spawned
+ */
+pub struct Prefab {
+    pub name: Option<String>,
+    pub model: Option<ModelData>,
+    pub texture: Option<TextureData>,
+    pub collider: Option<Collider>,
+    pub hostile: bool,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct PrefabData {
+    pub name: Option<String>,
+    pub model: Option<ModelData>,
+    pub texture: Option<TextureData>,
+    pub collider: Option<ColliderData>,
+    #[serde(default)]
+    pub hostile: bool,
+}
+
+impl PrefabData {
+    pub fn build(self) -> Result<Prefab, Error> {
+        let collider = match self.collider {
+            Some(ref collider) => Some(collider.to_component()?),
+            None => None,
+        };
+
+        Ok(Prefab {
+            name: self.name,
+            model: self.model,
+            texture: self.texture,
+            collider,
+            hostile: self.hostile,
+        })
+    }
+}
+
+impl Prefab {
+    /**
+     * Components are inserted lazily, on the next world maintenance
+     */
+    pub fn instantiate(&self, entity: Entity, lazy: &LazyUpdate) {
+        if let Some(ref collider) = self.collider {
+            lazy.insert(entity, collider.clone());
+        }
+        if let Some(ref name) = self.name {
+            lazy.insert(entity, Name(name.clone()));
+        }
+        if let Some(ref model) = self.model {
+            lazy.insert(entity, model.to_component());
+        }
+        if let Some(ref texture) = self.texture {
+            lazy.insert(entity, texture.to_component());
+        }
+        if self.hostile {
+            lazy.insert(entity, Hostile);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_build_prefab() {
+        let data: PrefabData = toml::from_str(r#"
+            name = "Pillar"
+            model = { path = "assets/pillar.erm" }
+            collider = { type = "sphere", radius = 1.0 }
+        "#).unwrap();
+
+        let prefab = data.build().unwrap();
+        assert_eq!(prefab.name.as_ref().unwrap(), "Pillar");
+        assert!(prefab.collider.is_some());
+
+        // colliders are checked with the prefab
+        let data: PrefabData = toml::from_str(r#"
+            collider = { type = "sphere", radius = 1.0, layer = 40 }
+        "#).unwrap();
+        assert!(data.build().is_err());
+    }
+}
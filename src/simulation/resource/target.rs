@@ -0,0 +1,8 @@
+use specs::Entity;
+
+/**
+ * Entity selected by the active character, used by abilities that need a
+ * target
+ */
+#[derive(Default)]
+pub struct Target(pub Option<Entity>);
\ No newline at end of file
@@ -2,7 +2,7 @@
 pub struct TickLength(pub std::time::Duration);
 
 impl TickLength {
-    pub fn scale_to(&self, value: f64) -> f64 {
-        return value / 1000.0 * self.0.as_millis() as f64;
+    pub fn seconds(&self) -> f64 {
+        self.0.as_secs_f64()
     }
 }
\ No newline at end of file
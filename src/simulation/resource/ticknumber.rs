@@ -0,0 +1,24 @@
+use std::time::{
+    Duration,
+    SystemTime,
+    UNIX_EPOCH,
+};
+
+/**
+ * Number of the current tick, counted in tick lengths since the UNIX epoch
+ * by the local wall clock. Ticks dropped by a simulation that fell behind
+ * still count.
+ *
+ * Protocol v0.2.1 carries no tick number, neither in the handshake nor in
+ * world updates, so the base cannot be taken from the server. Two machines
+ * only agree on it as far as their clocks do, and nothing here checks that.
+ */
+#[derive(Default)]
+pub struct TickNumber(pub u64);
+
+impl TickNumber {
+    pub fn at(time: SystemTime, tick_length: Duration) -> TickNumber {
+        let elapsed = time.duration_since(UNIX_EPOCH).unwrap_or_default();
+        TickNumber((elapsed.as_nanos() / tick_length.as_nanos().max(1)) as u64)
+    }
+}
@@ -0,0 +1,13 @@
+use crate::simulation::collision::Aabb;
+
+/**
+ * Region of the level that entities are kept within. Anything with a
+ * `SpawnPoint` that leaves it is respawned.
+ */
+pub struct WorldBounds(pub Aabb);
+
+impl Default for WorldBounds {
+    fn default() -> WorldBounds {
+        WorldBounds(Aabb::infinite())
+    }
+}
@@ -1,184 +1,254 @@
 use std::sync::mpsc::Sender;
+use failure::Error;
 use specs::{
     DispatcherBuilder,
     World,
     WorldExt,
-    world::Builder,
 };
 use futures::sync::mpsc::UnboundedSender;
 
 use crate::input::MouseEuler;
-use crate::loaders::heightmap_from_bmp;
-use super::event::{
-    Event,
-    Update,
+use crate::loaders::{
+    abilities_from_toml,
+    level_from_toml,
+    prefab_from_toml,
 };
+use super::event::Update;
+use super::navigation::NavGrid;
 use super::component::{
-    collider::{self, Collider},
+    Abilities,
+    Collider,
+    ContinuousCollision,
+    Dead,
     Health,
+    Hostile,
     Jump,
+    LocalTransform,
     Model,
     Movement,
+    MovingPlatform,
     Name,
+    NavAgent,
+    Parent,
     Position,
+    Projectile,
+    RigidBody,
+    Rotation,
     ServerID,
+    SpawnPoint,
+    Submersion,
     Terrain,
     Texture,
     Velocity,
+    Water,
 };
 use super::resource::{
     ActiveCamera,
     ActiveCharacter,
+    CollisionWorld,
+    DamageQueue,
+    ImpactQueue,
     InputMap,
+    Target,
     TickLength,
+    TriggerQueue,
 };
 use super::system::{
     CollisionDetection,
     CollisionResolver,
+    Damage,
+    MotionSweep,
+    MovingPlatforms,
+    NavAgents,
+    OutOfBounds,
     Physics,
     PlayerMovement,
+    Projectiles,
+    RigidBodyResolver,
+    Targeting,
+    TransformPropagation,
     UpdateInputs,
     UpdateSender,
     UpdateWorld,
+    UseAbilities,
+    WaterVolumes,
 };
 use super::PhysicsConfig;
-
-use eternalreckoning_core::simulation::Simulation;
+use super::fixedstep::FixedStepSimulation;
 
 #[derive(Clone, serde::Serialize, serde::Deserialize)]
 #[serde(default, rename_all = "kebab-case")]
 pub struct SimulationConfig {
+    pub level: String,
+    pub prefabs: String,
+    pub abilities: String,
     pub movement_speed: f64,
+    pub sprint_speed: f64,
+    pub crouch_speed: f64,
+    pub swim_speed: f64,
+    pub air_control: f64,
+    pub step_height: f64,
     pub jump_force: f64,
+    pub coyote_time: f64,
+    pub jump_buffer_time: f64,
     pub physics: PhysicsConfig,
 }
 
 impl Default for SimulationConfig {
     fn default() -> SimulationConfig {
         SimulationConfig {
+            level: "levels/default.toml".to_string(),
+            prefabs: "levels/prefabs.toml".to_string(),
+            abilities: "levels/abilities.toml".to_string(),
             movement_speed: 6.0,
+            sprint_speed: 10.0,
+            crouch_speed: 3.0,
+            swim_speed: 4.0,
+            air_control: 0.3,
+            step_height: 0.5,
             jump_force: 10.35,
+            coyote_time: 0.1,
+            jump_buffer_time: 0.1,
             physics: PhysicsConfig::default(),
         }
     }
 }
 
 pub fn build_simulation<'a, 'b>(
-    mut config: SimulationConfig,
+    config: SimulationConfig,
     update_tx: Sender<Update>,
     net_update_tx: UnboundedSender<Update>,
     tick_length: std::time::Duration,
-) -> Simulation<'a, 'b, Event>
+    max_catch_up: u32,
+) -> Result<FixedStepSimulation<'a, 'b>, Error>
 {
     let mut world = World::new();
 
-    let tick_length = TickLength(tick_length);
-
-    config.movement_speed = tick_length.scale_to(config.movement_speed);
-    config.jump_force = tick_length.scale_to(config.jump_force);
-    
-    config.physics.gravity = tick_length.scale_to(config.physics.gravity);
-
     world.insert(InputMap::default());
     world.insert(MouseEuler::default());
-    world.insert(tick_length);
-
-    world.register::<Collider>();
-    world.register::<Health>();
-    world.register::<Jump>();
-    world.register::<Model>();
-    world.register::<Movement>();
-    world.register::<Name>();
-    world.register::<Position>();
-    world.register::<ServerID>();
-    world.register::<Terrain>();
-    world.register::<Texture>();
-    world.register::<Velocity>();
-
-    // Floor collision plane
-    world.create_entity()
-        .with(Position(nalgebra::Point3::new(-64.0, 5.0, -64.0)))
-        .with(Collider::new(collider::ColliderType::HeightMap(
-            heightmap_from_bmp("assets/terrain.bmp", 25.0).unwrap()
-        )))
-        .with(Terrain::new("assets/terrain.bmp", 25.0))
-        .with(Texture {
-            path: "assets/sand.png".to_string(),
-            wrap_mode: rendy::resource::WrapMode::Tile,
-        })
-        .build();
-
-    // Player
-    let player = world.create_entity()
-        .with(Name("Player".to_string()))
-        .with(Health(100))
-        .with(Position(nalgebra::Point3::new(0.0, -1.0, 0.0)))
-        .with(Velocity(nalgebra::Vector3::new(0.0, 0.0, 0.0)))
-        .with(Movement { speed: config.movement_speed, on_ground: true })
-        .with(Jump { force: config.jump_force })
-        .with(Collider::new(collider::ColliderType::Sphere(1.0)))
-        .with(Model {
-            path: "assets/marker.erm".to_string(),
-            offset: Some(nalgebra::Vector3::new(0.0, 1.0, 0.0))
-        })
-        .with(Texture::new("assets/marker.png"))
-        .build();
-
-    world.insert(ActiveCamera(Some(player)));
-    world.insert(ActiveCharacter(Some(player)));
+    world.insert(TickLength(tick_length));
+    world.insert(CollisionWorld::new(config.physics.broadphase_cell_size));
+    world.insert(TriggerQueue::new());
+    world.insert(DamageQueue::new());
+    world.insert(ImpactQueue::new());
+    world.insert(Target::default());
+    world.insert(prefab_from_toml(&config.prefabs)?);
+    world.insert(abilities_from_toml(&config.abilities)?);
+
+    register_components(&mut world);
+
+    let level = level_from_toml(&config.level)?;
+    let player = level.spawn(&mut world, &config)?;
+    if player.is_none() {
+        log::warn!("Level {} has no player entity", config.level);
+    }
 
-    world.create_entity()
-        .with(Position(nalgebra::Point3::new(-8.0, -1.1, 16.0)))
-        .with(Model {
-            path: "assets/pillar.erm".to_string(),
-            offset: Some(nalgebra::Vector3::new(0.0, 1.0, 0.0))
-        })
-        .with(Texture::new("assets/pillar.png"))
-        .with(Collider::new(collider::ColliderType::Sphere(1.0)))
-        .build();
+    world.insert(NavGrid::from_world(&world, config.physics.max_ground_slope, config.step_height));
 
-    /*
-    world.create_entity()
-        .with(Position(nalgebra::Point3::new(-11.0, -0.8, 13.0)))
-        .with(Model {
-            path: "assets/elf-spear.erm".to_string(),
-            offset: Some(nalgebra::Vector3::new(0.0, 0.5, 0.0))
-        })
-        .with(Collider::new(collider::ColliderType::Sphere(0.5)))
-        .build();
-    */
+    world.insert(ActiveCamera(player));
+    world.insert(ActiveCharacter(player));
 
-    world.create_entity()
-        .with(Position(nalgebra::Point3::new(-14.0, -1.2, 10.0)))
-        .with(Model {
-            path: "assets/pillar.erm".to_string(),
-            offset: Some(nalgebra::Vector3::new(0.0, 1.0, 0.0))
-        })
-        .with(Texture::new("assets/pillar.png"))
-        .with(Collider::new(collider::ColliderType::Sphere(1.0)))
-        .build();
+    let use_abilities = UseAbilities::new(update_tx.clone(), net_update_tx.clone());
+    let projectiles = Projectiles::new(&config.physics, update_tx.clone());
 
     let dispatcher = DispatcherBuilder::new()
         .with(UpdateInputs, "update_inputs", &[])
-        .with(PlayerMovement, "player_movement", &["update_inputs"])
-        .with(Physics::new(&config.physics), "physics", &["player_movement"])
+        .with(PlayerMovement::new(&config.physics), "player_movement", &["update_inputs"])
+        .with(MovingPlatforms, "moving_platforms", &["player_movement"])
+        .with(NavAgents, "nav_agents", &["player_movement"])
+        .with(Physics::new(&config.physics), "physics", &["moving_platforms", "nav_agents"])
+        .with(MotionSweep, "motion_sweep", &["physics"])
+        .with(TransformPropagation, "transform_propagation", &["motion_sweep"])
         .with(
             CollisionDetection::new(&config.physics),
             "collision_detection",
-            &["physics"]
+            &["transform_propagation"]
         )
+        .with(WaterVolumes, "water_volumes", &["collision_detection"])
         .with(
             CollisionResolver::new(&config.physics),
             "collision_resolver",
             &["collision_detection"]
         )
+        .with(
+            RigidBodyResolver::new(&config.physics),
+            "rigid_body_resolver",
+            &["collision_detection"]
+        )
+        .with(
+            OutOfBounds::new(update_tx.clone(), net_update_tx.clone()),
+            "out_of_bounds",
+            &["collision_resolver", "rigid_body_resolver"]
+        )
+        .with(UpdateWorld::new(update_tx.clone()), "update_world", &[])
+        .with(
+            Damage::new(update_tx.clone(), net_update_tx.clone()),
+            "damage",
+            &["update_world"]
+        )
+        .with(
+            Targeting::new(update_tx.clone()),
+            "targeting",
+            &["collision_resolver", "rigid_body_resolver", "damage"]
+        )
+        // attached entities follow their parents' resolved positions
+        .with(
+            TransformPropagation,
+            "late_transform_propagation",
+            &["out_of_bounds", "damage"]
+        )
         .with(
             UpdateSender::new(update_tx, net_update_tx),
             "update_sender",
-            &["player_movement", "physics", "collision_detection", "collision_resolver"]
+            &[
+                "player_movement",
+                "physics",
+                "collision_detection",
+                "collision_resolver",
+                "rigid_body_resolver",
+                "out_of_bounds",
+                "late_transform_propagation",
+                "damage",
+            ]
         )
-        .with(UpdateWorld, "update_world", &[])
+        // after the world updates, so that the display starts loading the
+        // level before it is told about the hotbar
+        .with(use_abilities, "use_abilities", &["update_sender", "targeting"])
+        // also after the world updates, so that projectiles removed on
+        // impact are gone by the next ones
+        .with(projectiles, "projectiles", &["update_sender"])
         .build();
 
-    Simulation::new(dispatcher, world)
-}
\ No newline at end of file
+    Ok(FixedStepSimulation::new(dispatcher, world, tick_length, max_catch_up))
+}
+
+/**
+ * Registers the storages of every component the simulation uses
+ */
+pub fn register_components(world: &mut World) {
+    world.register::<Abilities>();
+    world.register::<Collider>();
+    world.register::<ContinuousCollision>();
+    world.register::<Dead>();
+    world.register::<Health>();
+    world.register::<Hostile>();
+    world.register::<Jump>();
+    world.register::<LocalTransform>();
+    world.register::<Model>();
+    world.register::<Movement>();
+    world.register::<MovingPlatform>();
+    world.register::<Name>();
+    world.register::<NavAgent>();
+    world.register::<Parent>();
+    world.register::<Position>();
+    world.register::<Projectile>();
+    world.register::<RigidBody>();
+    world.register::<Rotation>();
+    world.register::<ServerID>();
+    world.register::<SpawnPoint>();
+    world.register::<Submersion>();
+    world.register::<Terrain>();
+    world.register::<Texture>();
+    world.register::<Velocity>();
+    world.register::<Water>();
+}
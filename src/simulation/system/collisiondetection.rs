@@ -1,62 +1,114 @@
+use std::collections::BTreeSet;
+
 use specs::prelude::*;
 
 use crate::simulation::PhysicsConfig;
 use crate::simulation::{
+    collision::{
+        Aabb,
+        Triangle,
+        TriMesh,
+    },
     component::{
         collider::{
             Collider,
             ColliderType,
             Collision,
         },
+        Parent,
         Position,
     },
+    event::{
+        ConfigEvent,
+        Event,
+        TriggerContact,
+        TriggerEvent,
+    },
+    resource::{
+        CollisionWorld,
+        EventQueue,
+        TriggerQueue,
+    },
 };
 use crate::display::terrain::HeightMap;
 
+type Contact = (nalgebra::Vector3<f64>, nalgebra::Unit<nalgebra::Vector3<f64>>);
+
 pub struct CollisionDetection {
     min_collision_depth: f64,
+    cell_size: f64,
+    // (trigger, other) pairs touching as of the last pass
+    triggered: BTreeSet<(Entity, Entity)>,
 }
 
 impl<'a> System<'a> for CollisionDetection {
     type SystemData = (
         Entities<'a>,
+        Read<'a, EventQueue>,
+        Write<'a, CollisionWorld>,
+        Write<'a, TriggerQueue>,
+        ReadStorage<'a, Parent>,
         ReadStorage<'a, Position>,
         WriteStorage<'a, Collider>,
     );
 
     fn run(&mut self, data: Self::SystemData) {
-        let (entities, positions, mut colliders) = data;
+        let (entities, events, mut world, mut triggers, parents, positions, mut colliders) = data;
+
+        for event in &*events {
+            if let Event::ConfigEvent(ConfigEvent::Physics(config)) = event {
+                // keeps the trigger contacts, to not enter them again
+                self.min_collision_depth = config.min_collision_depth;
+                self.cell_size = config.broadphase_cell_size;
+            }
+        }
 
         for collider in (&mut colliders).join() {
             collider.collisions.clear();
         }
 
-        let mut collisions = Vec::new();
+        world.set_cell_size(self.cell_size);
+        world.clear();
 
-        // TODO: pre-checking with AABBs
-        // TODO: fix the loop and avoid checking everything twice
+        let mut bodies = Vec::new();
         for (ent, pos, collider) in (&entities, &positions, &colliders).join() {
-            for (target, target_pos, target_collider)
-                in (&entities, &positions, &colliders).join()
+            world.insert(ent, pos, collider);
+            bodies.push((ent, pos, collider));
+        }
+
+        let mut collisions = Vec::new();
+        let mut triggered = BTreeSet::new();
+
+        for (a, b) in world.broadphase().pairs() {
+            let (ent, pos, collider) = bodies[a];
+            let (target, target_pos, target_collider) = bodies[b];
+
+            // attached entities do not push their parents around
+            let attached = matches!(parents.get(ent), Some(parent) if parent.0 == target)
+                || matches!(parents.get(target), Some(parent) if parent.0 == ent);
+
+            if attached
+                || !collider.interacts_with(target_collider)
+                || (collider.trigger && target_collider.trigger)
             {
-                if ent == target {
-                    continue;
-                }
+                continue;
+            }
 
-                match self.check_collision(
-                    &pos,
-                    &collider.collider,
-                    &target_pos,
-                    &target_collider.collider
-                )
-                {
-                    Some((depth, normal)) => {
-                        collisions.push((
-                            Collision { with: target, depth, normal },
-                            Collision { with: ent, depth: -depth, normal }
-                        ));
-                    },
-                    None => (),
+            let result = self.check_collision(
+                pos, &collider.collider,
+                target_pos, &target_collider.collider
+            );
+
+            if let Some((depth, normal)) = result {
+                if collider.trigger {
+                    triggered.insert((ent, target));
+                } else if target_collider.trigger {
+                    triggered.insert((target, ent));
+                } else {
+                    collisions.push((
+                        Collision { with: target, depth, normal },
+                        Collision { with: ent, depth: -depth, normal: negate(&normal) }
+                    ));
                 }
             }
         }
@@ -66,36 +118,134 @@ impl<'a> System<'a> for CollisionDetection {
             colliders.get_mut(c2.with).unwrap().collisions.push(c1);
             colliders.get_mut(e2).unwrap().collisions.push(c2);
         }
+
+        triggers.clear();
+        for &(trigger, other) in self.triggered.difference(&triggered) {
+            triggers.push(TriggerEvent::Exit(TriggerContact { trigger, other }));
+        }
+        for &(trigger, other) in &triggered {
+            let contact = TriggerContact { trigger, other };
+            if self.triggered.contains(&(trigger, other)) {
+                triggers.push(TriggerEvent::Stay(contact));
+            } else {
+                triggers.push(TriggerEvent::Enter(contact));
+            }
+        }
+        self.triggered = triggered;
     }
 }
 
+fn negate(normal: &nalgebra::Unit<nalgebra::Vector3<f64>>)
+    -> nalgebra::Unit<nalgebra::Vector3<f64>>
+{
+    nalgebra::Unit::new_unchecked(-normal.as_ref())
+}
+
+/**
+ * Immovable colliders, which are never tested against each other
+ */
+fn is_level_geometry(collider: &ColliderType) -> bool {
+    matches!(
+        collider,
+        ColliderType::Plane(_) | ColliderType::HeightMap(_) | ColliderType::TriMesh(_)
+    )
+}
+
+fn deepest(result: Option<Contact>, contact: Contact) -> Option<Contact> {
+    match result {
+        Some(ref best) if best.0.norm_squared() >= contact.0.norm_squared() => result,
+        _ => Some(contact),
+    }
+}
+
+/**
+ * Closest point to height 'y' on a vertical segment extending 'half_height'
+ * above and below 'center'
+ */
+fn closest_on_segment(center: &nalgebra::Point3<f64>, half_height: f64, y: f64)
+    -> nalgebra::Point3<f64>
+{
+    let y = y.max(center.y - half_height).min(center.y + half_height);
+    nalgebra::Point3::new(center.x, y, center.z)
+}
+
 impl CollisionDetection {
     pub fn new(config: &PhysicsConfig) -> CollisionDetection {
-        CollisionDetection { min_collision_depth: config.min_collision_depth }
+        CollisionDetection {
+            min_collision_depth: config.min_collision_depth,
+            cell_size: config.broadphase_cell_size,
+            triggered: BTreeSet::new(),
+        }
     }
 
+    /**
+     * Contacts are reported from the point of view of 't1': the normal
+     * points from 't1' towards 't2' and 'depth' is the penetration along it
+     */
     fn check_collision(
         &self,
         t1_pos: &Position, t1_collider: &ColliderType,
         t2_pos: &Position, t2_collider: &ColliderType,
-    ) -> Option<(nalgebra::Vector3<f64>, nalgebra::Unit<nalgebra::Vector3<f64>>)>
+    ) -> Option<Contact>
     {
-        match t1_collider {
-            ColliderType::Sphere(t1) => {
-                match t2_collider {
-                    ColliderType::Sphere(t2) => {
-                        self.sphere_to_sphere(&t1_pos, *t1, &t2_pos, *t2)
-                    },
-                    ColliderType::Plane(t2) => {
-                        self.sphere_to_plane(&t1_pos, *t1, &t2_pos, t2)
-                    },
-                    ColliderType::HeightMap(t2) => {
-                        self.sphere_to_heightmap(&t1_pos, *t1, &t2_pos, t2)
-                    }
-                }
+        match (t1_collider, t2_collider) {
+            (ColliderType::Sphere(t1), ColliderType::Sphere(t2)) => {
+                self.sphere_to_sphere(t1_pos, *t1, t2_pos, *t2)
+            },
+            (ColliderType::Sphere(t1), ColliderType::Plane(t2)) => {
+                self.sphere_to_plane(t1_pos, *t1, t2_pos, t2)
+            },
+            (ColliderType::Sphere(t1), ColliderType::HeightMap(t2)) => {
+                self.sphere_to_heightmap(t1_pos, *t1, t2_pos, t2)
+            },
+            (ColliderType::Sphere(t1), ColliderType::Aabb(t2)) => {
+                self.sphere_to_aabb(t1_pos, *t1, t2_pos, t2)
+            },
+            (ColliderType::Sphere(t1), ColliderType::Capsule { radius, half_height }) => {
+                self.sphere_to_capsule(t1_pos, *t1, t2_pos, *radius, *half_height)
+            },
+            (ColliderType::Aabb(t1), ColliderType::Plane(t2)) => {
+                self.aabb_to_plane(t1_pos, t1, t2_pos, t2)
+            },
+            (ColliderType::Aabb(t1), ColliderType::HeightMap(t2)) => {
+                self.aabb_to_heightmap(t1_pos, t1, t2_pos, t2)
+            },
+            (ColliderType::Aabb(t1), ColliderType::Aabb(t2)) => {
+                self.aabb_to_aabb(t1_pos, t1, t2_pos, t2)
+            },
+            (ColliderType::Capsule { radius, half_height }, ColliderType::Plane(t2)) => {
+                self.capsule_to_plane(t1_pos, *radius, *half_height, t2_pos, t2)
+            },
+            (ColliderType::Capsule { radius, half_height }, ColliderType::HeightMap(t2)) => {
+                self.capsule_to_heightmap(t1_pos, *radius, *half_height, t2_pos, t2)
+            },
+            (ColliderType::Capsule { radius, half_height }, ColliderType::Aabb(t2)) => {
+                self.capsule_to_aabb(t1_pos, *radius, *half_height, t2_pos, t2)
+            },
+            (
+                ColliderType::Capsule { radius: t1_radius, half_height: t1_half_height },
+                ColliderType::Capsule { radius: t2_radius, half_height: t2_half_height },
+            ) => {
+                self.capsule_to_capsule(
+                    t1_pos, *t1_radius, *t1_half_height,
+                    t2_pos, *t2_radius, *t2_half_height,
+                )
+            },
+            (ColliderType::Sphere(t1), ColliderType::TriMesh(t2)) => {
+                self.capsule_to_trimesh(t1_pos, *t1, 0.0, t2_pos, t2)
+            },
+            (ColliderType::Capsule { radius, half_height }, ColliderType::TriMesh(t2)) => {
+                self.capsule_to_trimesh(t1_pos, *radius, *half_height, t2_pos, t2)
+            },
+            (ColliderType::Aabb(t1), ColliderType::TriMesh(t2)) => {
+                self.aabb_to_trimesh(t1_pos, t1, t2_pos, t2)
+            },
+            _ if is_level_geometry(t1_collider) && is_level_geometry(t2_collider) => None,
+            // every other pairing is covered above with the arguments swapped
+            _ => {
+                self.check_collision(t2_pos, t2_collider, t1_pos, t1_collider)
+                    .map(|(depth, normal)| (-depth, negate(&normal)))
             },
-            ColliderType::Plane(_) => None,
-            ColliderType::HeightMap(_) => None,
         }
     }
 
@@ -103,7 +253,7 @@ impl CollisionDetection {
         &self,
         t1_pos: &Position, t1_radius: f64,
         t2_pos: &Position, t2_radius: f64,
-    ) -> Option<(nalgebra::Vector3<f64>, nalgebra::Unit<nalgebra::Vector3<f64>>)>
+    ) -> Option<Contact>
     {
         let min_distance = t1_radius + t2_radius;
         let collision_vec = t2_pos.0 - t1_pos.0;
@@ -135,7 +285,7 @@ impl CollisionDetection {
         &self,
         t1_pos: &Position, t1_radius: f64,
         t2_pos: &Position, t2_normal: &nalgebra::Unit<nalgebra::Vector3<f64>>,
-    ) -> Option<(nalgebra::Vector3<f64>, nalgebra::Unit<nalgebra::Vector3<f64>>)>
+    ) -> Option<Contact>
     {
         let collision_vec = t1_pos.0 - t2_pos.0;
         let distance = collision_vec.dot(t2_normal);
@@ -153,64 +303,419 @@ impl CollisionDetection {
         &self,
         t1_pos: &Position, t1_radius: f64,
         t2_pos: &Position, t2_data: &HeightMap,
-    ) -> Option<(nalgebra::Vector3<f64>, nalgebra::Unit<nalgebra::Vector3<f64>>)>
+    ) -> Option<Contact>
+    {
+        self.capsule_to_heightmap(t1_pos, t1_radius, 0.0, t2_pos, t2_data)
+    }
+
+    fn sphere_to_aabb(
+        &self,
+        t1_pos: &Position, t1_radius: f64,
+        t2_pos: &Position, t2_half_extents: &nalgebra::Vector3<f64>,
+    ) -> Option<Contact>
+    {
+        self.capsule_to_aabb(t1_pos, t1_radius, 0.0, t2_pos, t2_half_extents)
+    }
+
+    fn sphere_to_capsule(
+        &self,
+        t1_pos: &Position, t1_radius: f64,
+        t2_pos: &Position, t2_radius: f64, t2_half_height: f64,
+    ) -> Option<Contact>
     {
-        // TODO: change to generic AABB check
-        if t1_pos.0.x <= t2_pos.0.x ||
-            t1_pos.0.x >= t2_pos.0.x + t2_data.size as f64 ||
-            t1_pos.0.z <= t2_pos.0.z ||
-            t1_pos.0.z >= t2_pos.0.z + t2_data.size as f64
-        {
-            return None
+        let closest = closest_on_segment(&t2_pos.0, t2_half_height, t1_pos.0.y);
+        self.sphere_to_sphere(t1_pos, t1_radius, &Position(closest), t2_radius)
+    }
+
+    fn aabb_to_plane(
+        &self,
+        t1_pos: &Position, t1_half_extents: &nalgebra::Vector3<f64>,
+        t2_pos: &Position, t2_normal: &nalgebra::Unit<nalgebra::Vector3<f64>>,
+    ) -> Option<Contact>
+    {
+        // distance from the centre to the corner furthest towards the plane
+        let extent = t1_half_extents.dot(&t2_normal.as_ref().abs());
+        self.sphere_to_plane(t1_pos, extent, t2_pos, t2_normal)
+    }
+
+    fn aabb_to_heightmap(
+        &self,
+        t1_pos: &Position, t1_half_extents: &nalgebra::Vector3<f64>,
+        t2_pos: &Position, t2_data: &HeightMap,
+    ) -> Option<Contact>
+    {
+        // sample the corners and the centre of the bottom face, which faces
+        // positive y
+        let bottom = t1_pos.0.y + t1_half_extents.y;
+        let samples = [
+            (0.0, 0.0),
+            (-1.0, -1.0),
+            (1.0, -1.0),
+            (-1.0, 1.0),
+            (1.0, 1.0),
+        ];
+
+        samples.iter()
+            .filter_map(|(x, z)| {
+                let point = nalgebra::Point3::new(
+                    t1_pos.0.x + x * t1_half_extents.x,
+                    bottom,
+                    t1_pos.0.z + z * t1_half_extents.z,
+                );
+                self.sphere_to_heightmap(&Position(point), 0.0, t2_pos, t2_data)
+            })
+            .fold(None, deepest)
+    }
+
+    fn aabb_to_aabb(
+        &self,
+        t1_pos: &Position, t1_half_extents: &nalgebra::Vector3<f64>,
+        t2_pos: &Position, t2_half_extents: &nalgebra::Vector3<f64>,
+    ) -> Option<Contact>
+    {
+        let offset = t2_pos.0 - t1_pos.0;
+        let overlap = t1_half_extents + t2_half_extents - offset.abs();
+
+        // separate along the axis of least penetration
+        let axis = overlap.imin();
+        let depth = overlap[axis];
+        if depth < self.min_collision_depth {
+            return None;
         }
 
-        let map_offs = t1_pos.0 - t2_pos.0;
-        let grid_x = map_offs.x as usize;
-        let grid_y = map_offs.z as usize;
+        let mut normal = nalgebra::Vector3::zeros();
+        normal[axis] = if offset[axis] < 0.0 { -1.0 } else { 1.0 };
+        let normal = nalgebra::Unit::new_unchecked(normal);
 
-        let quad = (
-            -t2_data.get(grid_x, grid_y).unwrap() as f64,
-            -t2_data.get(grid_x + 1, grid_y).unwrap() as f64,
-            -t2_data.get(grid_x, grid_y + 1).unwrap() as f64,
-            -t2_data.get(grid_x + 1, grid_y + 1).unwrap() as f64,
-        );
-        let quad_x = map_offs.x - grid_x as f64;
-        let quad_y = map_offs.y - grid_y as f64;
-
-        // get the normal for the relevant height map triangle
-        let normal: _;
-        if quad_x + quad_y <= 0.5 {
-            // cross(quad.1 - quad.0, quad.2 - quad.0)
-            normal = nalgebra::Unit::new_normalize(
-                nalgebra::Vector3::<f64>::new(1.0, quad.1 - quad.0, 0.0).cross(
-                    &nalgebra::Vector3::<f64>::new(0.0, quad.2 - quad.0, 1.0)
-                )
-            );
+        Some((normal.as_ref() * depth, normal))
+    }
+
+    fn capsule_to_plane(
+        &self,
+        t1_pos: &Position, t1_radius: f64, t1_half_height: f64,
+        t2_pos: &Position, t2_normal: &nalgebra::Unit<nalgebra::Vector3<f64>>,
+    ) -> Option<Contact>
+    {
+        // the end of the segment closest to the plane
+        let y = t1_pos.0.y - t1_half_height * t2_normal.y.signum();
+        let end = nalgebra::Point3::new(t1_pos.0.x, y, t1_pos.0.z);
+
+        self.sphere_to_plane(&Position(end), t1_radius, t2_pos, t2_normal)
+    }
+
+    fn capsule_to_heightmap(
+        &self,
+        t1_pos: &Position, t1_radius: f64, t1_half_height: f64,
+        t2_pos: &Position, t2_data: &HeightMap,
+    ) -> Option<Contact>
+    {
+        if t2_data.size < 2 {
+            return None;
+        }
+
+        // test every triangle in the cells under the capsule
+        let center = t1_pos.0 - t2_pos.0.coords;
+        let last_cell = (t2_data.size - 2) as f64;
+        let cells = |position: f64| {
+            let min = (position - t1_radius).floor();
+            let max = (position + t1_radius).floor();
+            if max < 0.0 || min > last_cell {
+                None
+            } else {
+                Some((min.max(0.0) as usize, max.min(last_cell) as usize))
+            }
+        };
+        let (min_x, max_x) = cells(center.x)?;
+        let (min_z, max_z) = cells(center.z)?;
+
+        let half_height = nalgebra::Vector3::new(0.0, t1_half_height, 0.0);
+        let (top, bottom) = (center - half_height, center + half_height);
+
+        let mut result = None;
+        for z in min_z..=max_z {
+            for x in min_x..=max_x {
+                for triangle in &Triangle::from_heightmap_cell(t2_data, x, z) {
+                    let contact = self.capsule_to_terrain(&top, &bottom, t1_radius, triangle);
+                    if let Some(contact) = contact {
+                        result = deepest(result, contact);
+                    }
+                }
+            }
+        }
+
+        result
+    }
+
+    /**
+     * Terrain is one-sided: anything below a triangle is pushed back up
+     * through it, rather than out of its underside
+     */
+    fn capsule_to_terrain(
+        &self,
+        top: &nalgebra::Point3<f64>,
+        bottom: &nalgebra::Point3<f64>,
+        radius: f64,
+        triangle: &Triangle,
+    ) -> Option<Contact>
+    {
+        let up = triangle.normal()?;
+        let (on_segment, on_triangle) = triangle.closest_to_segment(top, bottom);
+        let offset = on_triangle - on_segment;
+
+        let beneath = match nalgebra::Unit::try_new(offset, f64::EPSILON) {
+            // the segment passes through the triangle
+            None => true,
+            // below the triangle, or beside it below its plane
+            Some(direction) if (on_segment - triangle.a).dot(&up) < 0.0 => {
+                if direction.dot(&up) < 1.0 - 1e-9 {
+                    return None;
+                }
+                true
+            },
+            Some(_) => false,
+        };
+
+        let (depth, normal) = if beneath {
+            // the bottom of the segment is always the deepest below a
+            // triangle whose normal points upwards
+            let below = -(bottom - triangle.a).dot(&up);
+            (radius + below.max(0.0), negate(&up))
         } else {
-            // cross(quad.3 - quad.1, quad.2 - quad.1)
-            normal = nalgebra::Unit::new_normalize(
-                nalgebra::Vector3::<f64>::new(0.0, quad.3 - quad.1, 1.0).cross(
-                    &nalgebra::Vector3::<f64>::new(-1.0, quad.2 - quad.1, 1.0)
-                )
-            );
+            if offset.norm_squared() > radius * radius {
+                return None;
+            }
+            (radius - offset.norm(), nalgebra::Unit::new_normalize(offset))
+        };
+
+        if depth < self.min_collision_depth {
+            return None;
         }
 
-        let quad_pos = nalgebra::Point3::new(
-            t2_pos.0.x + grid_x as f64,
-            t2_pos.0.y + quad.0,
-            t2_pos.0.z + grid_y as f64
+        Some((normal.as_ref() * depth, normal))
+    }
+
+    fn capsule_to_aabb(
+        &self,
+        t1_pos: &Position, t1_radius: f64, t1_half_height: f64,
+        t2_pos: &Position, t2_half_extents: &nalgebra::Vector3<f64>,
+    ) -> Option<Contact>
+    {
+        let min = t2_pos.0 - t2_half_extents;
+        let max = t2_pos.0 + t2_half_extents;
+
+        // as the segment is vertical, its closest point to the box is at the
+        // y closest to the box's y range
+        let y = t1_pos.0.y.max(min.y).min(max.y);
+        let point = closest_on_segment(&t1_pos.0, t1_half_height, y);
+
+        let closest = nalgebra::Point3::new(
+            point.x.max(min.x).min(max.x),
+            point.y.max(min.y).min(max.y),
+            point.z.max(min.z).min(max.z),
         );
-        let collision_vec = t1_pos.0 - quad_pos;
-        let distance = collision_vec.dot(normal.as_ref());
 
-        let depth = t1_radius - distance;
-        if depth >= self.min_collision_depth {
-            let normal = nalgebra::Unit::new_unchecked(normal.as_ref() * -1.0);
-            Some((normal.as_ref() * depth, normal))
-        } else {
-            None
+        let offset = closest - point;
+        if offset.norm_squared() > t1_radius * t1_radius {
+            return None;
+        }
+
+        match nalgebra::Unit::try_new(offset, f64::EPSILON) {
+            Some(normal) => {
+                let depth = t1_radius - offset.norm();
+                if depth < self.min_collision_depth {
+                    return None;
+                }
+                Some((normal.as_ref() * depth, normal))
+            },
+            // the segment is inside the box, push the whole capsule out
+            None => {
+                let capsule_extents = nalgebra::Vector3::new(
+                    t1_radius,
+                    t1_half_height + t1_radius,
+                    t1_radius,
+                );
+                self.aabb_to_aabb(t1_pos, &capsule_extents, t2_pos, t2_half_extents)
+            },
         }
     }
+
+    fn capsule_to_capsule(
+        &self,
+        t1_pos: &Position, t1_radius: f64, t1_half_height: f64,
+        t2_pos: &Position, t2_radius: f64, t2_half_height: f64,
+    ) -> Option<Contact>
+    {
+        // both segments are vertical: where their y ranges overlap the
+        // closest points share the middle of the overlap, otherwise the
+        // closest points are the nearest ends
+        let low = (t1_pos.0.y - t1_half_height).max(t2_pos.0.y - t2_half_height);
+        let high = (t1_pos.0.y + t1_half_height).min(t2_pos.0.y + t2_half_height);
+        let y = (low + high) * 0.5;
+
+        self.sphere_to_sphere(
+            &Position(closest_on_segment(&t1_pos.0, t1_half_height, y)), t1_radius,
+            &Position(closest_on_segment(&t2_pos.0, t2_half_height, y)), t2_radius,
+        )
+    }
+
+    fn capsule_to_trimesh(
+        &self,
+        t1_pos: &Position, t1_radius: f64, t1_half_height: f64,
+        t2_pos: &Position, t2_mesh: &TriMesh,
+    ) -> Option<Contact>
+    {
+        // triangles are relative to the mesh position
+        let center = t1_pos.0 - t2_pos.0.coords;
+        let half_height = nalgebra::Vector3::new(0.0, t1_half_height, 0.0);
+        let bounds = Aabb::from_center(
+            &center,
+            &nalgebra::Vector3::new(t1_radius, t1_half_height + t1_radius, t1_radius),
+        );
+
+        let mut result = None;
+        t2_mesh.query(&bounds, |triangle| {
+            let contact = self.capsule_to_triangle(
+                &center, &(center - half_height), &(center + half_height), t1_radius,
+                triangle,
+            );
+            if let Some(contact) = contact {
+                result = deepest(result.take(), contact);
+            }
+        });
+
+        result
+    }
+
+    fn aabb_to_trimesh(
+        &self,
+        t1_pos: &Position, t1_half_extents: &nalgebra::Vector3<f64>,
+        t2_pos: &Position, t2_mesh: &TriMesh,
+    ) -> Option<Contact>
+    {
+        // triangles are relative to the mesh position
+        let center = t1_pos.0 - t2_pos.0.coords;
+        let bounds = Aabb::from_center(&center, t1_half_extents);
+
+        let mut result = None;
+        t2_mesh.query(&bounds, |triangle| {
+            if let Some(contact) = self.aabb_to_triangle(&center, t1_half_extents, triangle) {
+                result = deepest(result.take(), contact);
+            }
+        });
+
+        result
+    }
+
+    /**
+     * Separating axis test: the box is pushed out along whichever of its
+     * own axes, the triangle's normal and the cross products of their edges
+     * it overlaps the triangle least on
+     */
+    fn aabb_to_triangle(
+        &self,
+        center: &nalgebra::Point3<f64>,
+        half_extents: &nalgebra::Vector3<f64>,
+        triangle: &Triangle,
+    ) -> Option<Contact>
+    {
+        let corners = [triangle.a, triangle.b, triangle.c];
+        let edges = [triangle.b - triangle.a, triangle.c - triangle.b, triangle.a - triangle.c];
+
+        let box_axes = [
+            nalgebra::Vector3::x(),
+            nalgebra::Vector3::y(),
+            nalgebra::Vector3::z(),
+        ];
+
+        let mut axes = box_axes.to_vec();
+        if let Some(normal) = triangle.normal() {
+            axes.push(normal.into_inner());
+        }
+        for edge in &edges {
+            for box_axis in &box_axes {
+                axes.push(box_axis.cross(edge));
+            }
+        }
+
+        let mut result: Option<(f64, nalgebra::Unit<nalgebra::Vector3<f64>>)> = None;
+        for axis in axes {
+            // edges parallel to a box axis give no axis of their own
+            let axis = match nalgebra::Unit::try_new(axis, f64::EPSILON) {
+                Some(axis) => axis,
+                None => continue,
+            };
+
+            let middle = center.coords.dot(&axis);
+            let radius = half_extents.dot(&axis.abs());
+            let projections = corners.iter().map(|corner| corner.coords.dot(&axis));
+            let min = projections.clone().fold(f64::INFINITY, f64::min);
+            let max = projections.fold(f64::NEG_INFINITY, f64::max);
+
+            // overlap with the triangle ahead along the axis and behind it
+            let ahead = middle + radius - min;
+            let behind = max - (middle - radius);
+            if ahead <= 0.0 || behind <= 0.0 {
+                return None;
+            }
+
+            let (depth, normal) = if ahead <= behind {
+                (ahead, axis)
+            } else {
+                (behind, negate(&axis))
+            };
+            match result {
+                Some((least, _)) if least <= depth => (),
+                _ => result = Some((depth, normal)),
+            }
+        }
+
+        let (depth, normal) = result?;
+        if depth < self.min_collision_depth {
+            return None;
+        }
+
+        Some((normal.as_ref() * depth, normal))
+    }
+
+    fn capsule_to_triangle(
+        &self,
+        center: &nalgebra::Point3<f64>,
+        start: &nalgebra::Point3<f64>,
+        end: &nalgebra::Point3<f64>,
+        radius: f64,
+        triangle: &Triangle,
+    ) -> Option<Contact>
+    {
+        let (on_segment, on_triangle) = triangle.closest_to_segment(start, end);
+        let offset = on_triangle - on_segment;
+        if offset.norm_squared() > radius * radius {
+            return None;
+        }
+
+        let (depth, normal) = match nalgebra::Unit::try_new(offset, f64::EPSILON) {
+            Some(normal) => (radius - offset.norm(), normal),
+            None => {
+                // the segment passes through the triangle, push it back out
+                // to the side its centre is on
+                let face = triangle.normal()?;
+                let normal = if (center - triangle.a).dot(&face) >= 0.0 {
+                    negate(&face)
+                } else {
+                    face
+                };
+                let past = (start - triangle.a).dot(&normal)
+                    .max((end - triangle.a).dot(&normal))
+                    .max(0.0);
+                (radius + past, normal)
+            },
+        };
+
+        if depth < self.min_collision_depth {
+            return None;
+        }
+
+        Some((normal.as_ref() * depth, normal))
+    }
 }
 
 #[cfg(test)]
@@ -219,7 +724,7 @@ mod tests {
 
     #[test]
     fn test_sphere_to_heightmap_collision() {
-        let detection = CollisionDetection { min_collision_depth: 0.001 };
+        let detection = CollisionDetection::new(&PhysicsConfig::default());
 
         let heightmap_pos = Position(nalgebra::Point3::<f64>::new(0.0, 0.0, 0.0));
         let heightmap_data = vec![
@@ -264,4 +769,540 @@ mod tests {
         );
         assert!(result.is_some());
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn test_sphere_to_heightmap_edges() {
+        let detection = CollisionDetection::new(&PhysicsConfig::default());
+
+        let heightmap_pos = Position(nalgebra::Point3::<f64>::new(0.0, 0.0, 0.0));
+        let heightmap = flat_heightmap();
+
+        // past the last row of vertices, touching the edge
+        let sphere_pos = Position(nalgebra::Point3::<f64>::new(2.25, -0.25, 1.0));
+        let result = detection.sphere_to_heightmap(&sphere_pos, 0.5, &heightmap_pos, &heightmap);
+        let (_, normal) = result.unwrap();
+        assert!(normal.x < 0.0 && normal.y > 0.0);
+
+        let sphere_pos = Position(nalgebra::Point3::<f64>::new(1.0, -0.25, 2.75));
+        let result = detection.sphere_to_heightmap(&sphere_pos, 0.5, &heightmap_pos, &heightmap);
+        assert!(result.is_none());
+
+        let sphere_pos = Position(nalgebra::Point3::<f64>::new(-0.25, -0.25, -0.25));
+        let result = detection.sphere_to_heightmap(&sphere_pos, 0.5, &heightmap_pos, &heightmap);
+        assert!(result.is_some());
+
+        // on the far corner vertex
+        let sphere_pos = Position(nalgebra::Point3::<f64>::new(2.0, -0.25, 2.0));
+        let result = detection.sphere_to_heightmap(&sphere_pos, 0.5, &heightmap_pos, &heightmap);
+        let (depth, normal) = result.unwrap();
+        assert_eq!(depth.y, 0.25);
+        assert_eq!(normal.y, 1.0);
+    }
+
+    #[test]
+    fn test_sphere_to_heightmap_neighbouring_cells() {
+        let detection = CollisionDetection::new(&PhysicsConfig::default());
+
+        // flat first column of cells, the second slopes up towards +x
+        let heightmap_pos = Position(nalgebra::Point3::<f64>::new(0.0, 0.0, 0.0));
+        let heightmap_data = vec![
+            0.0, 0.0, 1.0,
+            0.0, 0.0, 1.0,
+            0.0, 0.0, 1.0,
+        ];
+        let heightmap = HeightMap::new(heightmap_data, 3, 1.0);
+
+        // centre over the flat cell, clear of it, but overlapping the slope
+        let sphere_pos = Position(nalgebra::Point3::<f64>::new(0.9, -0.5, 0.5));
+        let result = detection.sphere_to_heightmap(&sphere_pos, 0.5, &heightmap_pos, &heightmap);
+        let (depth, normal) = result.unwrap();
+        assert!(depth.norm() > 0.05);
+        assert!((normal.x - normal.y).abs() < 1e-9);
+        assert!(normal.x > 0.0);
+    }
+
+    #[test]
+    fn test_sphere_to_heightmap_diagonal_split() {
+        let detection = CollisionDetection::new(&PhysicsConfig::default());
+
+        // single cell with only the far corner raised: the triangle nearest
+        // the origin is flat and the other slopes up towards the corner
+        let heightmap_pos = Position(nalgebra::Point3::<f64>::new(0.0, 0.0, 0.0));
+        let heightmap_data = vec![
+            0.0, 0.0,
+            0.0, 1.0,
+        ];
+        let heightmap = HeightMap::new(heightmap_data, 2, 1.0);
+
+        // beyond the middle of the cell, but still on the flat triangle
+        let sphere_pos = Position(nalgebra::Point3::<f64>::new(0.625, -0.0625, 0.3125));
+        let result = detection.sphere_to_heightmap(&sphere_pos, 0.125, &heightmap_pos, &heightmap);
+        let (depth, normal) = result.unwrap();
+        assert_eq!(depth.y, 0.0625);
+        assert_eq!(normal.y, 1.0);
+
+        // on the sloped triangle, half way up
+        let sphere_pos = Position(nalgebra::Point3::<f64>::new(0.75, -0.5, 0.75));
+        let result = detection.sphere_to_heightmap(&sphere_pos, 0.125, &heightmap_pos, &heightmap);
+        let (_, normal) = result.unwrap();
+        assert!(normal.x > 0.0 && normal.z > 0.0);
+        assert!((normal.x - normal.y).abs() < 1e-9);
+        assert!((normal.z - normal.y).abs() < 1e-9);
+    }
+
+    fn flat_heightmap() -> HeightMap {
+        HeightMap::new(vec![0.0; 9], 3, 1.0)
+    }
+
+    #[test]
+    fn test_sphere_to_aabb_collision() {
+        let detection = CollisionDetection::new(&PhysicsConfig::default());
+
+        let box_pos = Position(nalgebra::Point3::<f64>::new(0.0, 0.0, 0.0));
+        let box_extents = nalgebra::Vector3::<f64>::new(1.0, 1.0, 1.0);
+
+        let sphere_pos = Position(nalgebra::Point3::<f64>::new(2.0, 0.0, 0.0));
+        let result = detection.sphere_to_aabb(&sphere_pos, 0.5, &box_pos, &box_extents);
+        assert!(result.is_none());
+
+        let sphere_pos = Position(nalgebra::Point3::<f64>::new(1.25, 0.0, 0.0));
+        let result = detection.sphere_to_aabb(&sphere_pos, 0.5, &box_pos, &box_extents);
+        let (depth, normal) = result.unwrap();
+        assert_eq!(depth.x, -0.25);
+        assert_eq!(normal.x, -1.0);
+
+        // centre inside the box, pushed out through the nearest face
+        let sphere_pos = Position(nalgebra::Point3::<f64>::new(0.75, 0.0, 0.0));
+        let result = detection.sphere_to_aabb(&sphere_pos, 0.5, &box_pos, &box_extents);
+        let (depth, normal) = result.unwrap();
+        assert_eq!(depth.x, -0.75);
+        assert_eq!(normal.x, -1.0);
+    }
+
+    #[test]
+    fn test_sphere_to_capsule_collision() {
+        let detection = CollisionDetection::new(&PhysicsConfig::default());
+
+        let capsule_pos = Position(nalgebra::Point3::<f64>::new(0.0, 0.0, 0.0));
+
+        let sphere_pos = Position(nalgebra::Point3::<f64>::new(1.5, -0.5, 0.0));
+        let result = detection.sphere_to_capsule(&sphere_pos, 0.5, &capsule_pos, 0.5, 1.0);
+        assert!(result.is_none());
+
+        let sphere_pos = Position(nalgebra::Point3::<f64>::new(0.75, -0.5, 0.0));
+        let result = detection.sphere_to_capsule(&sphere_pos, 0.5, &capsule_pos, 0.5, 1.0);
+        let (depth, normal) = result.unwrap();
+        assert_eq!(depth.x, -0.25);
+        assert_eq!(normal.x, -1.0);
+
+        // resting on the top cap
+        let sphere_pos = Position(nalgebra::Point3::<f64>::new(0.0, -1.75, 0.0));
+        let result = detection.sphere_to_capsule(&sphere_pos, 0.5, &capsule_pos, 0.5, 1.0);
+        let (depth, normal) = result.unwrap();
+        assert_eq!(depth.y, 0.25);
+        assert_eq!(normal.y, 1.0);
+    }
+
+    #[test]
+    fn test_aabb_to_plane_collision() {
+        let detection = CollisionDetection::new(&PhysicsConfig::default());
+
+        let plane_pos = Position(nalgebra::Point3::<f64>::new(0.0, 0.0, 0.0));
+        let plane_normal = nalgebra::Unit::new_normalize(
+            nalgebra::Vector3::<f64>::new(0.0, -1.0, 0.0)
+        );
+        let box_extents = nalgebra::Vector3::<f64>::new(1.0, 0.5, 1.0);
+
+        let box_pos = Position(nalgebra::Point3::<f64>::new(0.0, -1.0, 0.0));
+        let result = detection.aabb_to_plane(&box_pos, &box_extents, &plane_pos, &plane_normal);
+        assert!(result.is_none());
+
+        let box_pos = Position(nalgebra::Point3::<f64>::new(0.0, -0.25, 0.0));
+        let result = detection.aabb_to_plane(&box_pos, &box_extents, &plane_pos, &plane_normal);
+        let (depth, normal) = result.unwrap();
+        assert_eq!(depth.y, 0.25);
+        assert_eq!(normal.y, 1.0);
+    }
+
+    #[test]
+    fn test_aabb_to_heightmap_collision() {
+        let detection = CollisionDetection::new(&PhysicsConfig::default());
+
+        let heightmap_pos = Position(nalgebra::Point3::<f64>::new(0.0, 0.0, 0.0));
+        let heightmap = flat_heightmap();
+        let box_extents = nalgebra::Vector3::<f64>::new(0.25, 0.25, 0.25);
+
+        let box_pos = Position(nalgebra::Point3::<f64>::new(1.0, -1.0, 1.0));
+        let result = detection.aabb_to_heightmap(&box_pos, &box_extents, &heightmap_pos, &heightmap);
+        assert!(result.is_none());
+
+        let box_pos = Position(nalgebra::Point3::<f64>::new(1.0, -0.125, 1.0));
+        let result = detection.aabb_to_heightmap(&box_pos, &box_extents, &heightmap_pos, &heightmap);
+        let (depth, normal) = result.unwrap();
+        assert_eq!(depth.y, 0.125);
+        assert_eq!(normal.y, 1.0);
+    }
+
+    #[test]
+    fn test_aabb_to_aabb_collision() {
+        let detection = CollisionDetection::new(&PhysicsConfig::default());
+
+        let t1_pos = Position(nalgebra::Point3::<f64>::new(0.0, 0.0, 0.0));
+        let t1_extents = nalgebra::Vector3::<f64>::new(1.0, 1.0, 1.0);
+        let t2_extents = nalgebra::Vector3::<f64>::new(0.5, 0.5, 0.5);
+
+        // touching is not colliding
+        let t2_pos = Position(nalgebra::Point3::<f64>::new(1.5, 0.25, 0.0));
+        let result = detection.aabb_to_aabb(&t1_pos, &t1_extents, &t2_pos, &t2_extents);
+        assert!(result.is_none());
+
+        let t2_pos = Position(nalgebra::Point3::<f64>::new(1.25, 0.25, 0.0));
+        let result = detection.aabb_to_aabb(&t1_pos, &t1_extents, &t2_pos, &t2_extents);
+        let (depth, normal) = result.unwrap();
+        assert_eq!(depth.x, 0.25);
+        assert_eq!(normal.x, 1.0);
+
+        let t2_pos = Position(nalgebra::Point3::<f64>::new(0.25, -1.25, 0.0));
+        let result = detection.aabb_to_aabb(&t1_pos, &t1_extents, &t2_pos, &t2_extents);
+        let (depth, normal) = result.unwrap();
+        assert_eq!(depth.y, -0.25);
+        assert_eq!(normal.y, -1.0);
+    }
+
+    #[test]
+    fn test_capsule_to_plane_collision() {
+        let detection = CollisionDetection::new(&PhysicsConfig::default());
+
+        let plane_pos = Position(nalgebra::Point3::<f64>::new(0.0, 0.0, 0.0));
+        let plane_normal = nalgebra::Unit::new_normalize(
+            nalgebra::Vector3::<f64>::new(0.0, -1.0, 0.0)
+        );
+
+        let capsule_pos = Position(nalgebra::Point3::<f64>::new(0.0, -2.0, 0.0));
+        let result = detection.capsule_to_plane(&capsule_pos, 0.5, 1.0, &plane_pos, &plane_normal);
+        assert!(result.is_none());
+
+        let capsule_pos = Position(nalgebra::Point3::<f64>::new(0.0, -1.25, 0.0));
+        let result = detection.capsule_to_plane(&capsule_pos, 0.5, 1.0, &plane_pos, &plane_normal);
+        let (depth, normal) = result.unwrap();
+        assert_eq!(depth.y, 0.25);
+        assert_eq!(normal.y, 1.0);
+    }
+
+    #[test]
+    fn test_capsule_to_heightmap_collision() {
+        let detection = CollisionDetection::new(&PhysicsConfig::default());
+
+        let heightmap_pos = Position(nalgebra::Point3::<f64>::new(0.0, 0.0, 0.0));
+        let heightmap = flat_heightmap();
+
+        let capsule_pos = Position(nalgebra::Point3::<f64>::new(1.0, -2.0, 1.0));
+        let result = detection.capsule_to_heightmap(&capsule_pos, 0.5, 1.0, &heightmap_pos, &heightmap);
+        assert!(result.is_none());
+
+        let capsule_pos = Position(nalgebra::Point3::<f64>::new(1.0, -1.25, 1.0));
+        let result = detection.capsule_to_heightmap(&capsule_pos, 0.5, 1.0, &heightmap_pos, &heightmap);
+        let (depth, normal) = result.unwrap();
+        assert_eq!(depth.y, 0.25);
+        assert_eq!(normal.y, 1.0);
+    }
+
+    #[test]
+    fn test_capsule_to_aabb_collision() {
+        let detection = CollisionDetection::new(&PhysicsConfig::default());
+
+        let box_pos = Position(nalgebra::Point3::<f64>::new(0.0, 0.0, 0.0));
+        let box_extents = nalgebra::Vector3::<f64>::new(1.0, 1.0, 1.0);
+
+        let capsule_pos = Position(nalgebra::Point3::<f64>::new(2.0, -1.5, 0.0));
+        let result = detection.capsule_to_aabb(&capsule_pos, 0.5, 1.0, &box_pos, &box_extents);
+        assert!(result.is_none());
+
+        // side of the segment against the box edge
+        let capsule_pos = Position(nalgebra::Point3::<f64>::new(1.25, -1.5, 0.0));
+        let result = detection.capsule_to_aabb(&capsule_pos, 0.5, 1.0, &box_pos, &box_extents);
+        let (depth, normal) = result.unwrap();
+        assert_eq!(depth.x, -0.25);
+        assert_eq!(normal.x, -1.0);
+
+        // standing on top of the box
+        let capsule_pos = Position(nalgebra::Point3::<f64>::new(0.0, -2.25, 0.0));
+        let result = detection.capsule_to_aabb(&capsule_pos, 0.5, 1.0, &box_pos, &box_extents);
+        let (depth, normal) = result.unwrap();
+        assert_eq!(depth.y, 0.25);
+        assert_eq!(normal.y, 1.0);
+
+        // segment inside the box
+        let capsule_pos = Position(nalgebra::Point3::<f64>::new(0.5, 0.0, 0.0));
+        let result = detection.capsule_to_aabb(&capsule_pos, 0.5, 1.0, &box_pos, &box_extents);
+        let (depth, normal) = result.unwrap();
+        assert_eq!(depth.x, -1.0);
+        assert_eq!(normal.x, -1.0);
+    }
+
+    #[test]
+    fn test_capsule_to_capsule_collision() {
+        let detection = CollisionDetection::new(&PhysicsConfig::default());
+
+        let t1_pos = Position(nalgebra::Point3::<f64>::new(0.0, 0.0, 0.0));
+
+        let t2_pos = Position(nalgebra::Point3::<f64>::new(1.5, 0.5, 0.0));
+        let result = detection.capsule_to_capsule(&t1_pos, 0.5, 1.0, &t2_pos, 0.5, 1.0);
+        assert!(result.is_none());
+
+        let t2_pos = Position(nalgebra::Point3::<f64>::new(0.75, 0.5, 0.0));
+        let result = detection.capsule_to_capsule(&t1_pos, 0.5, 1.0, &t2_pos, 0.5, 1.0);
+        let (depth, normal) = result.unwrap();
+        assert_eq!(depth.x, 0.25);
+        assert_eq!(normal.x, 1.0);
+
+        // stacked on top of each other
+        let t2_pos = Position(nalgebra::Point3::<f64>::new(0.0, -2.75, 0.0));
+        let result = detection.capsule_to_capsule(&t1_pos, 0.5, 1.0, &t2_pos, 0.5, 1.0);
+        let (depth, normal) = result.unwrap();
+        assert_eq!(depth.y, -0.25);
+        assert_eq!(normal.y, -1.0);
+    }
+
+    #[test]
+    fn test_swapped_pairs() {
+        let detection = CollisionDetection::new(&PhysicsConfig::default());
+
+        let plane_pos = Position(nalgebra::Point3::<f64>::new(0.0, 0.0, 0.0));
+        let plane = ColliderType::Plane(nalgebra::Unit::new_normalize(
+            nalgebra::Vector3::<f64>::new(0.0, -1.0, 0.0)
+        ));
+        let capsule_pos = Position(nalgebra::Point3::<f64>::new(0.0, -1.25, 0.0));
+        let capsule = ColliderType::Capsule { radius: 0.5, half_height: 1.0 };
+
+        let (depth, normal) = detection
+            .check_collision(&capsule_pos, &capsule, &plane_pos, &plane)
+            .unwrap();
+        let (swapped_depth, swapped_normal) = detection
+            .check_collision(&plane_pos, &plane, &capsule_pos, &capsule)
+            .unwrap();
+        assert_eq!(swapped_depth, -depth);
+        assert_eq!(swapped_normal.as_ref(), &-normal.as_ref());
+
+        let heightmap = ColliderType::HeightMap(flat_heightmap());
+        assert!(detection.check_collision(&plane_pos, &plane, &plane_pos, &heightmap).is_none());
+    }
+
+    fn floor_trimesh() -> TriMesh {
+        // 4x4 floor at y = 0 facing negative y, with a wall along x = 4
+        TriMesh::new(vec![
+            Triangle::new(
+                nalgebra::Point3::new(0.0, 0.0, 0.0),
+                nalgebra::Point3::new(4.0, 0.0, 0.0),
+                nalgebra::Point3::new(0.0, 0.0, 4.0),
+            ),
+            Triangle::new(
+                nalgebra::Point3::new(4.0, 0.0, 0.0),
+                nalgebra::Point3::new(4.0, 0.0, 4.0),
+                nalgebra::Point3::new(0.0, 0.0, 4.0),
+            ),
+            Triangle::new(
+                nalgebra::Point3::new(4.0, 0.0, 0.0),
+                nalgebra::Point3::new(4.0, -4.0, 0.0),
+                nalgebra::Point3::new(4.0, 0.0, 4.0),
+            ),
+            Triangle::new(
+                nalgebra::Point3::new(4.0, -4.0, 0.0),
+                nalgebra::Point3::new(4.0, -4.0, 4.0),
+                nalgebra::Point3::new(4.0, 0.0, 4.0),
+            ),
+        ])
+    }
+
+    #[test]
+    fn test_sphere_to_trimesh_collision() {
+        let detection = CollisionDetection::new(&PhysicsConfig::default());
+
+        let mesh_pos = Position(nalgebra::Point3::<f64>::new(10.0, 10.0, 10.0));
+        let mesh = floor_trimesh();
+
+        let sphere_pos = Position(nalgebra::Point3::<f64>::new(11.0, 9.0, 11.0));
+        let result = detection.capsule_to_trimesh(&sphere_pos, 0.5, 0.0, &mesh_pos, &mesh);
+        assert!(result.is_none());
+
+        let sphere_pos = Position(nalgebra::Point3::<f64>::new(11.0, 9.75, 11.0));
+        let result = detection.capsule_to_trimesh(&sphere_pos, 0.5, 0.0, &mesh_pos, &mesh);
+        let (depth, normal) = result.unwrap();
+        assert_eq!(depth.y, 0.25);
+        assert_eq!(normal.y, 1.0);
+
+        // in the corner between the floor and the wall, deeper into the wall
+        let sphere_pos = Position(nalgebra::Point3::<f64>::new(13.75, 9.625, 11.0));
+        let result = detection.capsule_to_trimesh(&sphere_pos, 0.5, 0.0, &mesh_pos, &mesh);
+        let (depth, normal) = result.unwrap();
+        assert_eq!(depth.x, 0.25);
+        assert_eq!(normal.x, 1.0);
+
+        // centre below the floor
+        let sphere_pos = Position(nalgebra::Point3::<f64>::new(11.0, 10.25, 11.0));
+        let result = detection.capsule_to_trimesh(&sphere_pos, 0.5, 0.0, &mesh_pos, &mesh);
+        let (depth, normal) = result.unwrap();
+        assert_eq!(depth.y, -0.25);
+        assert_eq!(normal.y, -1.0);
+    }
+
+    #[test]
+    fn test_capsule_to_trimesh_collision() {
+        let detection = CollisionDetection::new(&PhysicsConfig::default());
+
+        let mesh_pos = Position(nalgebra::Point3::<f64>::new(0.0, 0.0, 0.0));
+        let mesh = floor_trimesh();
+
+        let capsule_pos = Position(nalgebra::Point3::<f64>::new(1.0, -2.0, 1.0));
+        let result = detection.capsule_to_trimesh(&capsule_pos, 0.5, 1.0, &mesh_pos, &mesh);
+        assert!(result.is_none());
+
+        let capsule_pos = Position(nalgebra::Point3::<f64>::new(1.0, -1.25, 1.0));
+        let result = detection.capsule_to_trimesh(&capsule_pos, 0.5, 1.0, &mesh_pos, &mesh);
+        let (depth, normal) = result.unwrap();
+        assert_eq!(depth.y, 0.25);
+        assert_eq!(normal.y, 1.0);
+
+        // sunk through the floor, pushed out by the whole overlap
+        let capsule_pos = Position(nalgebra::Point3::<f64>::new(1.0, -0.75, 1.0));
+        let result = detection.capsule_to_trimesh(&capsule_pos, 0.5, 1.0, &mesh_pos, &mesh);
+        let (depth, normal) = result.unwrap();
+        assert_eq!(depth.y, 0.75);
+        assert_eq!(normal.y, 1.0);
+
+        // against the wall
+        let capsule_pos = Position(nalgebra::Point3::<f64>::new(3.75, -2.0, 1.0));
+        let result = detection.capsule_to_trimesh(&capsule_pos, 0.5, 1.0, &mesh_pos, &mesh);
+        let (depth, normal) = result.unwrap();
+        assert_eq!(depth.x, 0.25);
+        assert_eq!(normal.x, 1.0);
+    }
+
+    #[test]
+    fn test_aabb_to_trimesh_collision() {
+        let detection = CollisionDetection::new(&PhysicsConfig::default());
+
+        let mesh_pos = Position(nalgebra::Point3::<f64>::new(10.0, 10.0, 10.0));
+        let mesh = ColliderType::TriMesh(floor_trimesh());
+        let aabb = ColliderType::Aabb(nalgebra::Vector3::new(0.5, 0.5, 0.5));
+
+        let aabb_pos = Position(nalgebra::Point3::<f64>::new(11.0, 9.0, 11.0));
+        assert!(detection.check_collision(&aabb_pos, &aabb, &mesh_pos, &mesh).is_none());
+
+        let aabb_pos = Position(nalgebra::Point3::<f64>::new(11.0, 9.75, 11.0));
+        let (depth, normal) = detection.check_collision(&aabb_pos, &aabb, &mesh_pos, &mesh).unwrap();
+        assert!((depth - nalgebra::Vector3::new(0.0, 0.25, 0.0)).norm() < 1e-9, "{}", depth);
+        assert!((normal.y - 1.0).abs() < 1e-9);
+
+        // in the corner between the floor and the wall, deeper into the wall
+        let aabb_pos = Position(nalgebra::Point3::<f64>::new(13.75, 9.625, 11.0));
+        let (depth, normal) = detection.check_collision(&aabb_pos, &aabb, &mesh_pos, &mesh).unwrap();
+        assert!((depth - nalgebra::Vector3::new(0.25, 0.0, 0.0)).norm() < 1e-9, "{}", depth);
+        assert!((normal.x - 1.0).abs() < 1e-9);
+
+        // and from the mesh's side
+        let (depth, normal) = detection.check_collision(&mesh_pos, &mesh, &aabb_pos, &aabb).unwrap();
+        assert!((depth - nalgebra::Vector3::new(-0.25, 0.0, 0.0)).norm() < 1e-9, "{}", depth);
+        assert!((normal.x + 1.0).abs() < 1e-9);
+    }
+
+    fn collision_world() -> World {
+        let mut world = World::new();
+        System::setup(&mut CollisionDetection::new(&PhysicsConfig::default()), &mut world);
+        world
+    }
+
+    #[test]
+    fn test_layer_masks() {
+        let mut world = collision_world();
+
+        let mut ghost = Collider::new(ColliderType::Sphere(1.0));
+        ghost.layer = 0b10;
+        ghost.mask = 0b10;
+
+        let a = world.create_entity()
+            .with(Position(nalgebra::Point3::new(0.0, 0.0, 0.0)))
+            .with(Collider::new(ColliderType::Sphere(1.0)))
+            .build();
+        let b = world.create_entity()
+            .with(Position(nalgebra::Point3::new(1.0, 0.0, 0.0)))
+            .with(ghost)
+            .build();
+
+        let mut detection = CollisionDetection::new(&PhysicsConfig::default());
+        detection.run_now(&world);
+        assert!(world.read_storage::<Collider>().get(a).unwrap().collisions.is_empty());
+        assert!(world.read_storage::<Collider>().get(b).unwrap().collisions.is_empty());
+
+        // only interact when each is in the other's mask
+        world.write_storage::<Collider>().get_mut(b).unwrap().mask = 0b11;
+        detection.run_now(&world);
+        assert_eq!(world.read_storage::<Collider>().get(a).unwrap().collisions.len(), 1);
+        assert_eq!(world.read_storage::<Collider>().get(b).unwrap().collisions.len(), 1);
+    }
+
+    #[test]
+    fn test_attached_colliders() {
+        let mut world = collision_world();
+
+        let parent = world.create_entity()
+            .with(Position(nalgebra::Point3::new(0.0, 0.0, 0.0)))
+            .with(Collider::new(ColliderType::Sphere(1.0)))
+            .build();
+        let child = world.create_entity()
+            .with(Position(nalgebra::Point3::new(1.0, 0.0, 0.0)))
+            .with(Collider::new(ColliderType::Sphere(1.0)))
+            .with(Parent(parent))
+            .build();
+        let other = world.create_entity()
+            .with(Position(nalgebra::Point3::new(2.0, 0.0, 0.0)))
+            .with(Collider::new(ColliderType::Sphere(0.5)))
+            .build();
+
+        let mut detection = CollisionDetection::new(&PhysicsConfig::default());
+        detection.run_now(&world);
+
+        let colliders = world.read_storage::<Collider>();
+        assert!(colliders.get(parent).unwrap().collisions.is_empty());
+        let collisions = &colliders.get(child).unwrap().collisions;
+        assert_eq!(collisions.len(), 1);
+        assert_eq!(collisions[0].with, other);
+    }
+
+    #[test]
+    fn test_trigger_events() {
+        let mut world = collision_world();
+
+        let mut zone = Collider::new(ColliderType::Aabb(nalgebra::Vector3::new(2.0, 2.0, 2.0)));
+        zone.trigger = true;
+        let trigger = world.create_entity()
+            .with(Position(nalgebra::Point3::new(0.0, 0.0, 0.0)))
+            .with(zone)
+            .build();
+        let other = world.create_entity()
+            .with(Position(nalgebra::Point3::new(-5.0, 0.0, 0.0)))
+            .with(Collider::new(ColliderType::Sphere(1.0)))
+            .build();
+
+        let contact = TriggerContact { trigger, other };
+        let mut detection = CollisionDetection::new(&PhysicsConfig::default());
+        let mut step = |x: f64| {
+            world.write_storage::<Position>().get_mut(other).unwrap().0.x = x;
+            detection.run_now(&world);
+
+            // triggers are never resolved
+            for collider in world.read_storage::<Collider>().join() {
+                assert!(collider.collisions.is_empty());
+            }
+            let events = world.read_resource::<TriggerQueue>();
+            events.to_vec()
+        };
+
+        assert_eq!(step(-5.0), vec![]);
+        assert_eq!(step(-2.5), vec![TriggerEvent::Enter(contact)]);
+        assert_eq!(step(0.0), vec![TriggerEvent::Stay(contact)]);
+        assert_eq!(step(2.5), vec![TriggerEvent::Stay(contact)]);
+        assert_eq!(step(5.0), vec![TriggerEvent::Exit(contact)]);
+        assert_eq!(step(5.0), vec![]);
+    }
+}
@@ -5,9 +5,15 @@ use crate::simulation::{
     component::{
         collider::Collider,
         Position,
+        RigidBody,
         Velocity,
         Movement,
     },
+    event::{
+        ConfigEvent,
+        Event,
+    },
+    resource::EventQueue,
 };
 
 pub struct CollisionResolver {
@@ -29,31 +35,43 @@ impl CollisionResolver {
 impl<'a> System<'a> for CollisionResolver {
     type SystemData = (
         Entities<'a>,
+        Read<'a, EventQueue>,
         ReadStorage<'a, Collider>,
+        ReadStorage<'a, RigidBody>,
         WriteStorage<'a, Position>,
         WriteStorage<'a, Velocity>,
         WriteStorage<'a, Movement>,
     );
 
     fn run(&mut self, data: Self::SystemData) {
-        let (entity, colliders, mut positions, mut velocities, mut movement) = data;
+        let (entity, events, colliders, bodies, mut positions, mut velocities, mut movement) = data;
 
-        for (ent, collider, pos, vel)
-            in (&entity, &colliders, &mut positions, &mut velocities).join()
+        for event in &*events {
+            if let Event::ConfigEvent(ConfigEvent::Physics(config)) = event {
+                *self = CollisionResolver::new(config);
+            }
+        }
+
+        // rigid bodies are left to the RigidBodyResolver
+        for (ent, collider, pos, vel, _)
+            in (&entity, &colliders, &mut positions, &mut velocities, !&bodies).join()
         {
-            let mut on_ground = false;
+            let mut ground = None;
 
             for collision in &collider.collisions {
                 pos.0 -= collision.depth;
                 vel.0 -= vel.0.dot(collision.normal.as_ref()) * collision.normal.as_ref();
 
                 if collision.normal.as_ref().y >= self.min_ground_y {
-                    on_ground = true;
+                    ground = Some(collision.with);
                 }
             }
             
             if let Some(mov) = movement.get_mut(ent) {
-                mov.on_ground = on_ground;
+                mov.on_ground = ground.is_some();
+                if ground.is_some() {
+                    mov.ground = ground;
+                }
             }
         }
     }
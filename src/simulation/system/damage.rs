@@ -0,0 +1,228 @@
+use std::sync::mpsc::Sender;
+
+use futures::sync::mpsc::UnboundedSender;
+use specs::prelude::*;
+
+use crate::simulation::{
+    component::{
+        Dead,
+        Health,
+        Movement,
+        Position,
+        RigidBody,
+        SpawnPoint,
+        Velocity,
+    },
+    event::{
+        DamageUpdate,
+        DeathUpdate,
+        RespawnUpdate,
+        Update,
+    },
+    resource::{
+        ActiveCamera,
+        ActiveCharacter,
+        DamageQueue,
+        InputMap,
+    },
+};
+use super::outofbounds::respawn;
+
+/**
+ * Applies the tick's damage to health, marking entities whose health runs
+ * out as dead. Pressing respawn while the active character is dead brings
+ * it back at its spawn point with full health. The protocol has no respawn
+ * operation, so the server is only told about the new position.
+ */
+pub struct Damage {
+    sender: Sender<Update>,
+    net_sender: Option<UnboundedSender<Update>>,
+    respawn_held: bool,
+}
+
+impl Damage {
+    pub fn new(sender: Sender<Update>, net_sender: UnboundedSender<Update>) -> Damage {
+        Damage {
+            sender,
+            net_sender: Some(net_sender),
+            respawn_held: false,
+        }
+    }
+
+    fn send_event(&self, event: Update) {
+        self.sender.send(event).unwrap_or_else(|err| {
+            log::error!("failed to send update event: {}", err);
+        });
+    }
+}
+
+impl<'a> System<'a> for Damage {
+    type SystemData = (
+        Entities<'a>,
+        Read<'a, ActiveCamera>,
+        Read<'a, ActiveCharacter>,
+        Read<'a, InputMap>,
+        Write<'a, DamageQueue>,
+        ReadStorage<'a, SpawnPoint>,
+        WriteStorage<'a, Health>,
+        WriteStorage<'a, Dead>,
+        WriteStorage<'a, Position>,
+        WriteStorage<'a, Velocity>,
+        WriteStorage<'a, Movement>,
+        WriteStorage<'a, RigidBody>,
+    );
+
+    fn run(&mut self, data: Self::SystemData) {
+        let (
+            entities,
+            camera,
+            character,
+            input,
+            mut damage,
+            spawns,
+            mut health,
+            mut dead,
+            mut positions,
+            mut velocities,
+            mut movement,
+            mut bodies,
+        ) = data;
+
+        for event in damage.drain(..) {
+            if !entities.is_alive(event.target) || dead.contains(event.target) {
+                continue;
+            }
+            let hp = match health.get_mut(event.target) {
+                Some(hp) => hp,
+                None => continue,
+            };
+
+            hp.current = hp.current.saturating_sub(event.amount);
+            self.send_event(Update::DamageUpdate(DamageUpdate {
+                entity: event.target,
+                amount: event.amount,
+                source: event.source,
+                health: hp.current,
+                max_health: hp.max,
+                camera: camera.0 == Some(event.target),
+            }));
+
+            if hp.current == 0 {
+                log::debug!("Entity {:?} died to {:?}", event.target, event.source);
+                dead.insert(event.target, Dead).unwrap();
+                self.send_event(Update::DeathUpdate(DeathUpdate {
+                    entity: event.target,
+                    camera: camera.0 == Some(event.target),
+                }));
+            }
+        }
+
+        let respawn_pressed = input.respawn && !self.respawn_held;
+        self.respawn_held = input.respawn;
+
+        let ent = match character.0 {
+            Some(ent) if respawn_pressed && dead.contains(ent) => ent,
+            _ => return,
+        };
+
+        dead.remove(ent);
+        if let Some(hp) = health.get_mut(ent) {
+            hp.current = hp.max;
+        }
+
+        if let Some(spawn) = spawns.get(ent) {
+            respawn(ent, spawn, &mut positions, &mut velocities, &mut movement, &mut bodies);
+
+            let update = Update::RespawnUpdate(RespawnUpdate {
+                entity: ent,
+                position: spawn.0,
+                camera: camera.0 == Some(ent),
+            });
+
+            if let Some(net_sender) = &self.net_sender {
+                net_sender.unbounded_send(update.clone()).unwrap_or_else(|err| {
+                    log::error!("failed to send update event: {}", err);
+                    self.net_sender = None;
+                });
+            }
+
+            self.send_event(update);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::simulation::event::{
+        DamageEvent,
+        DamageSource,
+    };
+
+    #[test]
+    fn test_damage_and_respawn() {
+        let (tx, rx) = std::sync::mpsc::channel();
+        let (net_tx, net_rx) = futures::sync::mpsc::unbounded();
+        let mut system = Damage::new(tx, net_tx);
+
+        let mut world = World::new();
+        System::setup(&mut system, &mut world);
+        let spawn = nalgebra::Point3::new(0.0, -1.0, 0.0);
+        let player = world.create_entity()
+            .with(Health::new(100))
+            .with(Position(nalgebra::Point3::new(5.0, -1.0, 5.0)))
+            .with(SpawnPoint(spawn))
+            .build();
+        let enemy = world.create_entity().build();
+        world.insert(ActiveCamera(Some(player)));
+        world.insert(ActiveCharacter(Some(player)));
+
+        let mut hit = |world: &mut World, amount: u64| {
+            world.write_resource::<DamageQueue>().push(DamageEvent {
+                target: player,
+                amount,
+                source: DamageSource::Entity(enemy),
+            });
+            system.run_now(world);
+        };
+
+        hit(&mut world, 40);
+        assert_eq!(world.read_storage::<Health>().get(player).unwrap().current, 60);
+        assert!(!world.read_storage::<Dead>().contains(player));
+
+        // overkill stops at zero, and the dead take no more damage
+        hit(&mut world, 80);
+        hit(&mut world, 10);
+        assert_eq!(world.read_storage::<Health>().get(player).unwrap().current, 0);
+        assert!(world.read_storage::<Dead>().contains(player));
+
+        let updates = rx.try_iter().collect::<Vec<_>>();
+        assert_eq!(updates.len(), 3);
+        match (&updates[1], &updates[2]) {
+            (Update::DamageUpdate(damage), Update::DeathUpdate(death)) => {
+                assert_eq!(damage.amount, 80);
+                assert_eq!(damage.health, 0);
+                assert_eq!(damage.source, DamageSource::Entity(enemy));
+                assert!(death.camera);
+            },
+            _ => panic!("expected damage and death updates"),
+        }
+
+        world.write_resource::<InputMap>().respawn = true;
+        system.run_now(&world);
+        system.run_now(&world);
+
+        assert!(!world.read_storage::<Dead>().contains(player));
+        assert_eq!(world.read_storage::<Health>().get(player).unwrap().current, 100);
+        assert_eq!(world.read_storage::<Position>().get(player).unwrap().0, spawn);
+        assert_eq!(rx.try_iter().count(), 1);
+
+        drop(system);
+        let net_updates = futures::Future::wait(futures::Stream::collect(net_rx)).unwrap();
+        assert_eq!(net_updates.len(), 1);
+        match &net_updates[0] {
+            Update::RespawnUpdate(update) => assert_eq!(update.position, spawn),
+            _ => panic!("expected the spawn position to be sent to the server"),
+        }
+    }
+}
@@ -1,15 +1,37 @@
 mod collisiondetection;
 mod collisionresolver;
+mod damage;
+mod motionsweep;
+mod movingplatforms;
+mod navagents;
+mod outofbounds;
 mod physics;
 mod playermovement;
+mod projectiles;
+mod rigidbodyresolver;
+mod targeting;
+mod transformpropagation;
 mod updateinputs;
 mod updatesender;
+mod useabilities;
 mod updateworld;
+mod watervolumes;
 
 pub use collisiondetection::CollisionDetection;
 pub use collisionresolver::CollisionResolver;
+pub use damage::Damage;
+pub use motionsweep::MotionSweep;
+pub use movingplatforms::MovingPlatforms;
+pub use navagents::NavAgents;
+pub use outofbounds::OutOfBounds;
 pub use physics::Physics;
 pub use playermovement::PlayerMovement;
+pub use projectiles::Projectiles;
+pub use rigidbodyresolver::RigidBodyResolver;
+pub use targeting::Targeting;
+pub use transformpropagation::TransformPropagation;
 pub use updateinputs::UpdateInputs;
 pub use updatesender::UpdateSender;
-pub use updateworld::UpdateWorld;
\ No newline at end of file
+pub use useabilities::UseAbilities;
+pub use updateworld::UpdateWorld;
+pub use watervolumes::WaterVolumes;
\ No newline at end of file
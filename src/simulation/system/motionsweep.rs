@@ -0,0 +1,208 @@
+use specs::prelude::*;
+
+use crate::simulation::{
+    component::{
+        Collider,
+        ContinuousCollision,
+        Position,
+        Velocity,
+    },
+    resource::CollisionWorld,
+};
+
+/**
+ * Continuous collision detection for entities flagged with
+ * `ContinuousCollision`. Their motion this tick is swept as a sphere against
+ * the world, and stopped at the first surface hit so that fast movers can not
+ * tunnel through thin colliders. Overlaps at the start of the motion are left
+ * for `CollisionDetection` to resolve, and triggers are never stopped.
+ */
+pub struct MotionSweep;
+
+impl<'a> System<'a> for MotionSweep {
+    type SystemData = (
+        Entities<'a>,
+        Read<'a, CollisionWorld>,
+        ReadStorage<'a, Collider>,
+        WriteStorage<'a, ContinuousCollision>,
+        WriteStorage<'a, Position>,
+        WriteStorage<'a, Velocity>,
+    );
+
+    fn run(&mut self, data: Self::SystemData) {
+        let (entities, world, colliders, mut continuous, mut positions, mut velocities) = data;
+
+        let mut impacts = Vec::new();
+        for (ent, continuous, pos, collider)
+            in (&entities, &mut continuous, &positions, &colliders).join()
+        {
+            let start = match continuous.start.take() {
+                Some(start) => start,
+                None => continue,
+            };
+            let radius = match collider.collider.inner_radius() {
+                Some(radius) if !collider.trigger => radius,
+                _ => continue,
+            };
+
+            let motion = pos.0 - start;
+            let distance = motion.norm();
+            if distance < f64::EPSILON {
+                continue;
+            }
+
+            let hit = world.cast_with(
+                &positions, &colliders,
+                &start, &motion,
+                radius, distance,
+                |other, other_collider, (hit_distance, _)| {
+                    other != ent
+                        && !other_collider.trigger
+                        && collider.interacts_with(other_collider)
+                        && *hit_distance > 0.0
+                },
+            );
+
+            if let Some(hit) = hit {
+                impacts.push((ent, start + motion * (hit.distance / distance), hit.normal));
+            }
+        }
+
+        for (ent, position, normal) in impacts {
+            positions.get_mut(ent).unwrap().0 = position;
+
+            // stop moving into the surface, as the resolver would on contact
+            if let Some(vel) = velocities.get_mut(ent) {
+                let approach = vel.0.dot(&normal);
+                if approach < 0.0 {
+                    vel.0 -= normal.as_ref() * approach;
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::simulation::{
+        collision::{
+            Triangle,
+            TriMesh,
+        },
+        component::collider::ColliderType,
+        resource::TickLength,
+        system::{
+            CollisionDetection,
+            CollisionResolver,
+            Physics,
+        },
+        PhysicsConfig,
+    };
+
+    const TICK_RATE: f64 = 60.0;
+
+    fn build_world(floor: ColliderType, speed: f64, continuous: bool) -> (World, Entity) {
+        let config = PhysicsConfig::default();
+        let mut world = World::new();
+        System::setup(&mut Physics::new(&config), &mut world);
+        System::setup(&mut MotionSweep, &mut world);
+        System::setup(&mut CollisionDetection::new(&config), &mut world);
+        System::setup(&mut CollisionResolver::new(&config), &mut world);
+        world.insert(TickLength(std::time::Duration::from_secs_f64(1.0 / TICK_RATE)));
+
+        world.create_entity()
+            .with(Position(nalgebra::Point3::new(0.0, 0.0, 0.0)))
+            .with(Collider::new(floor))
+            .build();
+
+        let mut builder = world.create_entity()
+            .with(Position(nalgebra::Point3::new(0.0, -20.0, 0.0)))
+            .with(Velocity(nalgebra::Vector3::new(0.0, speed, 0.0)))
+            .with(Collider::new(ColliderType::Sphere(0.5)));
+        if continuous {
+            builder = builder.with(ContinuousCollision::default());
+        }
+        let sphere = builder.build();
+
+        (world, sphere)
+    }
+
+    /**
+     * Runs the collision pipeline for a number of ticks, returning the
+     * sphere's height after each of them
+     */
+    fn simulate(world: &mut World, sphere: Entity, ticks: usize) -> Vec<f64> {
+        let config = PhysicsConfig::default();
+        let mut physics = Physics::new(&config);
+        let mut sweep = MotionSweep;
+        let mut detection = CollisionDetection::new(&config);
+        let mut resolver = CollisionResolver::new(&config);
+
+        // the broadphase is filled by collision detection
+        detection.run_now(world);
+
+        (0..ticks)
+            .map(|_| {
+                physics.run_now(world);
+                sweep.run_now(world);
+                detection.run_now(world);
+                resolver.run_now(world);
+                world.maintain();
+
+                world.read_storage::<Position>().get(sphere).unwrap().0.y
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_sphere_stops_at_plane() {
+        let plane = || ColliderType::Plane(nalgebra::Unit::new_normalize(
+            nalgebra::Vector3::new(0.0, -1.0, 0.0)
+        ));
+
+        // far more than the sphere's diameter per tick
+        for speed in &[120.0, 600.0, 3000.0] {
+            let (mut world, sphere) = build_world(plane(), *speed, true);
+            let heights = simulate(&mut world, sphere, 30);
+
+            // stopped right at the time of impact, never inside the plane
+            let impact = heights.iter().position(|y| *y > -1.0).unwrap();
+            assert!((heights[impact] + 0.5).abs() < 1e-6, "speed {}: {:?}", speed, heights);
+            for y in &heights {
+                assert!(*y <= -0.5 + 1e-6, "speed {}: {:?}", speed, heights);
+            }
+
+            let velocity = world.read_storage::<Velocity>().get(sphere).unwrap().0;
+            assert!(velocity.y.abs() < 1.0);
+        }
+    }
+
+    #[test]
+    fn test_no_tunneling_through_thin_mesh() {
+        let floor = || ColliderType::TriMesh(TriMesh::new(vec![
+            Triangle::new(
+                nalgebra::Point3::new(-10.0, 0.0, -10.0),
+                nalgebra::Point3::new(10.0, 0.0, -10.0),
+                nalgebra::Point3::new(-10.0, 0.0, 10.0),
+            ),
+            Triangle::new(
+                nalgebra::Point3::new(10.0, 0.0, -10.0),
+                nalgebra::Point3::new(10.0, 0.0, 10.0),
+                nalgebra::Point3::new(-10.0, 0.0, 10.0),
+            ),
+        ]));
+
+        // without sweeping, the sphere skips over the floor in a single tick
+        let (mut world, sphere) = build_world(floor(), 660.0, false);
+        let heights = simulate(&mut world, sphere, 5);
+        assert!(*heights.last().unwrap() > 0.5);
+
+        let (mut world, sphere) = build_world(floor(), 660.0, true);
+        let heights = simulate(&mut world, sphere, 5);
+        assert!((heights[1] + 0.5).abs() < 1e-6, "{:?}", heights);
+        for y in &heights {
+            assert!(*y <= -0.5 + 1e-6, "{:?}", heights);
+        }
+    }
+}
@@ -0,0 +1,213 @@
+use std::collections::BTreeMap;
+
+use specs::prelude::*;
+
+use crate::simulation::{
+    component::{
+        Movement,
+        MovingPlatform,
+        Position,
+    },
+    resource::{
+        TickLength,
+        TickNumber,
+    },
+};
+
+/**
+ * Moves platforms along their paths, carrying the characters standing on
+ * them along. Runs after the character controller has settled what each
+ * character stands on.
+ */
+pub struct MovingPlatforms;
+
+impl<'a> System<'a> for MovingPlatforms {
+    type SystemData = (
+        Entities<'a>,
+        Read<'a, TickLength>,
+        Read<'a, TickNumber>,
+        ReadStorage<'a, Movement>,
+        ReadStorage<'a, MovingPlatform>,
+        WriteStorage<'a, Position>,
+    );
+
+    fn run(&mut self, data: Self::SystemData) {
+        let (entities, tick_length, tick, movement, platforms, mut positions) = data;
+
+        let time = tick.0 as f64 * tick_length.seconds();
+
+        let mut moved = BTreeMap::new();
+        for (ent, platform, pos) in (&entities, &platforms, &mut positions).join() {
+            let destination = platform.position_at(time);
+            moved.insert(ent, destination - pos.0);
+            pos.0 = destination;
+        }
+
+        for (mov, pos) in (&movement, &mut positions).join() {
+            if !mov.on_ground {
+                continue;
+            }
+
+            if let Some(offset) = mov.ground.and_then(|ground| moved.get(&ground)) {
+                pos.0 += offset;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::simulation::{
+        component::{
+            collider::ColliderType,
+            Collider,
+            PathMode,
+            Velocity,
+        },
+        system::{
+            CollisionDetection,
+            CollisionResolver,
+            Physics,
+            PlayerMovement,
+        },
+        PhysicsConfig,
+    };
+
+    fn build_world() -> World {
+        let config = PhysicsConfig::default();
+        let mut world = World::new();
+        System::setup(&mut PlayerMovement::new(&config), &mut world);
+        System::setup(&mut MovingPlatforms, &mut world);
+        System::setup(&mut Physics::new(&config), &mut world);
+        System::setup(&mut CollisionDetection::new(&config), &mut world);
+        System::setup(&mut CollisionResolver::new(&config), &mut world);
+        world.insert(TickLength(std::time::Duration::from_secs_f64(1.0 / 60.0)));
+        world
+    }
+
+    fn add_platform(world: &mut World, waypoints: Vec<nalgebra::Point3<f64>>, mode: PathMode) -> Entity {
+        world.create_entity()
+            .with(Position(waypoints[0]))
+            .with(Collider::new(ColliderType::Aabb(nalgebra::Vector3::new(2.0, 0.25, 2.0))))
+            .with(MovingPlatform::new(waypoints, 2.0, mode))
+            .build()
+    }
+
+    fn add_rider(world: &mut World, position: [f64; 3]) -> Entity {
+        world.create_entity()
+            .with(Position(position.into()))
+            .with(Velocity(nalgebra::Vector3::zeros()))
+            .with(Collider::new(ColliderType::Sphere(0.5)))
+            .with(Movement::new(6.0))
+            .build()
+    }
+
+    /**
+     * Runs the movement and collision pipeline, returning the rider's
+     * position relative to the platform after each tick
+     */
+    fn simulate(world: &mut World, platform: Entity, rider: Entity, ticks: usize)
+        -> Vec<nalgebra::Vector3<f64>>
+    {
+        let config = PhysicsConfig::default();
+        let mut movement = PlayerMovement::new(&config);
+        let mut physics = Physics::new(&config);
+        let mut detection = CollisionDetection::new(&config);
+        let mut resolver = CollisionResolver::new(&config);
+
+        (0..ticks)
+            .map(|_| {
+                world.write_resource::<TickNumber>().0 += 1;
+                movement.run_now(world);
+                MovingPlatforms.run_now(world);
+                physics.run_now(world);
+                detection.run_now(world);
+                resolver.run_now(world);
+                world.maintain();
+
+                let positions = world.read_storage::<Position>();
+                positions.get(rider).unwrap().0 - positions.get(platform).unwrap().0
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_carries_riders() {
+        let mut world = build_world();
+        let platform = add_platform(&mut world, vec![
+            nalgebra::Point3::new(0.0, 0.0, 0.0),
+            nalgebra::Point3::new(4.0, 0.0, 0.0),
+            nalgebra::Point3::new(4.0, -3.0, 0.0),
+        ], PathMode::PingPong);
+        let rider = add_rider(&mut world, [0.5, -0.75, 0.0]);
+
+        // there and back again, both sideways and up and down, after the
+        // first tick finds what the rider stands on
+        let offsets = simulate(&mut world, platform, rider, 420);
+        for offset in &offsets {
+            assert!((offset.x - offsets[0].x).abs() < 0.01, "{}", offset);
+            assert!((offset.y + 0.75).abs() < 0.05, "{}", offset);
+        }
+        let position = world.read_storage::<Position>().get(platform).unwrap().0;
+        assert!(position.x.abs() < 1e-6, "{}", position);
+    }
+
+    #[test]
+    fn test_leaves_others_behind() {
+        let mut world = build_world();
+        let platform = add_platform(&mut world, vec![
+            nalgebra::Point3::new(0.0, 0.0, 0.0),
+            nalgebra::Point3::new(8.0, 0.0, 0.0),
+        ], PathMode::Loop);
+
+        // standing on something else
+        let floor = world.create_entity()
+            .with(Position(nalgebra::Point3::new(0.0, 5.0, 0.0)))
+            .build();
+        let mut walker = Movement::new(6.0);
+        walker.ground = Some(floor);
+        let walker = world.create_entity()
+            .with(Position(nalgebra::Point3::new(0.0, 4.0, 0.0)))
+            .with(walker)
+            .build();
+
+        // jumped off the platform
+        let mut jumper = Movement::new(6.0);
+        jumper.ground = Some(platform);
+        jumper.on_ground = false;
+        let jumper = world.create_entity()
+            .with(Position(nalgebra::Point3::new(0.0, -2.0, 0.0)))
+            .with(jumper)
+            .build();
+
+        world.insert(TickNumber(1));
+        MovingPlatforms.run_now(&world);
+
+        let positions = world.read_storage::<Position>();
+        assert!(positions.get(platform).unwrap().0.x > 0.0);
+        assert_eq!(positions.get(walker).unwrap().0.x, 0.0);
+        assert_eq!(positions.get(jumper).unwrap().0.x, 0.0);
+    }
+
+    #[test]
+    fn test_follows_tick_number() {
+        let mut world = build_world();
+        let waypoints = vec![
+            nalgebra::Point3::new(0.0, 0.0, 0.0),
+            nalgebra::Point3::new(8.0, 0.0, 0.0),
+        ];
+        let platform = add_platform(&mut world, waypoints.clone(), PathMode::Loop);
+        let expected = MovingPlatform::new(waypoints, 2.0, PathMode::Loop);
+
+        // ticks skipped by a simulation that fell behind are caught up on
+        for tick in &[1, 2, 90, 91, 60 * 60 * 24 * 365 * 50] {
+            world.insert(TickNumber(*tick));
+            MovingPlatforms.run_now(&world);
+
+            let position = world.read_storage::<Position>().get(platform).unwrap().0;
+            let time = *tick as f64 * world.read_resource::<TickLength>().seconds();
+            assert!((position - expected.position_at(time)).norm() < 1e-6, "{}", position);
+        }
+    }
+}
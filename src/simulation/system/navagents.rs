@@ -0,0 +1,186 @@
+use specs::prelude::*;
+
+use crate::simulation::{
+    component::{
+        Dead,
+        NavAgent,
+        Position,
+        Rotation,
+    },
+    navigation::{
+        find_path,
+        NavGrid,
+    },
+    resource::TickLength,
+};
+
+/**
+ * Finds paths for agents given somewhere to go and walks them along,
+ * turning them to face where they are heading. Agents only move across the
+ * ground, falling and collisions are left to the physics.
+ */
+pub struct NavAgents;
+
+impl<'a> System<'a> for NavAgents {
+    type SystemData = (
+        Entities<'a>,
+        Read<'a, TickLength>,
+        Read<'a, NavGrid>,
+        ReadStorage<'a, Dead>,
+        WriteStorage<'a, NavAgent>,
+        WriteStorage<'a, Position>,
+        WriteStorage<'a, Rotation>,
+    );
+
+    fn run(&mut self, data: Self::SystemData) {
+        let (entities, tick_length, grid, dead, mut agents, mut positions, mut rotations) = data;
+
+        let dt = tick_length.seconds();
+
+        for (ent, agent, pos, _) in (&entities, &mut agents, &mut positions, !&dead).join() {
+            if agent.destination.is_none() && !agent.patrol.is_empty() {
+                let next = agent.next_patrol % agent.patrol.len();
+                agent.next_patrol = next + 1;
+                agent.move_to(agent.patrol[next]);
+            }
+
+            let destination = match agent.destination {
+                Some(destination) => destination,
+                None => continue,
+            };
+            if agent.path.is_empty() {
+                match find_path(&grid, &pos.0, &destination) {
+                    Some(ref path) if path.is_empty() => {
+                        agent.stop();
+                        continue;
+                    },
+                    Some(path) => agent.path = path,
+                    None => {
+                        log::debug!("No path for {:?} to {}", ent, destination);
+                        agent.stop();
+                        continue;
+                    },
+                }
+            }
+
+            let mut distance = agent.speed * dt;
+            let mut heading = None;
+            while distance > 0.0 {
+                let waypoint = match agent.path.first() {
+                    Some(waypoint) => waypoint,
+                    None => break,
+                };
+
+                let offset = nalgebra::Vector3::new(waypoint.x - pos.0.x, 0.0, waypoint.z - pos.0.z);
+                let length = offset.norm();
+                if length > 0.0 {
+                    heading = Some(offset);
+                }
+
+                if length <= distance {
+                    pos.0.x = waypoint.x;
+                    pos.0.z = waypoint.z;
+                    distance -= length;
+                    agent.path.remove(0);
+                } else {
+                    pos.0 += offset * (distance / length);
+                    distance = 0.0;
+                }
+            }
+
+            if agent.path.is_empty() {
+                agent.destination = None;
+            }
+
+            if let (Some(heading), Some(rotation)) = (heading, rotations.get_mut(ent)) {
+                // facing -z at yaw 0
+                *rotation = Rotation::from_yaw((-heading.x).atan2(-heading.z));
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::display::terrain::HeightMap;
+
+    #[test]
+    fn test_walk_and_patrol() {
+        let mut world = World::new();
+        System::setup(&mut NavAgents, &mut world);
+        world.insert(TickLength(std::time::Duration::from_secs_f64(1.0 / 60.0)));
+
+        let map = HeightMap::new(vec![0.0; 11 * 11], 11, 1.0);
+        world.insert(NavGrid::new(&nalgebra::Point3::origin(), &map, 0.8, 0.5, vec![]));
+
+        let mut agent = NavAgent::new(3.0);
+        agent.move_to(nalgebra::Point3::new(1.5, 0.0, 7.5));
+        agent.patrol = vec![
+            nalgebra::Point3::new(7.5, 0.0, 7.5),
+            nalgebra::Point3::new(7.5, 0.0, 1.5),
+        ];
+        let ent = world.create_entity()
+            .with(Position(nalgebra::Point3::new(1.5, -1.0, 1.5)))
+            .with(Rotation::default())
+            .with(agent)
+            .build();
+
+        // 6m along z takes two seconds, keeping the height
+        for _ in 0..60 {
+            NavAgents.run_now(&world);
+        }
+        {
+            let positions = world.read_storage::<Position>();
+            let pos = positions.get(ent).unwrap().0;
+            assert!((pos - nalgebra::Point3::new(1.5, -1.0, 4.5)).norm() < 1e-6, "{}", pos);
+
+            let rotations = world.read_storage::<Rotation>();
+            let forward = rotations.get(ent).unwrap().0 * -nalgebra::Vector3::z();
+            assert!((forward - nalgebra::Vector3::z()).norm() < 1e-9);
+        }
+
+        // then on to the patrol
+        for _ in 0..60 + 120 {
+            NavAgents.run_now(&world);
+        }
+        {
+            let positions = world.read_storage::<Position>();
+            let pos = positions.get(ent).unwrap().0;
+            assert!((pos - nalgebra::Point3::new(7.5, -1.0, 7.5)).norm() < 1e-6, "{}", pos);
+        }
+
+        for _ in 0..2 {
+            NavAgents.run_now(&world);
+        }
+        let agents = world.read_storage::<NavAgent>();
+        let agent = agents.get(ent).unwrap();
+        assert_eq!(agent.destination, Some(nalgebra::Point3::new(7.5, 0.0, 1.5)));
+        assert_eq!(agent.next_patrol, 2);
+    }
+
+    #[test]
+    fn test_already_there() {
+        let mut world = World::new();
+        System::setup(&mut NavAgents, &mut world);
+        world.insert(TickLength(std::time::Duration::from_secs_f64(1.0 / 60.0)));
+
+        let map = HeightMap::new(vec![0.0; 11 * 11], 11, 1.0);
+        world.insert(NavGrid::new(&nalgebra::Point3::origin(), &map, 0.8, 0.5, vec![]));
+
+        let mut agent = NavAgent::new(3.0);
+        agent.move_to(nalgebra::Point3::new(1.5, 0.0, 1.5));
+        let ent = world.create_entity()
+            .with(Position(nalgebra::Point3::new(1.5, 0.0, 1.5)))
+            .with(agent)
+            .build();
+
+        NavAgents.run_now(&world);
+
+        let agents = world.read_storage::<NavAgent>();
+        let agent = agents.get(ent).unwrap();
+        assert!(agent.destination.is_none());
+        assert!(agent.path.is_empty());
+        assert_eq!(world.read_storage::<Position>().get(ent).unwrap().0, nalgebra::Point3::new(1.5, 0.0, 1.5));
+    }
+}
@@ -0,0 +1,187 @@
+use std::sync::mpsc::Sender;
+
+use futures::sync::mpsc::UnboundedSender;
+use specs::prelude::*;
+
+use crate::simulation::{
+    component::{
+        Movement,
+        Position,
+        RigidBody,
+        SpawnPoint,
+        Velocity,
+    },
+    event::{
+        RespawnUpdate,
+        Update,
+    },
+    resource::{
+        ActiveCamera,
+        ActiveCharacter,
+        WorldBounds,
+    },
+};
+
+/**
+ * Moves entities that left the world bounds, such as by falling off the edge
+ * of the terrain, back to their spawn points. The server is told about the
+ * active character's new position straight away.
+ */
+pub struct OutOfBounds {
+    sender: Sender<Update>,
+    net_sender: Option<UnboundedSender<Update>>,
+}
+
+impl OutOfBounds {
+    pub fn new(sender: Sender<Update>, net_sender: UnboundedSender<Update>) -> OutOfBounds {
+        OutOfBounds { sender, net_sender: Some(net_sender) }
+    }
+}
+
+impl<'a> System<'a> for OutOfBounds {
+    type SystemData = (
+        Entities<'a>,
+        Read<'a, WorldBounds>,
+        Read<'a, ActiveCamera>,
+        Read<'a, ActiveCharacter>,
+        ReadStorage<'a, SpawnPoint>,
+        WriteStorage<'a, Position>,
+        WriteStorage<'a, Velocity>,
+        WriteStorage<'a, Movement>,
+        WriteStorage<'a, RigidBody>,
+    );
+
+    fn run(&mut self, data: Self::SystemData) {
+        let (
+            entities,
+            bounds,
+            camera,
+            character,
+            spawns,
+            mut positions,
+            mut velocities,
+            mut movement,
+            mut bodies,
+        ) = data;
+
+        let mut outside = Vec::new();
+        for (ent, spawn, pos) in (&entities, &spawns, &positions).join() {
+            if !bounds.0.contains(&pos.0) {
+                log::debug!("Entity {:?} left the world bounds at {}", ent, pos.0);
+                outside.push((ent, spawn));
+            }
+        }
+
+        for (ent, spawn) in outside {
+            respawn(ent, spawn, &mut positions, &mut velocities, &mut movement, &mut bodies);
+
+            let update = Update::RespawnUpdate(RespawnUpdate {
+                entity: ent,
+                position: spawn.0,
+                camera: camera.0 == Some(ent),
+            });
+
+            if character.0 == Some(ent) {
+                if let Some(net_sender) = &self.net_sender {
+                    net_sender.unbounded_send(update.clone()).unwrap_or_else(|err| {
+                        log::error!("failed to send update event: {}", err);
+                        self.net_sender = None;
+                    });
+                }
+            }
+
+            self.sender.send(update).unwrap_or_else(|err| {
+                log::error!("failed to send update event: {}", err);
+            });
+        }
+    }
+}
+
+/**
+ * Puts an entity back at its spawn point, at rest
+ */
+pub(super) fn respawn(
+    ent: Entity,
+    spawn: &SpawnPoint,
+    positions: &mut WriteStorage<Position>,
+    velocities: &mut WriteStorage<Velocity>,
+    movement: &mut WriteStorage<Movement>,
+    bodies: &mut WriteStorage<RigidBody>,
+)
+{
+    if let Some(pos) = positions.get_mut(ent) {
+        pos.0 = spawn.0;
+    }
+    if let Some(vel) = velocities.get_mut(ent) {
+        vel.0 = nalgebra::Vector3::zeros();
+    }
+    if let Some(mov) = movement.get_mut(ent) {
+        mov.on_ground = false;
+        mov.air_time = 0.0;
+    }
+    if let Some(body) = bodies.get_mut(ent) {
+        body.wake();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::simulation::collision::Aabb;
+
+    #[test]
+    fn test_respawn() {
+        let (tx, rx) = std::sync::mpsc::channel();
+        let (net_tx, net_rx) = futures::sync::mpsc::unbounded();
+        let mut system = OutOfBounds::new(tx, net_tx);
+
+        let mut world = World::new();
+        System::setup(&mut system, &mut world);
+        world.insert(WorldBounds(Aabb::new(
+            nalgebra::Point3::new(-10.0, -10.0, -10.0),
+            nalgebra::Point3::new(10.0, 10.0, 10.0),
+        )));
+
+        let spawn = nalgebra::Point3::new(1.0, -1.0, 1.0);
+        let falling = world.create_entity()
+            .with(Position(nalgebra::Point3::new(0.0, 10.5, 0.0)))
+            .with(Velocity(nalgebra::Vector3::new(0.0, 20.0, 0.0)))
+            .with(Movement::new(6.0))
+            .with(SpawnPoint(spawn))
+            .build();
+        let inside = world.create_entity()
+            .with(Position(nalgebra::Point3::new(0.0, 9.5, 0.0)))
+            .with(SpawnPoint(spawn))
+            .build();
+        let static_outside = world.create_entity()
+            .with(Position(nalgebra::Point3::new(0.0, 20.0, 0.0)))
+            .build();
+
+        world.insert(ActiveCamera(Some(falling)));
+        world.insert(ActiveCharacter(Some(falling)));
+
+        system.run_now(&world);
+
+        {
+            let positions = world.read_storage::<Position>();
+            assert_eq!(positions.get(falling).unwrap().0, spawn);
+            assert_eq!(positions.get(inside).unwrap().0.y, 9.5);
+            assert_eq!(positions.get(static_outside).unwrap().0.y, 20.0);
+            assert_eq!(world.read_storage::<Velocity>().get(falling).unwrap().0, nalgebra::Vector3::zeros());
+        }
+
+        let updates = rx.try_iter().collect::<Vec<_>>();
+        assert_eq!(updates.len(), 1);
+        match &updates[0] {
+            Update::RespawnUpdate(update) => {
+                assert_eq!(update.entity, falling);
+                assert!(update.camera);
+            },
+            _ => panic!("expected a respawn update"),
+        }
+
+        drop(system);
+        let net_updates = futures::Stream::collect(net_rx);
+        assert_eq!(futures::Future::wait(net_updates).unwrap().len(), 1);
+    }
+}
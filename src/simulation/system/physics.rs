@@ -3,24 +3,35 @@ use specs::prelude::*;
 use crate::simulation::PhysicsConfig;
 use crate::simulation::{
     component::{
+        ContinuousCollision,
         Movement,
         Position,
+        RigidBody,
+        Submersion,
         Velocity,
     },
+    event::{
+        ConfigEvent,
+        Event,
+    },
+    resource::{
+        EventQueue,
+        TickLength,
+    },
 };
 
 pub struct Physics {
     gravity: nalgebra::Vector3::<f64>,
-    horisontal_drag_coeff: f64,
-    vertical_drag_coeff: f64,
+    horisontal_drag: f64,
+    vertical_drag: f64,
 }
 
 impl Physics {
     pub fn new(config: &PhysicsConfig) -> Physics {
         Physics {
             gravity: nalgebra::Vector3::new(0.0, config.gravity, 0.0),
-            horisontal_drag_coeff: 1.0 - config.horisontal_drag,
-            vertical_drag_coeff: 1.0 - config.vertical_drag,
+            horisontal_drag: config.horisontal_drag,
+            vertical_drag: config.vertical_drag,
         }
     }
 }
@@ -28,29 +39,152 @@ impl Physics {
 impl<'a> System<'a> for Physics {
     type SystemData = (
         Entities<'a>,
+        Read<'a, EventQueue>,
+        Read<'a, TickLength>,
         WriteStorage<'a, Position>,
         WriteStorage<'a, Velocity>,
         ReadStorage<'a, Movement>,
+        WriteStorage<'a, ContinuousCollision>,
+        ReadStorage<'a, RigidBody>,
+        ReadStorage<'a, Submersion>,
     );
 
     fn run(&mut self, data: Self::SystemData) {
-        let (ent, mut pos, mut vel, mov) = data;
+        let (ent, events, tick_length, mut pos, mut vel, mov, mut continuous, bodies, submersion) = data;
+
+        for event in &*events {
+            if let Event::ConfigEvent(ConfigEvent::Physics(config)) = event {
+                *self = Physics::new(config);
+            }
+        }
+
+        let dt = tick_length.seconds();
 
         for (ent, pos, vel) in (&ent, &mut pos, &mut vel).join() {
-            pos.0 += vel.0;
+            let body = bodies.get(ent);
+            if matches!(body, Some(body) if body.sleeping) {
+                continue;
+            }
 
-            vel.0.x *= self.horisontal_drag_coeff;
-            vel.0.y *= self.vertical_drag_coeff;
-            vel.0.z *= self.horisontal_drag_coeff;
+            let on_ground = match mov.get(ent) {
+                Some(mov) => mov.on_ground,
+                None => false,
+            };
 
-            if let Some(mov) = mov.get(ent) {
-                if mov.on_ground {
-                    // skip applying gravity for on-ground player
-                    continue;
+            // skip applying gravity for on-ground player, buoyancy offsets
+            // it in proportion to how deep in water the entity is
+            let acceleration = if on_ground {
+                nalgebra::Vector3::<f64>::zeros()
+            } else {
+                match submersion.get(ent) {
+                    Some(water) => self.gravity * (1.0 - water.buoyancy * water.depth),
+                    None => self.gravity,
                 }
+            };
+
+            let (horisontal_drag, vertical_drag) = match submersion.get(ent) {
+                Some(water) => (
+                    self.horisontal_drag + (water.drag - self.horisontal_drag) * water.depth,
+                    self.vertical_drag + (water.drag - self.vertical_drag) * water.depth,
+                ),
+                None => (self.horisontal_drag, self.vertical_drag),
+            };
+
+            if let Some(continuous) = continuous.get_mut(ent) {
+                continuous.start = Some(pos.0);
             }
 
-            vel.0 += self.gravity;
+            // rigid bodies slow down through friction on contact instead,
+            // only air drag applies to them
+            let drag = if body.is_some() {
+                nalgebra::Vector3::repeat(vertical_drag)
+            } else {
+                nalgebra::Vector3::new(horisontal_drag, vertical_drag, horisontal_drag)
+            };
+
+            // acceleration and drag are integrated exactly, so trajectories
+            // do not depend on the tick rate
+            for axis in 0..3 {
+                let (offset, velocity) = integrate(vel.0[axis], acceleration[axis], drag[axis], dt);
+                pos.0[axis] += offset;
+                vel.0[axis] = velocity;
+            }
         }
     }
-}
\ No newline at end of file
+}
+
+/**
+ * Distance covered and the velocity reached after 'dt' seconds of constant
+ * 'acceleration' against drag proportional to the velocity
+ */
+fn integrate(velocity: f64, acceleration: f64, drag: f64, dt: f64) -> (f64, f64) {
+    if drag * dt < 1e-9 {
+        return (
+            velocity * dt + acceleration * (0.5 * dt * dt),
+            velocity + acceleration * dt,
+        );
+    }
+
+    // the velocity decays exponentially towards the terminal velocity
+    let terminal = acceleration / drag;
+    let decay = (-drag * dt).exp();
+    (
+        terminal * dt + (velocity - terminal) * (1.0 - decay) / drag,
+        terminal + (velocity - terminal) * decay,
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn move_for_one_second(tick_rate: u32, config: &PhysicsConfig) -> nalgebra::Point3<f64> {
+        let mut physics = Physics::new(config);
+        let mut world = World::new();
+        System::setup(&mut physics, &mut world);
+        world.insert(TickLength(std::time::Duration::from_secs_f64(
+            1.0 / tick_rate as f64
+        )));
+
+        let entity = world.create_entity()
+            .with(Position(nalgebra::Point3::new(0.0, 0.0, 0.0)))
+            .with(Velocity(nalgebra::Vector3::new(4.0, -5.0, 0.0)))
+            .build();
+
+        for _ in 0..tick_rate {
+            physics.run_now(&world);
+        }
+
+        let position = world.read_storage::<Position>().get(entity).unwrap().0;
+        position
+    }
+
+    #[test]
+    fn test_tick_rate_independence() {
+        let config = PhysicsConfig::default();
+        let expected_x = 4.0 * (1.0 - (-config.horisontal_drag).exp()) / config.horisontal_drag;
+        let expected_y = -5.0 + 0.5 * config.gravity;
+
+        for tick_rate in &[30, 60, 128] {
+            let position = move_for_one_second(*tick_rate, &config);
+            assert!((position.x - expected_x).abs() < 0.0001, "{}", position.x);
+            assert!((position.y - expected_y).abs() < 0.0001, "{}", position.y);
+        }
+
+        // falling against drag approaches the terminal velocity
+        let config = PhysicsConfig {
+            horisontal_drag: 0.5,
+            vertical_drag: 1.5,
+            ..PhysicsConfig::default()
+        };
+        let terminal = config.gravity / config.vertical_drag;
+        let expected_x = 4.0 * (1.0 - (-0.5f64).exp()) / 0.5;
+        let expected_y = terminal + (-5.0 - terminal) * (1.0 - (-1.5f64).exp()) / 1.5;
+
+        for tick_rate in &[30, 60, 128] {
+            let position = move_for_one_second(*tick_rate, &config);
+            assert!((position.x - expected_x).abs() < 0.0001, "{}", position.x);
+            assert!((position.y - expected_y).abs() < 0.0001, "{}", position.y);
+        }
+    }
+}
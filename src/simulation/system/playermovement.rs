@@ -1,36 +1,154 @@
 use specs::prelude::*;
 
 use crate::input::MouseEuler;
+use crate::simulation::PhysicsConfig;
 use crate::simulation::{
     component::{
+        collider::Collider,
+        Dead,
         Jump,
         Movement,
         Position,
+        Rotation,
+        Submersion,
         Velocity,
     },
-    resource::InputMap,
+    event::{
+        ConfigEvent,
+        Event,
+    },
+    resource::{
+        ActiveCharacter,
+        CollisionWorld,
+        EventQueue,
+        InputMap,
+        RayHit,
+        TickLength,
+    },
 };
 
-pub struct PlayerMovement;
+/**
+ * Distance in m within which a character is kept on the ground
+ */
+const GROUND_SNAP: f64 = 0.05;
+
+/**
+ * How far past a ledge's edge its top surface is checked, in m
+ */
+const EDGE_MARGIN: f64 = 0.01;
+
+/**
+ * Submersion depth from which characters swim instead of walking
+ */
+const SWIM_DEPTH: f64 = 0.5;
+
+/**
+ * Character controller for entities with `Movement`, driven by the input
+ * state and last tick's collisions
+ */
+pub struct PlayerMovement {
+    min_ground_y: f64,
+    jump_held: bool,
+}
+
+impl PlayerMovement {
+    pub fn new(config: &PhysicsConfig) -> PlayerMovement {
+        PlayerMovement {
+            min_ground_y: 1.0 - config.max_ground_slope,
+            jump_held: false,
+        }
+    }
+}
 
 impl<'a> System<'a> for PlayerMovement {
     type SystemData = (
+        Entities<'a>,
+        Read<'a, EventQueue>,
+        Read<'a, ActiveCharacter>,
         Read<'a, InputMap>,
         Read<'a, MouseEuler>,
-        ReadStorage<'a, Movement>,
-        ReadStorage<'a, Jump>,
+        Read<'a, TickLength>,
+        Read<'a, CollisionWorld>,
+        ReadStorage<'a, Collider>,
+        ReadStorage<'a, Dead>,
+        ReadStorage<'a, Submersion>,
+        WriteStorage<'a, Movement>,
+        WriteStorage<'a, Jump>,
         WriteStorage<'a, Position>,
         WriteStorage<'a, Velocity>,
+        WriteStorage<'a, Rotation>,
     );
 
     fn run(&mut self, data: Self::SystemData) {
-        let (input, mouse_euler, mov, jump, mut pos, mut vel) = data;
+        let (
+            entities,
+            events,
+            character,
+            input,
+            mouse_euler,
+            tick_length,
+            world,
+            colliders,
+            dead,
+            submersion,
+            mut mov,
+            mut jump,
+            mut pos,
+            mut vel,
+            mut rot,
+        ) = data;
 
-        if input.move_up {
-            for (mov, jump, vel) in (&mov, &jump, &mut vel).join() {
-                if mov.on_ground {
-                    vel.0.y -= jump.force;
-                }
+        for event in &*events {
+            match event {
+                // the setting is the player's walking speed
+                Event::ConfigEvent(ConfigEvent::MovementSpeed(speed)) => {
+                    if let Some(mov) = character.0.and_then(|ent| mov.get_mut(ent)) {
+                        mov.speed = *speed;
+                    }
+                },
+                Event::ConfigEvent(ConfigEvent::Physics(config)) => {
+                    self.min_ground_y = 1.0 - config.max_ground_slope;
+                },
+                _ => (),
+            }
+        }
+
+        // characters face where the camera is looking
+        for (_, rot, _) in (&mov, &mut rot, !&dead).join() {
+            *rot = Rotation::from_yaw(mouse_euler.yaw);
+        }
+
+        let dt = tick_length.seconds();
+        let jump_pressed = input.move_up && !self.jump_held;
+        self.jump_held = input.move_up;
+
+        for (ent, mov) in (&entities, &mut mov).join() {
+            mov.swimming = matches!(submersion.get(ent), Some(water) if water.depth >= SWIM_DEPTH);
+        }
+
+        for (mov, jump, vel, _) in (&mov, &mut jump, &mut vel, !&dead).join() {
+            // jumping swims up instead
+            if mov.swimming {
+                jump.airborne = false;
+                jump.buffered = 0.0;
+                continue;
+            }
+
+            if mov.on_ground {
+                jump.airborne = false;
+            }
+
+            // jumps are allowed shortly after walking off a ledge, and
+            // pressing jump shortly before landing jumps on landing
+            let can_jump = !jump.airborne && (mov.on_ground || mov.air_time <= jump.coyote_time);
+            if can_jump && (jump_pressed || jump.buffered > 0.0) {
+                vel.0.y = -jump.force;
+                jump.airborne = true;
+                jump.buffered = 0.0;
+            } else if jump_pressed {
+                jump.buffered = jump.buffer_time;
+            } else {
+                jump.buffered = (jump.buffered - dt).max(0.0);
             }
         }
 
@@ -48,16 +166,497 @@ impl<'a> System<'a> for PlayerMovement {
             movement += nalgebra::Vector3::x();
         }
 
-        if let Some(movement) = movement.try_normalize(0.001) {
+        let direction = movement.try_normalize(0.001).map(|movement| {
             let rotation = nalgebra::Rotation3::from_axis_angle(
                 &nalgebra::Vector3::<f64>::y_axis(),
                 mouse_euler.yaw
             );
+            rotation.transform_vector(&movement)
+        });
+
+        // swimming follows the view up and down as well
+        let mut swim_direction = nalgebra::Rotation3::from_euler_angles(
+            mouse_euler.pitch,
+            mouse_euler.yaw,
+            0.0,
+        ).transform_vector(&movement);
+        if input.move_up {
+            swim_direction -= nalgebra::Vector3::y();
+        }
+        let swim_direction = swim_direction.try_normalize(0.001);
+
+        let mut grounded = Vec::new();
+        for (ent, mov, position) in (&entities, &mov, &pos).join() {
+            let falling = match vel.get(ent) {
+                Some(vel) => vel.0.y >= 0.0,
+                None => true,
+            };
+            if mov.on_ground || mov.swimming || !falling || matches!(jump.get(ent), Some(jump) if jump.airborne) {
+                continue;
+            }
+
+            let ground = Probe::new(&world, &pos, &colliders, ent)
+                .and_then(|probe| self.find_ground(&probe, &position.0));
+            if let Some(ground) = ground {
+                grounded.push((ent, ground));
+            }
+        }
+
+        for (ent, (ground, on)) in grounded {
+            pos.get_mut(ent).unwrap().0 = ground;
+            let mov = mov.get_mut(ent).unwrap();
+            mov.on_ground = true;
+            mov.ground = Some(on);
+        }
+
+        // the dead stay where they fell
+        let mut moves = Vec::new();
+        for (ent, mov, _) in (&entities, &mut mov, !&dead).join() {
+            mov.sprinting = input.sprint;
+            mov.crouching = input.crouch;
+            if mov.on_ground {
+                mov.air_time = 0.0;
+            } else {
+                mov.air_time += dt;
+            }
+
+            if mov.swimming {
+                if let (Some(direction), Some(pos)) = (swim_direction, pos.get_mut(ent)) {
+                    pos.0 += direction * mov.swim_speed * dt;
+                }
+            } else if let Some(direction) = direction {
+                moves.push((ent, direction, mov.current_speed() * dt, mov.on_ground, mov.step_height));
+            }
+        }
+
+        for (ent, direction, distance, on_ground, step_height) in moves {
+            let start = match pos.get(ent) {
+                Some(pos) => pos.0,
+                None => continue,
+            };
+
+            let destination = match colliders.get(ent) {
+                Some(collider) => {
+                    let stepped = if on_ground && step_height > 0.0 && self.is_blocked(collider, &direction) {
+                        Probe::new(&world, &pos, &colliders, ent)
+                            .and_then(|probe| self.step_up(&probe, &start, &direction, distance, step_height))
+                    } else {
+                        None
+                    };
+
+                    // slopes too steep to stand on are not walked up, the
+                    // character slides down them under gravity instead
+                    stepped.unwrap_or_else(|| start + self.follow_surfaces(collider, direction) * distance)
+                },
+                None => start + direction * distance,
+            };
+
+            pos.get_mut(ent).unwrap().0 = destination;
+        }
+    }
+}
+
+impl PlayerMovement {
+    /**
+     * Whether last tick's collisions include something too steep to walk up
+     * in the way of 'direction'
+     */
+    fn is_blocked(&self, collider: &Collider, direction: &nalgebra::Vector3<f64>) -> bool {
+        collider.collisions.iter().any(|collision| {
+            collision.normal.y < self.min_ground_y
+                && matches!(horizontal(&collision.normal), Some(normal) if normal.dot(direction) > 0.0)
+        })
+    }
+
+    /**
+     * Turns 'direction' along the ground and away from walls and steep slopes
+     */
+    fn follow_surfaces(&self, collider: &Collider, mut direction: nalgebra::Vector3<f64>)
+        -> nalgebra::Vector3<f64>
+    {
+        let mut ground: Option<nalgebra::Unit<nalgebra::Vector3<f64>>> = None;
+
+        for collision in &collider.collisions {
+            let normal = collision.normal;
+            if normal.y >= self.min_ground_y {
+                ground = match ground {
+                    Some(ground) if ground.y >= normal.y => Some(ground),
+                    _ => Some(normal),
+                };
+            } else if let Some(normal) = horizontal(&normal) {
+                let into = direction.dot(&normal);
+                if into > 0.0 {
+                    direction -= normal.as_ref() * into;
+                }
+            }
+        }
+
+        if let Some(ground) = ground {
+            let length = direction.norm();
+            let along = direction - ground.as_ref() * direction.dot(&ground);
+            if let Some(along) = along.try_normalize(f64::EPSILON) {
+                direction = along * length;
+            }
+        }
+
+        direction
+    }
+
+    /**
+     * Finds walkable ground within 'GROUND_SNAP' below a character resting on
+     * it, which the resolver leaves just out of contact. Returns where the
+     * character stands on it, and what it is.
+     */
+    fn find_ground(&self, probe: &Probe, start: &nalgebra::Point3<f64>)
+        -> Option<(nalgebra::Point3<f64>, Entity)>
+    {
+        let up = nalgebra::Vector3::new(0.0, -1.0, 0.0);
+        let raised = start + up * GROUND_SNAP;
+        let ground = probe.cast(&raised, &-up, GROUND_SNAP * 2.0)?;
+        let position = raised - up * ground.distance;
+        if !self.is_walkable(probe, &position, &ground) {
+            return None;
+        }
+
+        Some((position, ground.entity))
+    }
+
+    /**
+     * Moves the character up onto a ledge no higher than 'step_height',
+     * returning None if there is none to step on
+     */
+    fn step_up(
+        &self,
+        probe: &Probe,
+        start: &nalgebra::Point3<f64>,
+        direction: &nalgebra::Vector3<f64>,
+        distance: f64,
+        step_height: f64,
+    ) -> Option<nalgebra::Point3<f64>>
+    {
+        let up = nalgebra::Vector3::new(0.0, -1.0, 0.0);
+        let rise = probe.cast(start, &up, step_height).map_or(step_height, |hit| hit.distance);
+        let raised = start + up * rise;
+        if probe.cast(&raised, direction, distance).is_some() {
+            return None;
+        }
+
+        let ahead = raised + direction * distance;
+        let landing = probe.cast(&ahead, &-up, rise)?;
+        let position = ahead - up * landing.distance;
+        if !self.is_walkable(probe, &position, &landing) {
+            return None;
+        }
+
+        Some(position)
+    }
+
+    /**
+     * Whether 'hit' is on ground the character can stand on at 'position'.
+     * Contacts on the rounded edges of ledges count as long as the surface
+     * at the edge is walkable.
+     */
+    fn is_walkable(&self, probe: &Probe, position: &nalgebra::Point3<f64>, hit: &RayHit) -> bool {
+        if -hit.normal.y >= self.min_ground_y {
+            return true;
+        }
+
+        let inwards = match horizontal(&hit.normal) {
+            Some(outwards) => -outwards.into_inner(),
+            None => return false,
+        };
+        let down = nalgebra::Vector3::new(0.0, 1.0, 0.0);
+        let mut origin = position - hit.normal.as_ref() * probe.radius + inwards * EDGE_MARGIN;
+        origin.y = position.y;
+
+        match probe.sweep(&origin, &down, 0.0, probe.radius * 2.0) {
+            Some(surface) => -surface.normal.y >= self.min_ground_y,
+            None => false,
+        }
+    }
+}
+
+/**
+ * Sphere casts of a character's collider against everything it collides with
+ */
+struct Probe<'s, 'e> {
+    world: &'s CollisionWorld,
+    positions: &'s WriteStorage<'e, Position>,
+    colliders: &'s ReadStorage<'e, Collider>,
+    entity: Entity,
+    collider: &'s Collider,
+    radius: f64,
+}
+
+impl<'s, 'e> Probe<'s, 'e> {
+    fn new(
+        world: &'s CollisionWorld,
+        positions: &'s WriteStorage<'e, Position>,
+        colliders: &'s ReadStorage<'e, Collider>,
+        entity: Entity,
+    ) -> Option<Probe<'s, 'e>>
+    {
+        let collider = colliders.get(entity)?;
+        let radius = collider.collider.inner_radius()?;
+        Some(Probe { world, positions, colliders, entity, collider, radius })
+    }
+
+    /**
+     * Surfaces touching the collider at 'origin' are ignored, so that casts
+     * can start from resting contact
+     */
+    fn cast(
+        &self,
+        origin: &nalgebra::Point3<f64>,
+        direction: &nalgebra::Vector3<f64>,
+        max_distance: f64,
+    ) -> Option<RayHit>
+    {
+        self.sweep(origin, direction, self.radius, max_distance)
+    }
+
+    fn sweep(
+        &self,
+        origin: &nalgebra::Point3<f64>,
+        direction: &nalgebra::Vector3<f64>,
+        radius: f64,
+        max_distance: f64,
+    ) -> Option<RayHit>
+    {
+        self.world.cast_with(
+            self.positions, self.colliders,
+            origin, direction,
+            radius, max_distance,
+            |other, other_collider, (hit_distance, _)| {
+                other != self.entity
+                    && !other_collider.trigger
+                    && self.collider.interacts_with(other_collider)
+                    && *hit_distance > 0.0
+            },
+        )
+    }
+}
+
+fn horizontal(normal: &nalgebra::Vector3<f64>) -> Option<nalgebra::Unit<nalgebra::Vector3<f64>>> {
+    nalgebra::Unit::try_new(nalgebra::Vector3::new(normal.x, 0.0, normal.z), f64::EPSILON)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::simulation::{
+        component::collider::ColliderType,
+        system::{
+            CollisionDetection,
+            CollisionResolver,
+            Physics,
+        },
+    };
+
+    const DT: f64 = 1.0 / 60.0;
+
+    fn build_world() -> World {
+        let config = PhysicsConfig::default();
+        let mut world = World::new();
+        System::setup(&mut PlayerMovement::new(&config), &mut world);
+        System::setup(&mut Physics::new(&config), &mut world);
+        System::setup(&mut CollisionDetection::new(&config), &mut world);
+        System::setup(&mut CollisionResolver::new(&config), &mut world);
+        world.insert(TickLength(std::time::Duration::from_secs_f64(DT)));
+        world
+    }
+
+    fn add_static(world: &mut World, position: [f64; 3], collider: ColliderType) {
+        world.create_entity()
+            .with(Position(position.into()))
+            .with(Collider::new(collider))
+            .build();
+    }
+
+    fn add_player(world: &mut World, position: [f64; 3]) -> Entity {
+        world.create_entity()
+            .with(Position(position.into()))
+            .with(Velocity(nalgebra::Vector3::zeros()))
+            .with(Collider::new(ColliderType::Sphere(0.5)))
+            .with(Movement {
+                sprint_speed: 12.0,
+                crouch_speed: 3.0,
+                air_control: 0.5,
+                step_height: 0.5,
+                ..Movement::new(6.0)
+            })
+            .with(Jump {
+                coyote_time: 0.1,
+                buffer_time: 0.1,
+                ..Jump::new(10.0)
+            })
+            .build()
+    }
+
+    fn floor() -> ColliderType {
+        ColliderType::Plane(nalgebra::Unit::new_normalize(nalgebra::Vector3::new(0.0, -1.0, 0.0)))
+    }
+
+    /**
+     * Runs the movement and collision pipeline for a number of ticks,
+     * returning the player's position after each of them
+     */
+    fn simulate(world: &mut World, player: Entity, ticks: usize) -> Vec<nalgebra::Point3<f64>> {
+        let config = PhysicsConfig::default();
+        let mut movement = PlayerMovement::new(&config);
+        let mut physics = Physics::new(&config);
+        let mut detection = CollisionDetection::new(&config);
+        let mut resolver = CollisionResolver::new(&config);
+
+        (0..ticks)
+            .map(|_| {
+                movement.run_now(world);
+                physics.run_now(world);
+                detection.run_now(world);
+                resolver.run_now(world);
+                world.maintain();
 
-            let movement = rotation.transform_vector(&movement);
-            for (mov, pos) in (&mov, &mut pos).join() {
-                pos.0 += movement * mov.speed;
+                world.read_storage::<Position>().get(player).unwrap().0
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_step_up() {
+        // a low ledge is walked onto
+        let mut world = build_world();
+        add_static(&mut world, [0.0, 0.0, 0.0], floor());
+        add_static(&mut world, [2.0, -0.2, 0.0], ColliderType::Aabb(nalgebra::Vector3::new(1.0, 0.2, 1.0)));
+        let player = add_player(&mut world, [0.0, -0.5, 0.0]);
+        world.write_resource::<InputMap>().move_right = true;
+
+        let path = simulate(&mut world, player, 20);
+        let on_step = path.iter().find(|pos| pos.x > 1.5).unwrap_or_else(|| panic!("{:?}", path));
+        assert!((on_step.y + 0.9).abs() < 0.05, "{:?}", path);
+
+        // while a high one blocks the way
+        let mut world = build_world();
+        add_static(&mut world, [0.0, 0.0, 0.0], floor());
+        add_static(&mut world, [2.0, -0.5, 0.0], ColliderType::Aabb(nalgebra::Vector3::new(1.0, 0.5, 1.0)));
+        let player = add_player(&mut world, [0.0, -0.5, 0.0]);
+        world.write_resource::<InputMap>().move_right = true;
+
+        let path = simulate(&mut world, player, 20);
+        for pos in &path {
+            assert!(pos.x < 0.55 && (pos.y + 0.5).abs() < 0.05, "{:?}", path);
+        }
+    }
+
+    #[test]
+    fn test_slopes() {
+        let slope = |angle: f64| ColliderType::Plane(nalgebra::Unit::new_normalize(
+            nalgebra::Vector3::new(angle.sin(), -angle.cos(), 0.0)
+        ));
+
+        // standing still on a gentle slope
+        let mut world = build_world();
+        add_static(&mut world, [0.0, 0.0, 0.0], slope(0.3));
+        let player = add_player(&mut world, [0.5 * 0.3f64.sin(), -0.5 * 0.3f64.cos(), 0.0]);
+        let path = simulate(&mut world, player, 30);
+        let last = path.last().unwrap();
+        assert!(last.x.abs() < 0.2 && last.y.abs() < 0.5, "{:?}", path);
+
+        // sliding down a steep one, even when walking up it
+        let mut world = build_world();
+        add_static(&mut world, [0.0, 0.0, 0.0], slope(1.0));
+        let player = add_player(&mut world, [0.5 * 1.0f64.sin(), -0.5 * 1.0f64.cos(), 0.0]);
+        world.write_resource::<InputMap>().move_left = true;
+        let path = simulate(&mut world, player, 30);
+        for pair in path.windows(2).skip(1) {
+            assert!(pair[1].x >= pair[0].x && pair[1].y >= pair[0].y, "{:?}", path);
+        }
+        assert!(path.last().unwrap().x > 1.0, "{:?}", path);
+    }
+
+    #[test]
+    fn test_speeds() {
+        let mut world = build_world();
+        let player = add_player(&mut world, [0.0, 0.0, 0.0]);
+        world.write_resource::<InputMap>().move_forward = true;
+        let mut movement = PlayerMovement::new(&PhysicsConfig::default());
+
+        let mut step = |world: &mut World, sprint: bool, crouch: bool, on_ground: bool| {
+            {
+                let mut input = world.write_resource::<InputMap>();
+                input.sprint = sprint;
+                input.crouch = crouch;
             }
+            world.write_storage::<Movement>().get_mut(player).unwrap().on_ground = on_ground;
+            let before = world.read_storage::<Position>().get(player).unwrap().0;
+            movement.run_now(world);
+            let after = world.read_storage::<Position>().get(player).unwrap().0;
+            (before - after).z / DT
+        };
+
+        let speed = step(&mut world, false, false, true);
+        assert!((speed - 6.0).abs() < 1e-6, "{}", speed);
+
+        let speed = step(&mut world, true, false, true);
+        assert!((speed - 12.0).abs() < 1e-6, "{}", speed);
+
+        let speed = step(&mut world, true, true, true);
+        assert!((speed - 3.0).abs() < 1e-6, "{}", speed);
+
+        let speed = step(&mut world, false, false, false);
+        assert!((speed - 3.0).abs() < 1e-6, "{}", speed);
+    }
+
+    #[test]
+    fn test_speed_setting_applies_to_player() {
+        let mut world = build_world();
+        let player = add_player(&mut world, [0.0, 0.0, 0.0]);
+        let other = add_player(&mut world, [10.0, 0.0, 0.0]);
+        world.insert(ActiveCharacter(Some(player)));
+        world.insert(vec![Event::ConfigEvent(ConfigEvent::MovementSpeed(9.0))]);
+
+        PlayerMovement::new(&PhysicsConfig::default()).run_now(&world);
+
+        let movement = world.read_storage::<Movement>();
+        assert_eq!(movement.get(player).unwrap().speed, 9.0);
+        assert_eq!(movement.get(other).unwrap().speed, 6.0);
+    }
+
+    #[test]
+    fn test_coyote_time_and_jump_buffer() {
+        let mut world = build_world();
+        let player = add_player(&mut world, [0.0, 0.0, 0.0]);
+        let mut movement = PlayerMovement::new(&PhysicsConfig::default());
+
+        let mut step = |world: &mut World, jump: bool, on_ground: bool| {
+            world.write_resource::<InputMap>().move_up = jump;
+            world.write_storage::<Movement>().get_mut(player).unwrap().on_ground = on_ground;
+            world.write_storage::<Velocity>().get_mut(player).unwrap().0.y = 0.0;
+            movement.run_now(world);
+            world.read_storage::<Velocity>().get(player).unwrap().0.y < 0.0
+        };
+
+        // jumping right after walking off a ledge
+        for _ in 0..3 {
+            assert!(!step(&mut world, false, false));
         }
+        assert!(step(&mut world, true, false));
+
+        // but only once per landing, the press is buffered instead
+        assert!(!step(&mut world, false, false));
+        assert!(!step(&mut world, true, false));
+        assert!(!step(&mut world, true, false));
+        assert!(step(&mut world, true, true));
+
+        // and forgotten after 'buffer_time'
+        assert!(!step(&mut world, false, false));
+        assert!(!step(&mut world, true, false));
+        for _ in 0..10 {
+            assert!(!step(&mut world, false, false));
+        }
+        assert!(!step(&mut world, false, true));
+
+        // holding jump does not jump again
+        assert!(step(&mut world, true, true));
+        assert!(!step(&mut world, true, true));
     }
-}
\ No newline at end of file
+}
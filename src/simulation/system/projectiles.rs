@@ -0,0 +1,276 @@
+use std::sync::mpsc::Sender;
+
+use specs::prelude::*;
+
+use crate::simulation::{
+    component::{
+        Collider,
+        Position,
+        Projectile,
+    },
+    event::{
+        ConfigEvent,
+        DespawnUpdate,
+        Event,
+        ImpactEvent,
+        Update,
+    },
+    resource::{
+        CollisionWorld,
+        EventQueue,
+        ImpactQueue,
+        TickLength,
+    },
+    PhysicsConfig,
+};
+
+/**
+ * Moves projectiles, sweeping them against solid colliders. Projectiles
+ * that hit something raise an impact event and are removed along with
+ * those that expired.
+ */
+pub struct Projectiles {
+    sender: Sender<Update>,
+    gravity: nalgebra::Vector3<f64>,
+}
+
+impl Projectiles {
+    pub fn new(config: &PhysicsConfig, sender: Sender<Update>) -> Projectiles {
+        Projectiles {
+            sender,
+            gravity: nalgebra::Vector3::new(0.0, config.gravity, 0.0),
+        }
+    }
+
+    fn send_event(&self, event: Update) {
+        self.sender.send(event).unwrap_or_else(|err| {
+            log::error!("failed to send update event: {}", err);
+        });
+    }
+}
+
+impl<'a> System<'a> for Projectiles {
+    type SystemData = (
+        Entities<'a>,
+        Read<'a, EventQueue>,
+        Read<'a, TickLength>,
+        Read<'a, CollisionWorld>,
+        Write<'a, ImpactQueue>,
+        WriteStorage<'a, Position>,
+        WriteStorage<'a, Projectile>,
+        ReadStorage<'a, Collider>,
+    );
+
+    fn run(&mut self, data: Self::SystemData) {
+        let (
+            entities,
+            events,
+            tick_length,
+            collision_world,
+            mut impacts,
+            mut positions,
+            mut projectiles,
+            colliders,
+        ) = data;
+
+        for event in &*events {
+            if let Event::ConfigEvent(ConfigEvent::Physics(config)) = event {
+                self.gravity = nalgebra::Vector3::new(0.0, config.gravity, 0.0);
+            }
+        }
+
+        impacts.clear();
+        let dt = tick_length.seconds();
+
+        let mut moves = Vec::new();
+        for (ent, position, projectile) in (&entities, &positions, &mut projectiles).join() {
+            // constant acceleration is integrated exactly
+            let start_velocity = projectile.velocity;
+            projectile.velocity += self.gravity * projectile.gravity * dt;
+            let motion = (start_velocity + projectile.velocity) * 0.5 * dt;
+            projectile.lifetime -= dt;
+
+            let owner = projectile.owner;
+            let distance = motion.norm();
+            // nothing to sweep for projectiles at rest
+            let hit = if distance < f64::EPSILON {
+                None
+            } else {
+                collision_world.cast_with(
+                    &positions,
+                    &colliders,
+                    &position.0,
+                    &motion,
+                    projectile.radius,
+                    distance,
+                    |other, collider, _| other != ent && Some(other) != owner && !collider.trigger,
+                )
+            };
+
+            match hit {
+                Some(hit) => {
+                    let destination = position.0 + motion * (hit.distance / distance);
+                    impacts.push(ImpactEvent {
+                        projectile: ent,
+                        owner,
+                        entity: hit.entity,
+                        point: hit.point,
+                        normal: hit.normal.into_inner(),
+                    });
+                    moves.push((ent, destination, true));
+                },
+                None => moves.push((ent, position.0 + motion, projectile.lifetime <= 0.0)),
+            };
+        }
+
+        for (ent, destination, despawn) in moves {
+            if let Some(position) = positions.get_mut(ent) {
+                position.0 = destination;
+            }
+
+            if despawn {
+                entities.delete(ent).unwrap_or_else(|err| {
+                    log::warn!("failed to remove projectile: {}", err);
+                });
+                self.send_event(Update::DespawnUpdate(DespawnUpdate { entity: ent }));
+            }
+        }
+
+        for impact in &*impacts {
+            log::debug!("Projectile {:?} hit {:?} at {:?}", impact.projectile, impact.entity, impact.point);
+            self.send_event(Update::ImpactUpdate(*impact));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::simulation::component::collider::ColliderType;
+
+    fn build_world(system: &mut Projectiles) -> World {
+        let mut world = World::new();
+        System::setup(system, &mut world);
+        world.insert(TickLength(std::time::Duration::from_secs_f64(1.0 / 60.0)));
+        world
+    }
+
+    fn build_collision_world(world: &mut World) {
+        let mut collision_world = CollisionWorld::new(4.0);
+        {
+            let positions = world.read_storage::<Position>();
+            let colliders = world.read_storage::<Collider>();
+            for (ent, position, collider) in (&world.entities(), &positions, &colliders).join() {
+                collision_world.insert(ent, position, collider);
+            }
+        }
+        world.insert(collision_world);
+    }
+
+    fn launch(world: &mut World, owner: Option<Entity>, velocity: nalgebra::Vector3<f64>, gravity: f64)
+        -> Entity
+    {
+        world.create_entity()
+            .with(Position(nalgebra::Point3::origin()))
+            .with(Projectile {
+                owner,
+                velocity,
+                gravity,
+                radius: 0.1,
+                lifetime: 5.0,
+            })
+            .build()
+    }
+
+    #[test]
+    fn test_impact() {
+        let (tx, rx) = std::sync::mpsc::channel();
+        let mut system = Projectiles::new(&PhysicsConfig::default(), tx);
+
+        let mut world = build_world(&mut system);
+        let owner = world.create_entity()
+            .with(Position(nalgebra::Point3::origin()))
+            .with(Collider::new(ColliderType::Sphere(1.0)))
+            .build();
+        let wall = world.create_entity()
+            .with(Position(nalgebra::Point3::new(0.0, 0.0, -20.0)))
+            .with(Collider::new(ColliderType::Sphere(1.0)))
+            .build();
+        build_collision_world(&mut world);
+
+        // fast enough to pass through the wall in a single tick
+        let spear = launch(&mut world, Some(owner), nalgebra::Vector3::new(0.0, 0.0, -1500.0), 0.0);
+
+        system.run_now(&world);
+
+        {
+            let impacts = world.read_resource::<ImpactQueue>();
+            assert_eq!(impacts.len(), 1);
+            assert_eq!(impacts[0].entity, wall);
+            assert_eq!(impacts[0].owner, Some(owner));
+            assert!((impacts[0].point.z + 19.0).abs() < 1e-6);
+            assert!((impacts[0].normal.z - 1.0).abs() < 1e-6);
+        }
+        world.maintain();
+        assert!(!world.is_alive(spear));
+
+        let updates = rx.try_iter().collect::<Vec<_>>();
+        assert_eq!(updates.len(), 2);
+        match &updates[0] {
+            Update::DespawnUpdate(update) => assert_eq!(update.entity, spear),
+            _ => panic!("expected a despawn update"),
+        }
+    }
+
+    #[test]
+    fn test_gravity_and_lifetime() {
+        let config = PhysicsConfig::default();
+        let (tx, _rx) = std::sync::mpsc::channel();
+        let mut system = Projectiles::new(&config, tx);
+
+        let mut world = build_world(&mut system);
+        build_collision_world(&mut world);
+
+        let arrow = launch(&mut world, None, nalgebra::Vector3::new(10.0, 0.0, 0.0), 1.0);
+        let bolt = launch(&mut world, None, nalgebra::Vector3::new(10.0, 0.0, 0.0), 0.0);
+
+        for _ in 0..60 {
+            system.run_now(&world);
+        }
+
+        {
+            let positions = world.read_storage::<Position>();
+            let arrow = positions.get(arrow).unwrap().0;
+            assert!((arrow.x - 10.0).abs() < 1e-6);
+            assert!((arrow.y - config.gravity * 0.5).abs() < 1e-6);
+            assert_eq!(positions.get(bolt).unwrap().0.y, 0.0);
+        }
+
+        for _ in 0..250 {
+            system.run_now(&world);
+        }
+        world.maintain();
+        assert!(!world.is_alive(arrow));
+        assert!(!world.is_alive(bolt));
+        assert!(world.read_resource::<ImpactQueue>().is_empty());
+    }
+
+    #[test]
+    fn test_at_rest() {
+        let (tx, _rx) = std::sync::mpsc::channel();
+        let mut system = Projectiles::new(&PhysicsConfig::default(), tx);
+
+        let mut world = build_world(&mut system);
+        world.create_entity()
+            .with(Position(nalgebra::Point3::origin()))
+            .with(Collider::new(ColliderType::Sphere(1.0)))
+            .build();
+        build_collision_world(&mut world);
+
+        let mine = launch(&mut world, None, nalgebra::Vector3::zeros(), 0.0);
+        system.run_now(&world);
+
+        assert_eq!(world.read_storage::<Position>().get(mine).unwrap().0, nalgebra::Point3::origin());
+        assert!(world.read_resource::<ImpactQueue>().is_empty());
+    }
+}
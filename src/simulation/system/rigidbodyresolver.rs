@@ -0,0 +1,338 @@
+use specs::prelude::*;
+
+use crate::simulation::PhysicsConfig;
+use crate::simulation::{
+    component::{
+        collider::Collider,
+        Position,
+        RigidBody,
+        Velocity,
+    },
+    event::{
+        ConfigEvent,
+        Event,
+    },
+    resource::{
+        EventQueue,
+        TickLength,
+    },
+};
+
+/**
+ * Impulse based collision response for rigid bodies. Anything colliding with
+ * a body that is not a rigid body itself is treated as having infinite mass,
+ * entities without a `Velocity` as static.
+ */
+pub struct RigidBodyResolver {
+    gravity: f64,
+    sleep_speed: f64,
+    sleep_time: f64,
+}
+
+impl RigidBodyResolver {
+    pub fn new(config: &PhysicsConfig) -> RigidBodyResolver {
+        RigidBodyResolver {
+            gravity: config.gravity,
+            sleep_speed: config.sleep_speed,
+            sleep_time: config.sleep_time,
+        }
+    }
+}
+
+/**
+ * Response properties of one side of a contact
+ */
+struct Side {
+    inverse_mass: f64,
+    restitution: f64,
+    friction: f64,
+}
+
+impl<'a> System<'a> for RigidBodyResolver {
+    type SystemData = (
+        Entities<'a>,
+        Read<'a, EventQueue>,
+        Read<'a, TickLength>,
+        ReadStorage<'a, Collider>,
+        WriteStorage<'a, RigidBody>,
+        WriteStorage<'a, Position>,
+        WriteStorage<'a, Velocity>,
+    );
+
+    fn run(&mut self, data: Self::SystemData) {
+        let (entities, events, tick_length, colliders, mut bodies, mut positions, mut velocities) = data;
+
+        for event in &*events {
+            if let Event::ConfigEvent(ConfigEvent::Physics(config)) = event {
+                *self = RigidBodyResolver::new(config);
+            }
+        }
+
+        let dt = tick_length.seconds();
+        // approach speeds gravity builds up within a couple of ticks are not
+        // bounced off, so that resting bodies settle
+        let bounce_speed = self.gravity * dt * 2.0;
+
+        let mut contacts = Vec::new();
+        for (ent, collider, _, _) in (&entities, &colliders, &bodies, &velocities).join() {
+            for collision in &collider.collisions {
+                // contacts between two bodies are handled once
+                if bodies.contains(collision.with) && collision.with < ent {
+                    continue;
+                }
+                contacts.push((ent, collision.with, collision.depth, collision.normal));
+            }
+        }
+
+        for (a, b, depth, normal) in contacts {
+            let a_velocity = velocities.get(a).unwrap().0;
+            let b_velocity = velocities.get(b).map_or(nalgebra::Vector3::zeros(), |vel| vel.0);
+
+            let a_side = match self.side(&mut bodies, a, &b_velocity) {
+                Some(side) => side,
+                None => continue,
+            };
+            let b_side = match self.side(&mut bodies, b, &a_velocity) {
+                Some(side) => side,
+                None => Side {
+                    inverse_mass: 0.0,
+                    restitution: a_side.restitution,
+                    friction: a_side.friction,
+                },
+            };
+
+            let total_inverse_mass = a_side.inverse_mass + b_side.inverse_mass;
+            if total_inverse_mass <= 0.0 {
+                continue;
+            }
+
+            // move apart in proportion to the inverse masses
+            let a_share = a_side.inverse_mass / total_inverse_mass;
+            let b_share = b_side.inverse_mass / total_inverse_mass;
+            positions.get_mut(a).unwrap().0 -= depth * a_share;
+            if b_share > 0.0 {
+                positions.get_mut(b).unwrap().0 += depth * b_share;
+            }
+
+            // the normal points from 'a' towards 'b'
+            let relative = b_velocity - a_velocity;
+            let approach = relative.dot(&normal);
+            if approach >= 0.0 {
+                continue;
+            }
+
+            let restitution = if -approach > bounce_speed {
+                a_side.restitution.max(b_side.restitution)
+            } else {
+                0.0
+            };
+            let normal_impulse = -(1.0 + restitution) * approach / total_inverse_mass;
+            let mut impulse = normal.as_ref() * normal_impulse;
+
+            // Coulomb friction along the sliding direction, limited by the
+            // normal impulse
+            let tangent = relative - normal.as_ref() * approach;
+            if let Some(tangent) = nalgebra::Unit::try_new(tangent, f64::EPSILON) {
+                let friction = (a_side.friction * b_side.friction).sqrt();
+                let limit = friction * normal_impulse;
+                let friction_impulse = (-relative.dot(&tangent) / total_inverse_mass)
+                    .clamp(-limit, limit);
+                impulse += tangent.as_ref() * friction_impulse;
+            }
+
+            velocities.get_mut(a).unwrap().0 -= impulse * a_side.inverse_mass;
+            if b_side.inverse_mass > 0.0 {
+                velocities.get_mut(b).unwrap().0 += impulse * b_side.inverse_mass;
+            }
+        }
+
+        for (body, vel) in (&mut bodies, &mut velocities).join() {
+            if body.sleeping {
+                continue;
+            }
+
+            if vel.0.norm() < self.sleep_speed {
+                body.idle_time += dt;
+                if body.idle_time >= self.sleep_time {
+                    body.sleeping = true;
+                    vel.0 = nalgebra::Vector3::zeros();
+                }
+            } else {
+                body.idle_time = 0.0;
+            }
+        }
+    }
+}
+
+impl RigidBodyResolver {
+    /**
+     * Response properties of 'entity' in a contact with something moving at
+     * 'other_velocity', waking it up if that is fast enough. Sleeping bodies
+     * take part with infinite mass, and None is returned for entities which
+     * are not rigid bodies.
+     */
+    fn side(
+        &self,
+        bodies: &mut WriteStorage<RigidBody>,
+        entity: Entity,
+        other_velocity: &nalgebra::Vector3<f64>,
+    ) -> Option<Side>
+    {
+        let body = bodies.get_mut(entity)?;
+        if body.sleeping && other_velocity.norm() >= self.sleep_speed {
+            body.wake();
+        }
+
+        Some(Side {
+            inverse_mass: if body.sleeping { 0.0 } else { body.inverse_mass() },
+            restitution: body.restitution,
+            friction: body.friction,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::simulation::{
+        component::collider::ColliderType,
+        system::{
+            CollisionDetection,
+            Physics,
+        },
+    };
+
+    fn build_world() -> World {
+        let config = PhysicsConfig::default();
+        let mut world = World::new();
+        System::setup(&mut Physics::new(&config), &mut world);
+        System::setup(&mut CollisionDetection::new(&config), &mut world);
+        System::setup(&mut RigidBodyResolver::new(&config), &mut world);
+        world.insert(TickLength(std::time::Duration::from_secs_f64(1.0 / 60.0)));
+
+        world.create_entity()
+            .with(Position(nalgebra::Point3::new(0.0, 0.0, 0.0)))
+            .with(Collider::new(ColliderType::Plane(nalgebra::Unit::new_normalize(
+                nalgebra::Vector3::new(0.0, -1.0, 0.0)
+            ))))
+            .build();
+
+        world
+    }
+
+    fn add_ball(world: &mut World, position: [f64; 3], velocity: [f64; 3], body: RigidBody) -> Entity {
+        world.create_entity()
+            .with(Position(position.into()))
+            .with(Velocity(velocity.into()))
+            .with(Collider::new(ColliderType::Sphere(0.5)))
+            .with(body)
+            .build()
+    }
+
+    fn simulate(world: &mut World, ticks: usize) {
+        let config = PhysicsConfig::default();
+        let mut physics = Physics::new(&config);
+        let mut detection = CollisionDetection::new(&config);
+        let mut resolver = RigidBodyResolver::new(&config);
+
+        for _ in 0..ticks {
+            physics.run_now(world);
+            detection.run_now(world);
+            resolver.run_now(world);
+            world.maintain();
+        }
+    }
+
+    fn position(world: &World, entity: Entity) -> nalgebra::Point3<f64> {
+        world.read_storage::<Position>().get(entity).unwrap().0
+    }
+
+    fn velocity(world: &World, entity: Entity) -> nalgebra::Vector3<f64> {
+        world.read_storage::<Velocity>().get(entity).unwrap().0
+    }
+
+    #[test]
+    fn test_restitution() {
+        let mut world = build_world();
+        let bouncy = add_ball(&mut world, [0.0, -0.6, 0.0], [0.0, 10.0, 0.0], RigidBody::new(1.0, 0.8, 0.0));
+        let dead = add_ball(&mut world, [10.0, -0.6, 0.0], [0.0, 10.0, 0.0], RigidBody::new(1.0, 0.0, 0.0));
+
+        simulate(&mut world, 2);
+
+        // bounced back up with most of the speed
+        let bounce = velocity(&world, bouncy).y;
+        assert!(bounce < -7.0 && bounce > -9.0, "{}", bounce);
+        assert!(velocity(&world, dead).y.abs() < 1e-9);
+        assert!(position(&world, dead).y <= -0.5 + 1e-9);
+    }
+
+    #[test]
+    fn test_friction_and_sleeping() {
+        let mut world = build_world();
+        let rough = add_ball(&mut world, [0.0, -0.5, 0.0], [5.0, 0.0, 0.0], RigidBody::new(1.0, 0.0, 1.0));
+        let smooth = add_ball(&mut world, [0.0, -0.5, 10.0], [5.0, 0.0, 0.0], RigidBody::new(1.0, 0.0, 0.0));
+
+        // friction decelerates by about 'friction * gravity'
+        simulate(&mut world, 6);
+        let speed = velocity(&world, rough).x;
+        let expected = 5.0 - PhysicsConfig::default().gravity * 0.1;
+        assert!((speed - expected).abs() < 0.5, "{} != {}", speed, expected);
+        assert!((velocity(&world, smooth).x - 5.0).abs() < 1e-9);
+
+        // comes to rest and falls asleep
+        simulate(&mut world, 60);
+        assert!(world.read_storage::<RigidBody>().get(rough).unwrap().sleeping);
+        assert!(!world.read_storage::<RigidBody>().get(smooth).unwrap().sleeping);
+        assert_eq!(velocity(&world, rough), nalgebra::Vector3::zeros());
+
+        let resting = position(&world, rough);
+        simulate(&mut world, 10);
+        assert_eq!(position(&world, rough), resting);
+    }
+
+    #[test]
+    fn test_momentum_between_bodies() {
+        let mut world = build_world();
+        let heavy = add_ball(&mut world, [0.0, -5.0, 0.0], [10.0, 0.0, 0.0], RigidBody::new(3.0, 0.0, 0.0));
+        let light = add_ball(&mut world, [1.1, -5.0, 0.0], [0.0, 0.0, 0.0], RigidBody::new(1.0, 0.0, 0.0));
+
+        simulate(&mut world, 1);
+
+        // a perfectly inelastic collision conserves momentum
+        let heavy_velocity = velocity(&world, heavy).x;
+        let light_velocity = velocity(&world, light).x;
+        assert!((heavy_velocity - light_velocity).abs() < 1e-9);
+        assert!((heavy_velocity * 3.0 + light_velocity - 30.0).abs() < 1e-9);
+
+        // and the lighter body is pushed three times as far apart
+        let heavy_moved = position(&world, heavy).x - 10.0 / 60.0;
+        let light_moved = position(&world, light).x - 1.1;
+        assert!(light_moved > 0.0);
+        assert!((light_moved + heavy_moved * 3.0).abs() < 1e-6);
+    }
+
+    #[test]
+    fn test_impulse_wakes_body() {
+        let mut world = build_world();
+        let mut body = RigidBody::new(2.0, 0.0, 0.5);
+        body.sleeping = true;
+        let ball = add_ball(&mut world, [0.0, -5.0, 0.0], [0.0, 0.0, 0.0], body);
+
+        simulate(&mut world, 5);
+        assert_eq!(position(&world, ball).y, -5.0);
+
+        {
+            let mut bodies = world.write_storage::<RigidBody>();
+            let mut velocities = world.write_storage::<Velocity>();
+            bodies.get_mut(ball).unwrap().apply_impulse(
+                velocities.get_mut(ball).unwrap(),
+                &nalgebra::Vector3::new(4.0, 0.0, 0.0),
+            );
+        }
+        assert_eq!(velocity(&world, ball).x, 2.0);
+
+        simulate(&mut world, 5);
+        assert!(position(&world, ball).x > 0.0);
+        assert!(position(&world, ball).y > -5.0);
+    }
+}
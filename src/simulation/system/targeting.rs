@@ -0,0 +1,303 @@
+use std::sync::mpsc::Sender;
+
+use specs::prelude::*;
+
+use crate::input::MouseEuler;
+use crate::simulation::{
+    component::{
+        Collider,
+        Dead,
+        Health,
+        Hostile,
+        Name,
+        Position,
+    },
+    event::{
+        Event,
+        InputEvent,
+        TargetUpdate,
+        Update,
+    },
+    resource::{
+        ActiveCharacter,
+        CollisionWorld,
+        EventQueue,
+        InputMap,
+        Target,
+    },
+};
+
+/**
+ * How far away hostiles can be tabbed to, in m
+ */
+const TAB_RANGE: f64 = 40.0;
+const SELECT_DISTANCE: f64 = 1000.0;
+
+/**
+ * Target and its current and maximum health
+ */
+type TargetState = (Option<Entity>, Option<(u64, u64)>);
+
+/**
+ * Selects the active character's target. Clicking selects whatever has
+ * health under the cursor, tabbing cycles through the nearby hostiles
+ * closest to the centre of the view, and clearing deselects.
+ */
+pub struct Targeting {
+    sender: Sender<Update>,
+    next_held: bool,
+    clear_held: bool,
+    sent: Option<TargetState>,
+}
+
+impl Targeting {
+    pub fn new(sender: Sender<Update>) -> Targeting {
+        Targeting {
+            sender,
+            next_held: false,
+            clear_held: false,
+            sent: None,
+        }
+    }
+}
+
+impl<'a> System<'a> for Targeting {
+    type SystemData = (
+        Entities<'a>,
+        Read<'a, EventQueue>,
+        Read<'a, ActiveCharacter>,
+        Read<'a, InputMap>,
+        Read<'a, MouseEuler>,
+        Read<'a, CollisionWorld>,
+        Write<'a, Target>,
+        ReadStorage<'a, Position>,
+        ReadStorage<'a, Collider>,
+        ReadStorage<'a, Health>,
+        ReadStorage<'a, Hostile>,
+        ReadStorage<'a, Dead>,
+        ReadStorage<'a, Name>,
+    );
+
+    fn run(&mut self, data: Self::SystemData) {
+        let (
+            entities,
+            events,
+            character,
+            input,
+            mouse_euler,
+            collision_world,
+            mut target,
+            positions,
+            colliders,
+            health,
+            hostiles,
+            dead,
+            names,
+        ) = data;
+
+        if let Some(ent) = target.0 {
+            if !entities.is_alive(ent) {
+                target.0 = None;
+            }
+        }
+
+        for event in &*events {
+            if let Event::InputEvent(InputEvent::Select { origin, direction }) = event {
+                // the view is behind the character, which is not in the way
+                let hit = collision_world.cast_with(
+                    &positions,
+                    &colliders,
+                    origin,
+                    direction,
+                    0.0,
+                    SELECT_DISTANCE,
+                    |ent, collider, _| Some(ent) != character.0 && !collider.trigger,
+                );
+
+                match hit {
+                    Some(hit) if health.contains(hit.entity) => target.0 = Some(hit.entity),
+                    Some(hit) => log::debug!("Entity {:?} cannot be targeted", hit.entity),
+                    None => (),
+                };
+            }
+        }
+
+        let next_pressed = input.target_next && !self.next_held;
+        self.next_held = input.target_next;
+        let clear_pressed = input.clear_target && !self.clear_held;
+        self.clear_held = input.clear_target;
+
+        if clear_pressed {
+            target.0 = None;
+        }
+
+        if next_pressed {
+            let origin = character.0.and_then(|ent| positions.get(ent));
+            if let Some(origin) = origin {
+                let forward = nalgebra::Rotation3::from_euler_angles(
+                    mouse_euler.pitch,
+                    mouse_euler.yaw,
+                    0.0,
+                ) * -nalgebra::Vector3::z();
+
+                let candidates = (&entities, &positions, &hostiles, !&dead).join()
+                    .filter(|(ent, ..)| Some(*ent) != character.0)
+                    .map(|(ent, position, ..)| (ent, position.0));
+                let order = tab_order(&origin.0, &forward, candidates);
+                target.0 = next_in_order(target.0, &order);
+            }
+        }
+
+        let target_health = target.0.and_then(|ent| health.get(ent)).map(|hp| (hp.current, hp.max));
+        let state: TargetState = (target.0, target_health);
+        if self.sent != Some(state) {
+            self.sent = Some(state);
+            self.sender.send(Update::TargetUpdate(TargetUpdate {
+                target: state.0,
+                name: state.0.and_then(|ent| names.get(ent)).map(|name| name.0.clone()),
+                health: state.1,
+            })).unwrap_or_else(|err| {
+                log::error!("failed to send update event: {}", err);
+            });
+        }
+    }
+}
+
+/**
+ * Hostiles within TAB_RANGE of 'origin', nearest first. Distances are
+ * weighted by the angle off 'forward', so that hostiles in view come before
+ * those behind.
+ */
+fn tab_order<I>(origin: &nalgebra::Point3<f64>, forward: &nalgebra::Vector3<f64>, hostiles: I)
+    -> Vec<Entity>
+where
+    I: Iterator<Item = (Entity, nalgebra::Point3<f64>)>,
+{
+    let mut order = hostiles
+        .filter_map(|(ent, position)| {
+            let offset = position - origin;
+            let distance = offset.norm();
+            if distance > TAB_RANGE {
+                return None;
+            }
+
+            let angle = if distance > 0.0 { forward.angle(&offset) } else { 0.0 };
+            Some((ent, distance * (1.0 + angle)))
+        })
+        .collect::<Vec<_>>();
+
+    order.sort_by(|a, b| a.1.partial_cmp(&b.1).unwrap_or(std::cmp::Ordering::Equal));
+    order.into_iter().map(|(ent, _)| ent).collect()
+}
+
+/**
+ * The entity after 'current' in 'order', wrapping around, or the first one
+ * if 'current' is not in it
+ */
+fn next_in_order(current: Option<Entity>, order: &[Entity]) -> Option<Entity> {
+    let index = current
+        .and_then(|current| order.iter().position(|ent| *ent == current))
+        .map(|index| (index + 1) % order.len())
+        .unwrap_or(0);
+
+    order.get(index).cloned()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_tab_order() {
+        let world = World::new();
+        let ahead = world.entities().create();
+        let behind = world.entities().create();
+        let far_ahead = world.entities().create();
+        let out_of_range = world.entities().create();
+
+        let hostiles = vec![
+            (behind, nalgebra::Point3::new(0.0, 0.0, 4.0)),
+            (far_ahead, nalgebra::Point3::new(0.0, 0.0, -10.0)),
+            (out_of_range, nalgebra::Point3::new(0.0, 0.0, -50.0)),
+            (ahead, nalgebra::Point3::new(1.0, 0.0, -5.0)),
+        ];
+
+        let order = tab_order(
+            &nalgebra::Point3::origin(),
+            &-nalgebra::Vector3::z(),
+            hostiles.into_iter(),
+        );
+        assert_eq!(order, vec![ahead, far_ahead, behind]);
+
+        assert_eq!(next_in_order(None, &order), Some(ahead));
+        assert_eq!(next_in_order(Some(ahead), &order), Some(far_ahead));
+        assert_eq!(next_in_order(Some(behind), &order), Some(ahead));
+        assert_eq!(next_in_order(Some(out_of_range), &order), Some(ahead));
+        assert_eq!(next_in_order(Some(ahead), &[]), None);
+    }
+
+    #[test]
+    fn test_select_and_clear() {
+        use crate::simulation::component::collider::ColliderType;
+
+        let (tx, rx) = std::sync::mpsc::channel();
+        let mut system = Targeting::new(tx);
+
+        let mut world = World::new();
+        System::setup(&mut system, &mut world);
+
+        let player = world.create_entity()
+            .with(Position(nalgebra::Point3::origin()))
+            .with(Collider::new(ColliderType::Sphere(1.0)))
+            .with(Health::new(100))
+            .build();
+        let enemy = world.create_entity()
+            .with(Position(nalgebra::Point3::new(0.0, 0.0, -6.0)))
+            .with(Collider::new(ColliderType::Sphere(1.0)))
+            .with(Health::new(50))
+            .with(Hostile)
+            .with(Name("Enemy".to_string()))
+            .build();
+        world.insert(ActiveCharacter(Some(player)));
+
+        let mut collision_world = CollisionWorld::new(4.0);
+        {
+            let positions = world.read_storage::<Position>();
+            let colliders = world.read_storage::<Collider>();
+            for (ent, position, collider) in (&world.entities(), &positions, &colliders).join() {
+                collision_world.insert(ent, position, collider);
+            }
+        }
+        world.insert(collision_world);
+
+        // clicking from behind the player goes through it
+        world.insert(vec![Event::InputEvent(InputEvent::Select {
+            origin: nalgebra::Point3::new(0.0, 0.0, 10.0),
+            direction: -nalgebra::Vector3::z(),
+        })]);
+
+        system.run_now(&world);
+        assert_eq!(world.read_resource::<Target>().0, Some(enemy));
+
+        world.insert(EventQueue::new());
+        world.write_resource::<InputMap>().clear_target = true;
+        system.run_now(&world);
+        assert_eq!(world.read_resource::<Target>().0, None);
+
+        // nothing changes after clearing, so nothing more is sent
+        system.run_now(&world);
+        let updates = rx.try_iter().collect::<Vec<_>>();
+        assert_eq!(updates.len(), 2);
+        match &updates[0] {
+            Update::TargetUpdate(update) => {
+                assert_eq!(update.name.as_ref().unwrap(), "Enemy");
+                assert_eq!(update.health, Some((50, 50)));
+            },
+            _ => panic!("expected a target update"),
+        }
+
+        world.write_resource::<InputMap>().target_next = true;
+        system.run_now(&world);
+        assert_eq!(world.read_resource::<Target>().0, Some(enemy));
+    }
+}
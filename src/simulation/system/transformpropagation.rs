@@ -0,0 +1,136 @@
+use specs::prelude::*;
+
+use crate::simulation::component::{
+    LocalTransform,
+    Parent,
+    Position,
+    Rotation,
+};
+
+/**
+ * Moves entities with a `Parent` to where their `LocalTransform` puts them
+ * relative to it. Parents are placed before their children, so chains of
+ * attached entities follow each other within a single pass.
+ */
+pub struct TransformPropagation;
+
+impl<'a> System<'a> for TransformPropagation {
+    type SystemData = (
+        Entities<'a>,
+        ReadStorage<'a, Parent>,
+        ReadStorage<'a, LocalTransform>,
+        WriteStorage<'a, Position>,
+        WriteStorage<'a, Rotation>,
+    );
+
+    fn run(&mut self, data: Self::SystemData) {
+        let (entities, parents, locals, mut positions, mut rotations) = data;
+
+        let count = (&parents, &locals).join().count();
+        let mut children = Vec::with_capacity(count);
+        'children: for (ent, parent, _) in (&entities, &parents, &locals).join() {
+            // the number of ancestors, walking up to the root
+            let mut depth = 0;
+            let mut ancestor = parent.0;
+            loop {
+                if !entities.is_alive(ancestor) || ancestor == ent || depth > count {
+                    log::debug!("Entity {:?} has an invalid parent", ent);
+                    continue 'children;
+                }
+                depth += 1;
+                match parents.get(ancestor) {
+                    Some(parent) => ancestor = parent.0,
+                    None => break,
+                }
+            }
+            children.push((depth, ent, parent.0));
+        }
+        children.sort();
+
+        for (_, ent, parent) in children {
+            let parent_pos = match positions.get(parent) {
+                Some(pos) => pos.0,
+                None => continue,
+            };
+            let parent_rot = match rotations.get(parent) {
+                Some(rot) => rot.0,
+                None => nalgebra::UnitQuaternion::identity(),
+            };
+
+            let local = locals.get(ent).unwrap();
+            let position = Position(parent_pos + parent_rot * local.translation);
+            let rotation = Rotation(parent_rot * local.rotation);
+
+            positions.insert(ent, position).unwrap();
+            rotations.insert(ent, rotation).unwrap();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn build_world() -> World {
+        let mut world = World::new();
+        System::setup(&mut TransformPropagation, &mut world);
+        world
+    }
+
+    fn attach(world: &mut World, parent: Entity, offset: nalgebra::Vector3<f64>) -> Entity {
+        world.create_entity()
+            .with(Parent(parent))
+            .with(LocalTransform::new(offset))
+            .build()
+    }
+
+    #[test]
+    fn test_propagation() {
+        let mut world = build_world();
+        let root = world.create_entity()
+            .with(Position(nalgebra::Point3::new(10.0, 0.0, 0.0)))
+            .with(Rotation::from_yaw(std::f64::consts::FRAC_PI_2))
+            .build();
+
+        // created before its parent, to check the order they are placed in
+        let placeholder = world.create_entity().build();
+        let grandchild = attach(&mut world, placeholder, nalgebra::Vector3::new(0.0, -1.0, -1.0));
+        let child = attach(&mut world, root, nalgebra::Vector3::new(0.0, 0.0, -2.0));
+        world.write_storage::<Parent>().insert(grandchild, Parent(child)).unwrap();
+
+        TransformPropagation.run_now(&world);
+
+        // a quarter turn left turns -z into -x
+        let positions = world.read_storage::<Position>();
+        let child_pos = positions.get(child).unwrap().0;
+        assert!((child_pos - nalgebra::Point3::new(8.0, 0.0, 0.0)).norm() < 1e-9, "{}", child_pos);
+        let grandchild_pos = positions.get(grandchild).unwrap().0;
+        assert!((grandchild_pos - nalgebra::Point3::new(7.0, -1.0, 0.0)).norm() < 1e-9, "{}", grandchild_pos);
+
+        let rotations = world.read_storage::<Rotation>();
+        let forward = rotations.get(grandchild).unwrap().0 * -nalgebra::Vector3::z();
+        assert!((forward + nalgebra::Vector3::x()).norm() < 1e-9, "{}", forward);
+    }
+
+    #[test]
+    fn test_invalid_parents() {
+        let mut world = build_world();
+        let placeholder = world.create_entity().build();
+        let a = attach(&mut world, placeholder, nalgebra::Vector3::new(1.0, 0.0, 0.0));
+        let b = attach(&mut world, a, nalgebra::Vector3::new(1.0, 0.0, 0.0));
+        world.write_storage::<Parent>().insert(a, Parent(b)).unwrap();
+
+        let removed = world.create_entity()
+            .with(Position(nalgebra::Point3::new(0.0, 0.0, 0.0)))
+            .build();
+        let orphan = attach(&mut world, removed, nalgebra::Vector3::new(1.0, 0.0, 0.0));
+        world.delete_entity(removed).unwrap();
+
+        TransformPropagation.run_now(&world);
+
+        let positions = world.read_storage::<Position>();
+        assert!(positions.get(a).is_none());
+        assert!(positions.get(b).is_none());
+        assert!(positions.get(orphan).is_none());
+    }
+}
@@ -34,6 +34,8 @@ impl<'a> System<'a> for UpdateInputs {
                             mouse_euler.pitch = data.pitch;
                             mouse_euler.yaw = data.yaw;
                         },
+                        // handled by targeting
+                        InputEvent::Select { .. } => (),
                     };
                 },
                 _ => (),
@@ -7,19 +7,24 @@ use eternalreckoning_core::simulation::TickTime;
 
 use super::super::{
     component::{
+        Collider,
         Model,
         Position,
+        Rotation,
         ServerID,
         Terrain,
         Texture,
+        Water,
     },
     event::{
         Update,
         PositionUpdate,
+        RotationUpdate,
         CameraUpdate,
         ModelUpdate,
         TerrainUpdate,
         TextureUpdate,
+        WaterUpdate,
     },
     resource::{
         ActiveCamera,
@@ -58,8 +63,11 @@ impl<'a> System<'a> for UpdateSender {
         ReadStorage<'a, Model>,
         ReadStorage<'a, Terrain>,
         ReadStorage<'a, Position>,
+        ReadStorage<'a, Rotation>,
         ReadStorage<'a, ServerID>,
         ReadStorage<'a, Texture>,
+        ReadStorage<'a, Water>,
+        ReadStorage<'a, Collider>,
     );
 
     fn run(&mut self, data: Self::SystemData) {
@@ -71,8 +79,11 @@ impl<'a> System<'a> for UpdateSender {
             model,
             terrain,
             pos,
+            rot,
             id,
-            texture
+            texture,
+            water,
+            collider,
         ) = data;
 
         // TODO: main loop sender hangup should be fatal
@@ -109,6 +120,19 @@ impl<'a> System<'a> for UpdateSender {
             }
         }
 
+        // rotations are for the display only: ClMoveSetPosition has no
+        // orientation in protocol v0.2.1, so the server is not told where
+        // the character faces
+        for (ent, rot) in (&entities, &rot).join() {
+            self.send_event(Update::RotationUpdate(
+                RotationUpdate {
+                    entity: ent,
+                    uuid: id.get(ent).map(|uuid| uuid.0),
+                    rotation: rot.0,
+                }
+            ));
+        }
+
         for (ent, model) in (&entities, &model).join() {
             self.send_event(Update::ModelUpdate(
                 ModelUpdate {
@@ -138,5 +162,29 @@ impl<'a> System<'a> for UpdateSender {
                 }
             ));
         }
+
+        for (ent, water, pos, collider) in (&entities, &water, &pos, &collider).join() {
+            let bounds = collider.collider.bounds(&pos.0);
+            if !bounds.is_finite() {
+                continue;
+            }
+
+            let surface = water.surface - pos.0.y;
+            self.send_event(Update::WaterUpdate(
+                WaterUpdate {
+                    entity: ent,
+                    min: nalgebra::convert(nalgebra::Point3::new(
+                        bounds.min.x - pos.0.x,
+                        surface,
+                        bounds.min.z - pos.0.z,
+                    )),
+                    max: nalgebra::convert(nalgebra::Point3::new(
+                        bounds.max.x - pos.0.x,
+                        surface,
+                        bounds.max.z - pos.0.z,
+                    )),
+                }
+            ));
+        }
     }
 }
\ No newline at end of file
@@ -1,41 +1,76 @@
+use std::sync::mpsc::Sender;
+
 use specs::prelude::*;
 
 use eternalreckoning_core::net::operation;
 
 use crate::simulation::{
     event::{
+        AliveUpdate,
+        DamageEvent,
+        DamageSource,
         Event,
         ConnectionEvent,
+        Update,
     },
     component::{
-        Model,
-        Texture,
+        Dead,
         Health,
         Position,
         ServerID,
     },
     resource::{
+        ActiveCamera,
         ActiveCharacter,
+        DamageQueue,
         EventQueue,
+        ServerPrefab,
     },
 };
 
-pub struct UpdateWorld;
+/**
+ * Applies the server's world updates. Lost health is passed on as damage,
+ * and entities the server gives health back to while dead come back alive.
+ */
+pub struct UpdateWorld {
+    sender: Sender<Update>,
+}
+
+impl UpdateWorld {
+    pub fn new(sender: Sender<Update>) -> UpdateWorld {
+        UpdateWorld { sender }
+    }
+}
 
 impl<'a> System<'a> for UpdateWorld {
     type SystemData = (
         Entities<'a>,
         Read<'a, EventQueue>,
+        Read<'a, ActiveCamera>,
         Read<'a, ActiveCharacter>,
+        Read<'a, ServerPrefab>,
+        Read<'a, LazyUpdate>,
+        Write<'a, DamageQueue>,
         WriteStorage<'a, ServerID>,
-        WriteStorage<'a, Model>,
-        WriteStorage<'a, Texture>,
+        WriteStorage<'a, Dead>,
         WriteStorage<'a, Health>,
         WriteStorage<'a, Position>,
     );
 
     fn run(&mut self, data: Self::SystemData) {
-        let (entities, events, character, mut id, mut model, mut texture, mut hp, mut pos) = data;
+        let (
+            entities,
+            events,
+            camera,
+            character,
+            prefab,
+            lazy,
+            mut damage,
+            mut id,
+            mut dead,
+            mut hp,
+            mut pos,
+        ) = data;
 
         for event in &*events {
             match event {
@@ -74,8 +109,11 @@ impl<'a> System<'a> for UpdateWorld {
                                     log::debug!("New entity: {}", update.uuid);
                                     entity = Some(entities.create());
                                     id.insert(entity.unwrap(), ServerID(update.uuid)).unwrap();
-                                    model.insert(entity.unwrap(), Model::new("assets/marker.erm")).unwrap();
-                                    texture.insert(entity.unwrap(), Texture::new("assets/marker.png")).unwrap();
+
+                                    match prefab.0 {
+                                        Some(ref prefab) => prefab.instantiate(entity.unwrap(), &lazy),
+                                        None => log::warn!("No prefab for new entity: {}", update.uuid),
+                                    };
                                 }
                                 let entity = entity.unwrap();
 
@@ -83,15 +121,42 @@ impl<'a> System<'a> for UpdateWorld {
                                     match component {
                                         operation::EntityComponent::Health(data) => {
                                             match hp.get_mut(entity) {
-                                                Some(ref mut health) => health.0 = *data,
+                                                // lost health goes through the damage
+                                                // pipeline, to be reacted to
+                                                Some(ref health) if *data < health.current => {
+                                                    damage.push(DamageEvent {
+                                                        target: entity,
+                                                        amount: health.current - *data,
+                                                        source: DamageSource::Server,
+                                                    });
+                                                },
+                                                Some(ref mut health) => {
+                                                    health.current = *data;
+                                                    health.max = health.max.max(*data);
+                                                },
                                                 None => {
-                                                    hp.insert(entity, Health(*data)).unwrap();
+                                                    hp.insert(entity, Health::new(*data)).unwrap();
                                                 },
                                             }
+
+                                            if *data > 0 && dead.remove(entity).is_some() {
+                                                log::debug!("Entity {:?} brought back by the server", entity);
+                                                self.sender.send(Update::AliveUpdate(AliveUpdate {
+                                                    entity,
+                                                    camera: camera.0 == Some(entity),
+                                                })).unwrap_or_else(|err| {
+                                                    log::error!("failed to send update event: {}", err);
+                                                });
+                                            }
                                         },
                                         operation::EntityComponent::Position(data) => {
                                             match pos.get_mut(entity) {
-                                                Some(ref mut position) => position.0 = *data,
+                                                // SvUpdateWorld has no orientation in
+                                                // protocol v0.2.1, remote entities keep
+                                                // their rotation until it does
+                                                Some(ref mut position) => {
+                                                    position.0 = *data;
+                                                },
                                                 None => {
                                                     pos.insert(entity, Position(*data)).unwrap();
                                                 },
@@ -104,8 +169,59 @@ impl<'a> System<'a> for UpdateWorld {
                         _ => (),
                     };
                 },
+                Event::ConfigEvent(_) => (),
                 Event::InputEvent(_) => (),
             }
         }
     }
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_brought_back_by_server() {
+        let (tx, rx) = std::sync::mpsc::channel();
+        let mut system = UpdateWorld::new(tx);
+
+        let mut world = World::new();
+        System::setup(&mut system, &mut world);
+        let uuid = uuid::Uuid::nil();
+        let player = world.create_entity()
+            .with(ServerID(uuid))
+            .with(Health { current: 0, max: 100 })
+            .with(Dead)
+            .build();
+        world.insert(ActiveCamera(Some(player)));
+
+        let mut update = |world: &mut World, health: u64| {
+            *world.write_resource::<EventQueue>() = vec![Event::NetworkEvent(
+                operation::Operation::SvUpdateWorld(operation::SvUpdateWorld {
+                    updates: vec![operation::Update {
+                        uuid,
+                        data: vec![operation::EntityComponent::Health(health)],
+                    }],
+                })
+            )];
+            system.run_now(world);
+        };
+
+        update(&mut world, 0);
+        assert!(world.read_storage::<Dead>().contains(player));
+        assert_eq!(rx.try_iter().count(), 0);
+
+        update(&mut world, 100);
+        assert!(!world.read_storage::<Dead>().contains(player));
+        assert_eq!(world.read_storage::<Health>().get(player).unwrap().current, 100);
+
+        let updates = rx.try_iter().collect::<Vec<_>>();
+        match &updates[..] {
+            [Update::AliveUpdate(alive)] => {
+                assert_eq!(alive.entity, player);
+                assert!(alive.camera);
+            },
+            _ => panic!("expected an alive update"),
+        }
+    }
+}
@@ -0,0 +1,565 @@
+use std::sync::mpsc::Sender;
+
+use futures::sync::mpsc::UnboundedSender;
+use specs::prelude::*;
+
+use crate::input::MouseEuler;
+use crate::simulation::{
+    component::{
+        Abilities,
+        Cast,
+        Dead,
+        Position,
+        Rotation,
+        ServerID,
+    },
+    event::{
+        AbilityRequest,
+        AbilityState,
+        AbilityUpdate,
+        Update,
+    },
+    resource::{
+        Ability,
+        AbilityRegistry,
+        ActiveCharacter,
+        InputMap,
+        ProjectileData,
+        Target,
+        TargetRequirement,
+        TickLength,
+    },
+};
+
+/**
+ * Times an ability request is sent, REQUEST_RESEND_INTERVAL seconds apart.
+ * The server does not acknowledge requests, so each is resent a fixed
+ * number of times for one of them to make it through.
+ */
+const REQUEST_SENDS: u32 = 3;
+const REQUEST_RESEND_INTERVAL: f64 = 0.25;
+
+struct PendingRequest {
+    request: AbilityRequest,
+    sends: u32,
+    next_send: f64,
+}
+
+/**
+ * Counts down ability cooldowns and casts, and uses the active character's
+ * abilities as their hotbar keys are pressed. Abilities take effect once
+ * cast, or right away without a cast time: the server is asked to carry
+ * them out, and projectiles are launched right away to hide the round trip.
+ * Casts end without effect when the caster dies.
+ */
+pub struct UseAbilities {
+    sender: Sender<Update>,
+    net_sender: Option<UnboundedSender<Update>>,
+    held: Vec<bool>,
+    requests: Vec<PendingRequest>,
+    next_sequence: u64,
+}
+
+impl UseAbilities {
+    pub fn new(sender: Sender<Update>, net_sender: UnboundedSender<Update>) -> UseAbilities {
+        UseAbilities {
+            sender,
+            net_sender: Some(net_sender),
+            held: Vec::new(),
+            requests: Vec::new(),
+            next_sequence: 0,
+        }
+    }
+
+    fn send_event(&self, event: Update) {
+        self.sender.send(event).unwrap_or_else(|err| {
+            log::error!("failed to send update event: {}", err);
+        });
+    }
+
+    fn queue_request(&mut self, request: AbilityRequest) {
+        self.requests.push(PendingRequest {
+            request,
+            sends: 0,
+            next_send: 0.0,
+        });
+        self.next_sequence += 1;
+    }
+
+    fn send_requests(&mut self) {
+        for pending in &mut self.requests {
+            if pending.next_send > 0.0 {
+                continue;
+            }
+            pending.sends += 1;
+            pending.next_send = REQUEST_RESEND_INTERVAL;
+
+            let sent = self.net_sender.as_ref()
+                .map(|net_sender| net_sender.unbounded_send(Update::AbilityRequest(pending.request.clone())));
+            if let Some(Err(err)) = sent {
+                log::error!("failed to send update event: {}", err);
+                self.net_sender = None;
+            }
+        }
+        self.requests.retain(|pending| pending.sends < REQUEST_SENDS);
+    }
+}
+
+impl<'a> System<'a> for UseAbilities {
+    type SystemData = (
+        Entities<'a>,
+        Read<'a, LazyUpdate>,
+        Read<'a, MouseEuler>,
+        Read<'a, ActiveCharacter>,
+        Read<'a, AbilityRegistry>,
+        Read<'a, InputMap>,
+        Read<'a, Target>,
+        Read<'a, TickLength>,
+        ReadStorage<'a, Dead>,
+        ReadStorage<'a, Position>,
+        ReadStorage<'a, ServerID>,
+        WriteStorage<'a, Abilities>,
+    );
+
+    fn run(&mut self, data: Self::SystemData) {
+        let (
+            entities,
+            lazy,
+            mouse_euler,
+            character,
+            registry,
+            input,
+            target,
+            tick_length,
+            dead,
+            positions,
+            ids,
+            mut abilities,
+        ) = data;
+
+        let dt = tick_length.seconds();
+
+        for pending in &mut self.requests {
+            pending.next_send -= dt;
+        }
+
+        // abilities taking effect this tick, with their caster and target
+        let mut used = Vec::new();
+
+        for (ent, abilities) in (&entities, &mut abilities).join() {
+            for slot in &mut abilities.slots {
+                slot.cooldown = (slot.cooldown - dt).max(0.0);
+            }
+
+            if dead.contains(ent) {
+                if let Some(cast) = abilities.casting.take() {
+                    log::debug!("Cast of {} interrupted by death", cast.ability);
+                }
+                continue;
+            }
+
+            let cast_done = abilities.casting.as_mut()
+                .map(|cast| {
+                    cast.remaining -= dt;
+                    cast.remaining <= 0.0
+                })
+                .unwrap_or(false);
+            if cast_done {
+                if let Some(cast) = abilities.casting.take() {
+                    used.push((ent, cast.ability, cast.target));
+                }
+            }
+        }
+
+        let pressed = input.abilities.iter()
+            .enumerate()
+            .filter(|(slot, held)| **held && !self.held.get(*slot).cloned().unwrap_or(false))
+            .map(|(slot, _)| slot)
+            .collect::<Vec<_>>();
+        self.held = input.abilities.clone();
+
+        if let Some(ent) = character.0.filter(|ent| !dead.contains(*ent)) {
+            if let Some(character_abilities) = abilities.get_mut(ent) {
+                let target = target.0;
+                for slot in pressed {
+                    let ability = activate(character_abilities, slot, target, &registry, |ability| {
+                        check_target(ent, target, ability, &positions, &dead)
+                    });
+                    if let Some(ability) = ability {
+                        used.push((ent, ability, target));
+                    }
+                }
+            }
+        }
+
+        for (caster, ability, target) in used {
+            let projectile = registry.get(&ability).and_then(|ability| ability.projectile.as_ref());
+            let origin = positions.get(caster);
+            if let (Some(projectile), Some(origin)) = (projectile, origin) {
+                // towards the target, or along the view without one
+                let direction = target
+                    .and_then(|target| positions.get(target))
+                    .and_then(|to| nalgebra::Unit::try_new(to.0 - origin.0, 0.001))
+                    .unwrap_or_else(|| nalgebra::Unit::new_normalize(
+                        nalgebra::Rotation3::from_euler_angles(
+                            mouse_euler.pitch,
+                            mouse_euler.yaw,
+                            0.0,
+                        ) * -nalgebra::Vector3::z()
+                    ));
+                launch(&entities, &lazy, projectile, caster, origin, &direction);
+            }
+
+            let sequence = self.next_sequence;
+            self.queue_request(AbilityRequest {
+                sequence,
+                entity: caster,
+                uuid: ids.get(caster).map(|id| id.0),
+                ability,
+                target,
+                target_uuid: target.and_then(|target| ids.get(target)).map(|id| id.0),
+            });
+        }
+        self.send_requests();
+
+        let ent = match character.0 {
+            Some(ent) => ent,
+            None => return,
+        };
+        let character_abilities = match abilities.get(ent) {
+            Some(abilities) => abilities,
+            None => return,
+        };
+        let states = character_abilities.slots.iter()
+            .filter_map(|slot| {
+                registry.get(&slot.ability).map(|ability| AbilityState {
+                    id: slot.ability.clone(),
+                    name: ability.name.clone(),
+                    icon: ability.icon.clone(),
+                    cooldown: ability.cooldown,
+                    remaining: slot.cooldown,
+                })
+            })
+            .collect();
+        self.send_event(Update::AbilityUpdate(AbilityUpdate {
+            entity: ent,
+            abilities: states,
+        }));
+    }
+}
+
+/**
+ * Starts the cooldown of the ability in 'slot' if it is ready and
+ * 'check_target' accepts the target. Returns the ability's id if it takes
+ * effect right away, abilities with a cast time are cast at 'target' first.
+ */
+fn activate<F>(
+    abilities: &mut Abilities,
+    slot: usize,
+    target: Option<Entity>,
+    registry: &AbilityRegistry,
+    check_target: F,
+) -> Option<String>
+where
+    F: FnOnce(&Ability) -> Result<(), &'static str>,
+{
+    if abilities.casting.is_some() {
+        log::debug!("Already casting, ignoring hotbar slot {}", slot);
+        return None;
+    }
+
+    let state = abilities.slots.get_mut(slot)?;
+    let ability = match registry.get(&state.ability) {
+        Some(ability) => ability,
+        None => {
+            log::warn!("Unknown ability {} in hotbar slot {}", state.ability, slot);
+            return None;
+        },
+    };
+
+    if state.cooldown > 0.0 {
+        log::debug!("{} is on cooldown for {:.1}s", ability.name, state.cooldown);
+        return None;
+    }
+    if let Err(reason) = check_target(ability) {
+        log::debug!("Cannot use {}: {}", ability.name, reason);
+        return None;
+    }
+
+    log::debug!("Using {}", ability.name);
+    state.cooldown = ability.cooldown;
+    let id = state.ability.clone();
+
+    if ability.cast_time > 0.0 {
+        abilities.casting = Some(Cast {
+            slot,
+            ability: id,
+            target,
+            remaining: ability.cast_time,
+        });
+        return None;
+    }
+
+    Some(id)
+}
+
+fn launch(
+    entities: &Entities,
+    lazy: &LazyUpdate,
+    data: &ProjectileData,
+    owner: Entity,
+    origin: &Position,
+    direction: &nalgebra::Unit<nalgebra::Vector3<f64>>,
+) {
+    let rotation = nalgebra::UnitQuaternion::rotation_between(&-nalgebra::Vector3::z(), direction)
+        .unwrap_or_else(nalgebra::UnitQuaternion::identity);
+
+    let mut builder = lazy.create_entity(entities)
+        .with(Position(origin.0))
+        .with(Rotation(rotation))
+        .with(data.to_component(Some(owner), direction));
+    if let Some(ref model) = data.model {
+        builder = builder.with(model.to_component());
+    }
+    if let Some(ref texture) = data.texture {
+        builder = builder.with(texture.to_component());
+    }
+    builder.build();
+}
+
+fn check_target(
+    caster: Entity,
+    target: Option<Entity>,
+    ability: &Ability,
+    positions: &ReadStorage<Position>,
+    dead: &ReadStorage<Dead>,
+) -> Result<(), &'static str> {
+    let target = match (ability.target, target) {
+        (TargetRequirement::None, _) => return Ok(()),
+        (TargetRequirement::Entity, Some(target)) => target,
+        (TargetRequirement::Entity, None) => return Err("needs a target"),
+    };
+    if dead.contains(target) {
+        return Err("target is dead");
+    }
+
+    if let Some(range) = ability.range {
+        match (positions.get(caster), positions.get(target)) {
+            (Some(from), Some(to)) if nalgebra::distance(&from.0, &to.0) <= range => (),
+            _ => return Err("target out of range"),
+        };
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::simulation::component::Projectile;
+
+    fn build_world() -> World {
+        let registry: AbilityRegistry = toml::from_str(r#"
+            [ability.attack]
+            name = "Attack"
+            icon = "assets/icon_attack.png"
+            cooldown = 1.0
+            range = 3.0
+            target = "entity"
+
+            [ability.heal]
+            name = "Heal"
+            icon = "assets/icon_heal.png"
+            cooldown = 0.5
+            cast-time = 0.25
+
+            [ability.spear]
+            name = "Throw Spear"
+            icon = "assets/icon_spear.png"
+            projectile = { speed = 20.0, gravity = 1.0 }
+        "#).unwrap();
+
+        let mut world = World::new();
+        let (tx, _) = std::sync::mpsc::channel();
+        let (net_tx, _) = futures::sync::mpsc::unbounded();
+        System::setup(&mut UseAbilities::new(tx, net_tx), &mut world);
+        // projectiles are created lazily
+        world.register::<Projectile>();
+        world.register::<Rotation>();
+        world.insert(registry);
+        world.insert(TickLength(std::time::Duration::from_secs_f64(0.1)));
+        world
+    }
+
+    fn press(world: &mut World, system: &mut UseAbilities, slot: usize) {
+        let mut input = world.write_resource::<InputMap>();
+        input.set(crate::input::InputTypes::Ability(slot), true);
+        drop(input);
+        system.run_now(world);
+
+        let mut input = world.write_resource::<InputMap>();
+        input.set(crate::input::InputTypes::Ability(slot), false);
+    }
+
+    #[test]
+    fn test_cooldown_and_cast() {
+        let mut world = build_world();
+        let player = world.create_entity()
+            .with(Abilities::new(&["attack".to_string(), "heal".to_string()]))
+            .build();
+        world.insert(ActiveCharacter(Some(player)));
+
+        let (tx, rx) = std::sync::mpsc::channel();
+        let (net_tx, net_rx) = futures::sync::mpsc::unbounded();
+        let mut system = UseAbilities::new(tx, net_tx);
+
+        // attacking needs a target, healing does not
+        press(&mut world, &mut system, 0);
+        press(&mut world, &mut system, 1);
+        {
+            let abilities = world.read_storage::<Abilities>();
+            let abilities = abilities.get(player).unwrap();
+            assert_eq!(abilities.slots[0].cooldown, 0.0);
+            assert_eq!(abilities.slots[1].cooldown, 0.5);
+            assert_eq!(abilities.casting.as_ref().unwrap().slot, 1);
+        }
+
+        // on cooldown, and the cast ends a few ticks later
+        press(&mut world, &mut system, 1);
+        for _ in 0..3 {
+            system.run_now(&world);
+        }
+        {
+            let abilities = world.read_storage::<Abilities>();
+            let abilities = abilities.get(player).unwrap();
+            assert!(abilities.casting.is_none());
+            assert!(abilities.slots[1].cooldown > 0.0);
+        }
+
+        for _ in 0..2 {
+            system.run_now(&world);
+        }
+        press(&mut world, &mut system, 1);
+
+        let updates = rx.try_iter().collect::<Vec<_>>();
+        assert_eq!(updates.len(), 9);
+        match updates.last() {
+            Some(Update::AbilityUpdate(update)) => {
+                assert_eq!(update.abilities.len(), 2);
+                assert_eq!(update.abilities[1].icon, "assets/icon_heal.png");
+                assert_eq!(update.abilities[1].remaining, 0.5);
+            },
+            _ => panic!("expected an ability update"),
+        }
+
+        // the first heal took effect once cast, and was resent once since,
+        // the second one is still being cast
+        drop(system);
+        let requests = futures::Future::wait(futures::Stream::collect(net_rx)).unwrap();
+        assert_eq!(requests.len(), 2);
+        for request in &requests {
+            match request {
+                Update::AbilityRequest(request) => {
+                    assert_eq!(request.sequence, 0);
+                    assert_eq!(request.ability, "heal");
+                },
+                _ => panic!("expected an ability request"),
+            }
+        }
+    }
+
+    #[test]
+    fn test_death_interrupts_cast() {
+        let mut world = build_world();
+        let player = world.create_entity()
+            .with(Abilities::new(&["heal".to_string()]))
+            .build();
+        world.insert(ActiveCharacter(Some(player)));
+
+        let (tx, _rx) = std::sync::mpsc::channel();
+        let (net_tx, net_rx) = futures::sync::mpsc::unbounded();
+        let mut system = UseAbilities::new(tx, net_tx);
+
+        press(&mut world, &mut system, 0);
+        assert!(world.read_storage::<Abilities>().get(player).unwrap().casting.is_some());
+
+        world.write_storage::<Dead>().insert(player, Dead).unwrap();
+        for _ in 0..5 {
+            system.run_now(&world);
+        }
+        assert!(world.read_storage::<Abilities>().get(player).unwrap().casting.is_none());
+
+        drop(system);
+        let requests = futures::Future::wait(futures::Stream::collect(net_rx)).unwrap();
+        assert!(requests.is_empty());
+    }
+
+    #[test]
+    fn test_dead_cannot_cast() {
+        let mut world = build_world();
+        let player = world.create_entity()
+            .with(Abilities::new(&["heal".to_string()]))
+            .with(Dead)
+            .build();
+        world.insert(ActiveCharacter(Some(player)));
+
+        let (tx, _rx) = std::sync::mpsc::channel();
+        let (net_tx, _net_rx) = futures::sync::mpsc::unbounded();
+        let mut system = UseAbilities::new(tx, net_tx);
+
+        press(&mut world, &mut system, 0);
+        let abilities = world.read_storage::<Abilities>();
+        assert_eq!(abilities.get(player).unwrap().slots[0].cooldown, 0.0);
+    }
+
+    #[test]
+    fn test_launch_projectile() {
+        let mut world = build_world();
+        let player = world.create_entity()
+            .with(Abilities::new(&["spear".to_string()]))
+            .with(Position(nalgebra::Point3::new(1.0, 0.0, 0.0)))
+            .build();
+        world.insert(ActiveCharacter(Some(player)));
+
+        let (tx, _rx) = std::sync::mpsc::channel();
+        let (net_tx, _net_rx) = futures::sync::mpsc::unbounded();
+        let mut system = UseAbilities::new(tx, net_tx);
+
+        press(&mut world, &mut system, 0);
+        world.maintain();
+
+        let positions = world.read_storage::<Position>();
+        let projectiles = world.read_storage::<Projectile>();
+        let (position, projectile) = (&positions, &projectiles).join().next().unwrap();
+        assert_eq!(position.0, nalgebra::Point3::new(1.0, 0.0, 0.0));
+        assert_eq!(projectile.owner, Some(player));
+        assert!((projectile.velocity - nalgebra::Vector3::new(0.0, 0.0, -20.0)).norm() < 1e-9);
+    }
+
+    #[test]
+    fn test_target_range() {
+        let world = build_world();
+        let caster = world.entities().create();
+        let near = world.entities().create();
+        let far = world.entities().create();
+        {
+            let mut positions = world.write_storage::<Position>();
+            positions.insert(caster, Position(nalgebra::Point3::origin())).unwrap();
+            positions.insert(near, Position(nalgebra::Point3::new(2.0, 0.0, 0.0))).unwrap();
+            positions.insert(far, Position(nalgebra::Point3::new(5.0, 0.0, 0.0))).unwrap();
+        }
+
+        let registry = world.read_resource::<AbilityRegistry>();
+        let attack = registry.get("attack").unwrap();
+        let positions = world.read_storage::<Position>();
+        let dead = world.read_storage::<Dead>();
+        assert!(check_target(caster, Some(near), attack, &positions, &dead).is_ok());
+        assert!(check_target(caster, Some(far), attack, &positions, &dead).is_err());
+        assert!(check_target(caster, None, attack, &positions, &dead).is_err());
+        drop(dead);
+
+        world.write_storage::<Dead>().insert(near, Dead).unwrap();
+        let dead = world.read_storage::<Dead>();
+        assert!(check_target(caster, Some(near), attack, &positions, &dead).is_err());
+    }
+}
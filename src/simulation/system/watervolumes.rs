@@ -0,0 +1,169 @@
+use specs::prelude::*;
+
+use crate::simulation::{
+    component::{
+        Collider,
+        Position,
+        Submersion,
+        Velocity,
+        Water,
+    },
+    event::TriggerEvent,
+    resource::TriggerQueue,
+};
+
+/**
+ * Works out how deep moving entities touching water volumes are in them,
+ * from the latest trigger contacts. Entities in more than one volume take
+ * the one they are deepest in.
+ */
+pub struct WaterVolumes;
+
+impl<'a> System<'a> for WaterVolumes {
+    type SystemData = (
+        Entities<'a>,
+        Read<'a, TriggerQueue>,
+        ReadStorage<'a, Water>,
+        ReadStorage<'a, Position>,
+        ReadStorage<'a, Collider>,
+        ReadStorage<'a, Velocity>,
+        WriteStorage<'a, Submersion>,
+    );
+
+    fn run(&mut self, data: Self::SystemData) {
+        let (entities, triggers, water, positions, colliders, velocities, mut submersion) = data;
+
+        submersion.clear();
+
+        for event in &*triggers {
+            let contact = match event {
+                TriggerEvent::Enter(contact) | TriggerEvent::Stay(contact) => contact,
+                TriggerEvent::Exit(_) => continue,
+            };
+
+            let water = match water.get(contact.trigger) {
+                Some(water) => water,
+                None => continue,
+            };
+            if !velocities.contains(contact.other) || !entities.is_alive(contact.other) {
+                continue;
+            }
+
+            let (pos, collider) = match (positions.get(contact.other), colliders.get(contact.other)) {
+                (Some(pos), Some(collider)) => (pos, collider),
+                _ => continue,
+            };
+
+            // up is -y, so the bounds' max is their bottom
+            let bounds = collider.collider.bounds(&pos.0);
+            if !bounds.is_finite() {
+                continue;
+            }
+            let height = bounds.max.y - bounds.min.y;
+            let depth = if height > 0.0 {
+                ((bounds.max.y - water.surface) / height).clamp(0.0, 1.0)
+            } else if bounds.max.y >= water.surface {
+                1.0
+            } else {
+                0.0
+            };
+
+            if matches!(submersion.get(contact.other), Some(current) if current.depth >= depth) {
+                continue;
+            }
+
+            submersion.insert(contact.other, Submersion {
+                depth,
+                buoyancy: water.buoyancy,
+                drag: water.drag,
+            }).unwrap();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::simulation::{
+        component::collider::ColliderType,
+        resource::TickLength,
+        system::{
+            CollisionDetection,
+            Physics,
+        },
+        PhysicsConfig,
+    };
+
+    fn build_world() -> World {
+        let config = PhysicsConfig::default();
+        let mut world = World::new();
+        System::setup(&mut Physics::new(&config), &mut world);
+        System::setup(&mut CollisionDetection::new(&config), &mut world);
+        System::setup(&mut WaterVolumes, &mut world);
+        world.insert(TickLength(std::time::Duration::from_secs_f64(1.0 / 60.0)));
+
+        // a pool 10m deep, with its surface at y = 0
+        let mut pool = Collider::new(ColliderType::Aabb(nalgebra::Vector3::new(10.0, 5.0, 10.0)));
+        pool.trigger = true;
+        world.create_entity()
+            .with(Position(nalgebra::Point3::new(0.0, 5.0, 0.0)))
+            .with(pool)
+            .with(Water { surface: 0.0, buoyancy: 1.5, drag: 3.0 })
+            .build();
+
+        world
+    }
+
+    fn add_ball(world: &mut World, y: f64) -> Entity {
+        world.create_entity()
+            .with(Position(nalgebra::Point3::new(0.0, y, 0.0)))
+            .with(Velocity(nalgebra::Vector3::zeros()))
+            .with(Collider::new(ColliderType::Sphere(0.5)))
+            .build()
+    }
+
+    fn simulate(world: &mut World, ticks: usize) {
+        let config = PhysicsConfig::default();
+        let mut physics = Physics::new(&config);
+        let mut detection = CollisionDetection::new(&config);
+
+        for _ in 0..ticks {
+            physics.run_now(world);
+            detection.run_now(world);
+            WaterVolumes.run_now(world);
+            world.maintain();
+        }
+    }
+
+    #[test]
+    fn test_submersion_depth() {
+        let mut world = build_world();
+        let half = add_ball(&mut world, 0.0);
+        let deep = add_ball(&mut world, 4.0);
+        let dry = add_ball(&mut world, -2.0);
+
+        let mut detection = CollisionDetection::new(&PhysicsConfig::default());
+        detection.run_now(&world);
+        WaterVolumes.run_now(&world);
+
+        let submersion = world.read_storage::<Submersion>();
+        assert!((submersion.get(half).unwrap().depth - 0.5).abs() < 1e-9);
+        assert_eq!(submersion.get(deep).unwrap().depth, 1.0);
+        assert_eq!(submersion.get(deep).unwrap().buoyancy, 1.5);
+        assert!(submersion.get(dry).is_none());
+    }
+
+    #[test]
+    fn test_floats_to_surface() {
+        let mut world = build_world();
+        let ball = add_ball(&mut world, 4.0);
+
+        simulate(&mut world, 600);
+
+        // settles where buoyancy matches gravity, two thirds under
+        let y = world.read_storage::<Position>().get(ball).unwrap().0.y;
+        assert!((y - 1.0 / 6.0).abs() < 0.05, "{}", y);
+        let velocity = world.read_storage::<Velocity>().get(ball).unwrap().0;
+        assert!(velocity.norm() < 0.1);
+    }
+}
@@ -5,7 +5,10 @@ use eternalreckoning_core::util::logging::LoggingConfig;
 use crate::client::ClientConfig;
 use crate::input::MouseConfig;
 use crate::input::KeyMapConfig;
-use crate::simulation::SimulationConfig;
+use crate::simulation::{
+    event::ConfigEvent,
+    SimulationConfig,
+};
 use crate::display::DisplayConfig;
 
 #[derive(Serialize, Deserialize)]
@@ -30,4 +33,27 @@ impl Default for Config {
             simulation: SimulationConfig::default(),
         }
     }
-}
\ No newline at end of file
+}
+
+impl Config {
+    /**
+     * Keeps the settings in line with a change applied at runtime, so that
+     * it is saved on exit
+     */
+    pub fn apply(&mut self, event: &ConfigEvent) {
+        match event {
+            ConfigEvent::Physics(physics) => {
+                self.simulation.physics = physics.clone();
+            },
+            ConfigEvent::MovementSpeed(speed) => {
+                self.simulation.movement_speed = *speed;
+            },
+            ConfigEvent::MouseSensitivity(sensitivity) => {
+                self.mouse.sensitivity = *sensitivity;
+            },
+            ConfigEvent::ForwardInterpolate(value) => {
+                self.display.forward_interpolate = *value;
+            },
+        };
+    }
+}
@@ -0,0 +1,394 @@
+use std::path::{Path, PathBuf};
+
+use failure::{
+    Error,
+    format_err,
+};
+use toml::{
+    value::Table,
+    Value,
+};
+
+use super::config::Config;
+
+/**
+ * Builds the effective client configuration by merging layers on top of
+ * each other, later layers taking precedence:
+ *
+ * built-in defaults < config files (in order added) < environment < overrides
+ *
+ * Missing files are skipped, malformed ones are reported and never written to.
+ */
+#[derive(Clone)]
+pub struct ConfigLoader {
+    files: Vec<PathBuf>,
+    user_file: Option<PathBuf>,
+    env_prefix: Option<String>,
+    overrides: Vec<String>,
+}
+
+pub struct LoadedConfig {
+    pub data: Config,
+    pub merged: Value,
+    pub loader: ConfigLoader,
+}
+
+impl ConfigLoader {
+    pub fn new() -> ConfigLoader {
+        ConfigLoader {
+            files: Vec::new(),
+            user_file: None,
+            env_prefix: None,
+            overrides: Vec::new(),
+        }
+    }
+
+    pub fn with_file<P: AsRef<Path>>(mut self, path: P) -> ConfigLoader {
+        self.files.push(path.as_ref().to_path_buf());
+        self
+    }
+
+    /**
+     * Adds a config file layer that settings changed at runtime are saved to
+     */
+    pub fn with_user_file<P: AsRef<Path>>(mut self, path: P) -> ConfigLoader {
+        self.user_file = Some(path.as_ref().to_path_buf());
+        self.with_file(path)
+    }
+
+    /**
+     * Environment variables are mapped to keys by stripping the prefix,
+     * splitting sections on double underscores and converting the rest to
+     * kebab-case, e.g. `ER_SIMULATION__PHYSICS__GRAVITY`.
+     */
+    pub fn with_env(mut self, prefix: &str) -> ConfigLoader {
+        self.env_prefix = Some(prefix.to_string());
+        self
+    }
+
+    /**
+     * Overrides are given as `section.key=value`, e.g. `client.tick-rate=30`
+     */
+    pub fn with_override(mut self, key_value: &str) -> ConfigLoader {
+        self.overrides.push(key_value.to_string());
+        self
+    }
+
+    pub fn files(&self) -> &[PathBuf] {
+        &self.files[..]
+    }
+
+    pub fn user_file(&self) -> Option<&Path> {
+        self.user_file.as_deref()
+    }
+
+    pub fn load(&self) -> Result<LoadedConfig, Error> {
+        let mut merged = Value::try_from(Config::default())?;
+
+        for path in &self.files {
+            if let Some(layer) = read_file(path)? {
+                validate(&layer)
+                    .map_err(|e| format_err!("invalid config file {}: {}", path.display(), e))?;
+                merge(&mut merged, layer);
+            }
+        }
+
+        if let Some(ref prefix) = self.env_prefix {
+            let layer = env_layer(prefix, std::env::vars())?;
+            merge(&mut merged, layer);
+            validate(&merged)
+                .map_err(|e| format_err!("invalid environment override: {}", e))?;
+        }
+
+        for key_value in &self.overrides {
+            let (key, value) = split_key_value(key_value)?;
+            set_key(&mut merged, key, parse_value(value))?;
+            validate(&merged)
+                .map_err(|e| format_err!("invalid override {}: {}", key_value, e))?;
+        }
+
+        let data = merged.clone().try_into::<Config>()?;
+
+        Ok(LoadedConfig {
+            data,
+            merged,
+            loader: self.clone(),
+        })
+    }
+}
+
+impl LoadedConfig {
+    /**
+     * Serializes the effective configuration, including defaults, as TOML
+     */
+    pub fn dump(&self) -> Result<String, Error> {
+        Ok(toml::to_string_pretty(&Value::try_from(&self.data)?)?)
+    }
+}
+
+fn read_file(path: &Path) -> Result<Option<Value>, Error> {
+    let contents = match std::fs::read_to_string(path) {
+        Ok(contents) => contents,
+        Err(ref e) if e.kind() == std::io::ErrorKind::NotFound => {
+            log::debug!("Config file not found, skipping: {}", path.display());
+            return Ok(None);
+        },
+        Err(e) => {
+            return Err(format_err!("unable to read config file {}: {}", path.display(), e));
+        },
+    };
+
+    let value = contents.parse::<Value>()
+        .map_err(|e| format_err!("malformed config file {}: {}", path.display(), e))?;
+
+    Ok(Some(value))
+}
+
+fn validate(value: &Value) -> Result<(), Error> {
+    value.clone().try_into::<Config>()?;
+    Ok(())
+}
+
+/**
+ * Recursively merges 'layer' into 'base', tables are merged key by key while
+ * any other value replaces the existing one
+ */
+pub fn merge(base: &mut Value, layer: Value) {
+    match (base, layer) {
+        (Value::Table(base), Value::Table(layer)) => {
+            for (key, value) in layer {
+                match base.get_mut(&key) {
+                    Some(existing) => merge(existing, value),
+                    None => {
+                        base.insert(key, value);
+                    },
+                }
+            }
+        },
+        (base, layer) => *base = layer,
+    }
+}
+
+/**
+ * Sets a dot-separated key path, creating intermediate tables as needed
+ */
+pub fn set_key(root: &mut Value, key: &str, value: Value) -> Result<(), Error> {
+    let mut parts: Vec<&str> = key.split('.').collect();
+    let last = parts.pop().unwrap();
+    if last.is_empty() {
+        return Err(format_err!("invalid config key: {}", key));
+    }
+
+    let mut table = root;
+    for part in parts {
+        let current = match table {
+            Value::Table(current) => current,
+            _ => return Err(format_err!("config key {} is not a section", key)),
+        };
+        table = current.entry(part.to_string())
+            .or_insert_with(|| Value::Table(Table::new()));
+    }
+
+    match table {
+        Value::Table(current) => {
+            current.insert(last.to_string(), value);
+            Ok(())
+        },
+        _ => Err(format_err!("config key {} is not a section", key)),
+    }
+}
+
+/**
+ * Value at a dot-separated key path, None if any part of it is missing
+ */
+pub fn get_key<'a>(root: &'a Value, key: &str) -> Option<&'a Value> {
+    let mut value = root;
+    for part in key.split('.') {
+        value = value.as_table()?.get(part)?;
+    }
+    Some(value)
+}
+
+/**
+ * Removes a dot-separated key path, if it exists
+ */
+pub fn remove_key(root: &mut Value, key: &str) {
+    let mut parts: Vec<&str> = key.split('.').collect();
+    let last = match parts.pop() {
+        Some(last) => last,
+        None => return,
+    };
+
+    let mut table = root;
+    for part in parts {
+        table = match table.as_table_mut().and_then(|table| table.get_mut(part)) {
+            Some(table) => table,
+            None => return,
+        };
+    }
+
+    if let Some(table) = table.as_table_mut() {
+        table.remove(last);
+    }
+}
+
+/**
+ * Values are parsed as TOML where possible, anything else is kept as a string
+ */
+pub fn parse_value(raw: &str) -> Value {
+    format!("value = {}", raw).parse::<Value>()
+        .ok()
+        .and_then(|mut value| {
+            value.as_table_mut().and_then(|table| table.remove("value"))
+        })
+        .unwrap_or_else(|| Value::String(raw.to_string()))
+}
+
+/**
+ * Lists the dot-separated keys whose values differ between 'old' and 'new'
+ */
+pub fn changed_keys(old: &Value, new: &Value) -> Vec<String> {
+    let mut keys = Vec::new();
+    collect_changed_keys(old, new, "", &mut keys);
+    keys
+}
+
+fn collect_changed_keys(old: &Value, new: &Value, prefix: &str, keys: &mut Vec<String>) {
+    match (old, new) {
+        (Value::Table(old), Value::Table(new)) => {
+            for (key, value) in new {
+                let path = join_key(prefix, key);
+                match old.get(key) {
+                    Some(old_value) => collect_changed_keys(old_value, value, &path, keys),
+                    None => keys.push(path),
+                }
+            }
+            for key in old.keys() {
+                if !new.contains_key(key) {
+                    keys.push(join_key(prefix, key));
+                }
+            }
+        },
+        (old, new) => {
+            if old != new {
+                keys.push(prefix.to_string());
+            }
+        },
+    }
+}
+
+fn join_key(prefix: &str, key: &str) -> String {
+    if prefix.is_empty() {
+        key.to_string()
+    } else {
+        format!("{}.{}", prefix, key)
+    }
+}
+
+fn split_key_value(key_value: &str) -> Result<(&str, &str), Error> {
+    let mut split = key_value.splitn(2, '=');
+    match (split.next(), split.next()) {
+        (Some(key), Some(value)) if !key.trim().is_empty() => {
+            Ok((key.trim(), value.trim()))
+        },
+        _ => Err(format_err!("expected key=value, got: {}", key_value)),
+    }
+}
+
+fn env_layer<I>(prefix: &str, vars: I) -> Result<Value, Error>
+where
+    I: Iterator<Item = (String, String)>,
+{
+    let mut layer = Value::Table(Table::new());
+
+    for (name, value) in vars {
+        if !name.starts_with(prefix) {
+            continue;
+        }
+
+        let key = name[prefix.len()..]
+            .split("__")
+            .map(|part| part.to_lowercase().replace('_', "-"))
+            .collect::<Vec<String>>()
+            .join(".");
+
+        set_key(&mut layer, &key, parse_value(&value))?;
+    }
+
+    Ok(layer)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_merge_layers() {
+        let mut base = Value::try_from(Config::default()).unwrap();
+
+        let layer = r#"
+            [client]
+            tick-rate = 30
+
+            [simulation.physics]
+            gravity = 9.81
+        "#.parse::<Value>().unwrap();
+        merge(&mut base, layer);
+
+        let config = base.try_into::<Config>().unwrap();
+        assert_eq!(config.client.tick_rate, 30);
+        assert_eq!(
+            config.client.server_address,
+            crate::client::ClientConfig::default().server_address
+        );
+        assert_eq!(config.simulation.physics.gravity, 9.81);
+        assert_eq!(config.simulation.physics.max_ground_slope, 0.2);
+    }
+
+    #[test]
+    fn test_env_layer() {
+        let vars = vec![
+            ("ER_CLIENT__TICK_RATE".to_string(), "128".to_string()),
+            ("ER_CLIENT__SERVER_ADDRESS".to_string(), "example.com:6142".to_string()),
+            ("ER_SIMULATION__PHYSICS__GRAVITY".to_string(), "1.5".to_string()),
+            ("PATH".to_string(), "/usr/bin".to_string()),
+        ];
+
+        let mut base = Value::try_from(Config::default()).unwrap();
+        merge(&mut base, env_layer("ER_", vars.into_iter()).unwrap());
+
+        let config = base.try_into::<Config>().unwrap();
+        assert_eq!(config.client.tick_rate, 128);
+        assert_eq!(&config.client.server_address[..], "example.com:6142");
+        assert_eq!(config.simulation.physics.gravity, 1.5);
+    }
+
+    #[test]
+    fn test_override_type_errors() {
+        let mut base = Value::try_from(Config::default()).unwrap();
+        set_key(&mut base, "client.tick-rate", parse_value("fast")).unwrap();
+        assert!(validate(&base).is_err());
+
+        assert!(split_key_value("client.tick-rate").is_err());
+        assert!(set_key(&mut base, "client.tick-rate.x", parse_value("1")).is_err());
+    }
+
+    #[test]
+    fn test_changed_keys() {
+        let old = Value::try_from(Config::default()).unwrap();
+        let mut new = old.clone();
+        set_key(&mut new, "mouse.sensitivity", parse_value("1.5")).unwrap();
+        set_key(&mut new, "simulation.physics.gravity", parse_value("9.81")).unwrap();
+        set_key(&mut new, "unknown.key", parse_value("true")).unwrap();
+
+        let mut keys = changed_keys(&old, &new);
+        keys.sort();
+        assert_eq!(keys, vec![
+            "mouse.sensitivity".to_string(),
+            "simulation.physics.gravity".to_string(),
+            "unknown".to_string(),
+        ]);
+
+        assert!(changed_keys(&new, &new).is_empty());
+    }
+}
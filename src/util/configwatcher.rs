@@ -0,0 +1,232 @@
+use std::collections::HashMap;
+use std::sync::mpsc::Sender;
+use std::time::{
+    Duration,
+    SystemTime,
+};
+
+use toml::Value;
+
+use crate::simulation::event::{
+    ConfigEvent,
+    Event,
+    Update,
+};
+use super::configloader::{
+    changed_keys,
+    get_key,
+    remove_key,
+    set_key,
+    ConfigLoader,
+};
+
+const POLL_INTERVAL: Duration = Duration::from_secs(1);
+
+/**
+ * Polls the configuration files for changes and forwards the settings that
+ * can be applied at runtime to the simulation and the main loop. The main
+ * loop is sent every change, which it leaves out of the settings it saves.
+ * Settings that need a restart keep their running values until then.
+ */
+pub struct ConfigWatcher {
+    loader: ConfigLoader,
+    current: Value,
+    /**
+     * Settings edited on disk that need a restart, with the values last
+     * warned about
+     */
+    pending: HashMap<String, Option<Value>>,
+    modified: Vec<Option<SystemTime>>,
+    event_tx: Sender<Event>,
+    update_tx: Sender<Update>,
+}
+
+impl ConfigWatcher {
+    pub fn new(
+        loader: ConfigLoader,
+        current: Value,
+        event_tx: Sender<Event>,
+        update_tx: Sender<Update>,
+    ) -> ConfigWatcher
+    {
+        let modified = modification_times(&loader);
+
+        ConfigWatcher {
+            loader,
+            current,
+            pending: HashMap::new(),
+            modified,
+            event_tx,
+            update_tx,
+        }
+    }
+
+    pub fn run(mut self) {
+        loop {
+            std::thread::sleep(POLL_INTERVAL);
+
+            let modified = modification_times(&self.loader);
+            if modified == self.modified {
+                continue;
+            }
+            self.modified = modified;
+
+            if self.reload().is_err() {
+                break;
+            }
+        }
+    }
+
+    fn reload(&mut self) -> Result<(), ()> {
+        let loaded = match self.loader.load() {
+            Ok(loaded) => loaded,
+            Err(e) => {
+                log::error!("Failed to reload configuration: {}", e);
+                return Ok(());
+            },
+        };
+
+        let mut next = loaded.merged.clone();
+        let mut physics_changed = false;
+        let mut restart_keys = Vec::new();
+        for key in changed_keys(&self.current, &loaded.merged) {
+            match &key[..] {
+                "simulation.movement-speed" => {
+                    let event = ConfigEvent::MovementSpeed(loaded.data.simulation.movement_speed);
+                    self.send_event(event.clone())?;
+                    self.send_update(event)?;
+                },
+                "mouse.sensitivity" => {
+                    self.send_update(ConfigEvent::MouseSensitivity(
+                        loaded.data.mouse.sensitivity
+                    ))?;
+                },
+                "display.forward-interpolate" => {
+                    self.send_update(ConfigEvent::ForwardInterpolate(
+                        loaded.data.display.forward_interpolate
+                    ))?;
+                },
+                key if key.starts_with("simulation.physics.") => {
+                    physics_changed = true;
+                },
+                key => {
+                    keep_running_value(&mut next, &self.current, key);
+
+                    let value = get_key(&loaded.merged, key).cloned();
+                    if self.pending.get(key) != Some(&value) {
+                        log::warn!("Config setting {} cannot be changed at runtime, restart to apply", key);
+                        self.pending.insert(key.to_string(), value);
+                    }
+                    restart_keys.push(key.to_string());
+                    continue;
+                },
+            };
+            log::info!("Config setting {} reloaded", key);
+        }
+
+        if physics_changed {
+            let event = ConfigEvent::Physics(loaded.data.simulation.physics.clone());
+            self.send_event(event.clone())?;
+            self.send_update(event)?;
+        }
+
+        // edits reverted to the running value no longer need a restart
+        self.pending.retain(|key, _| {
+            let pending = restart_keys.contains(key);
+            if !pending {
+                log::info!("Config setting {} is back to its running value", key);
+            }
+            pending
+        });
+
+        self.current = next;
+
+        Ok(())
+    }
+
+    /**
+     * Settings edited on disk that only apply after a restart
+     */
+    #[cfg(test)]
+    fn pending_restart(&self) -> Vec<&str> {
+        let mut keys = self.pending.keys().map(|key| &key[..]).collect::<Vec<_>>();
+        keys.sort();
+        keys
+    }
+
+    fn send_event(&self, event: ConfigEvent) -> Result<(), ()> {
+        self.event_tx.send(Event::ConfigEvent(event))
+            .map_err(|err| {
+                log::error!("failed to send config event: {}", err);
+            })
+    }
+
+    fn send_update(&self, event: ConfigEvent) -> Result<(), ()> {
+        self.update_tx.send(Update::ConfigUpdate(event))
+            .map_err(|err| {
+                log::error!("failed to send config update: {}", err);
+            })
+    }
+}
+
+fn keep_running_value(next: &mut Value, current: &Value, key: &str) {
+    match get_key(current, key) {
+        Some(value) => {
+            set_key(next, key, value.clone()).unwrap_or_else(|e| {
+                log::warn!("Failed to keep running value of {}: {}", key, e);
+            });
+        },
+        None => remove_key(next, key),
+    };
+}
+
+fn modification_times(loader: &ConfigLoader) -> Vec<Option<SystemTime>> {
+    loader.files()
+        .iter()
+        .map(|path| {
+            std::fs::metadata(path)
+                .and_then(|meta| meta.modified())
+                .ok()
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_restart_settings_keep_running_values() {
+        let path = std::env::temp_dir().join(format!(
+            "er-client-watch-{}.toml",
+            std::process::id()
+        ));
+        std::fs::write(&path, "[client]\ntick-rate = 60\n").unwrap();
+
+        let loader = ConfigLoader::new().with_file(&path);
+        let loaded = loader.load().unwrap();
+        let (event_tx, event_rx) = std::sync::mpsc::channel();
+        let (update_tx, update_rx) = std::sync::mpsc::channel();
+        let mut watcher = ConfigWatcher::new(loader, loaded.merged, event_tx, update_tx);
+
+        std::fs::write(&path, "[client]\ntick-rate = 30\n\n[mouse]\nsensitivity = 2.0\n").unwrap();
+        watcher.reload().unwrap();
+        assert_eq!(watcher.pending_restart(), vec!["client.tick-rate"]);
+        assert_eq!(watcher.current["client"]["tick-rate"].as_integer(), Some(60));
+        assert_eq!(watcher.current["mouse"]["sensitivity"].as_float(), Some(2.0));
+
+        // still pending on the next edit of something else
+        std::fs::write(&path, "[client]\ntick-rate = 30\n\n[mouse]\nsensitivity = 3.0\n").unwrap();
+        watcher.reload().unwrap();
+        assert_eq!(watcher.pending_restart(), vec!["client.tick-rate"]);
+
+        // reverting the edit is noticed
+        std::fs::write(&path, "[client]\ntick-rate = 60\n\n[mouse]\nsensitivity = 3.0\n").unwrap();
+        watcher.reload().unwrap();
+        std::fs::remove_file(&path).unwrap();
+        assert!(watcher.pending_restart().is_empty());
+
+        assert_eq!(update_rx.try_iter().count(), 2);
+        assert_eq!(event_rx.try_iter().count(), 0);
+    }
+}
@@ -0,0 +1,212 @@
+use std::path::{
+    Path,
+    PathBuf,
+};
+
+use failure::{
+    Error,
+    format_err,
+};
+use serde::Serialize;
+use toml::{
+    value::Table,
+    Value,
+};
+
+use eternalreckoning_core::util::config::Config as CoreConfig;
+
+use crate::simulation::event::ConfigEvent;
+use super::config::Config;
+use super::configloader::{
+    changed_keys,
+    get_key,
+    set_key,
+};
+
+/**
+ * Saves the settings changed in game to the user config file. Changes picked
+ * up from the config files are already on disk: saving them would copy edits
+ * of the shipped config into the user's, where they would override later
+ * shipped defaults.
+ */
+pub struct SettingsWriter {
+    baseline: Config,
+    path: PathBuf,
+}
+
+impl SettingsWriter {
+    pub fn new(config: &Config, path: PathBuf) -> Result<SettingsWriter, Error> {
+        Ok(SettingsWriter {
+            baseline: Value::try_from(config)?.try_into()?,
+            path,
+        })
+    }
+
+    /**
+     * Notes a change loaded from the config files, which is not saved
+     */
+    pub fn reloaded(&mut self, event: &ConfigEvent) {
+        self.baseline.apply(event);
+    }
+
+    /**
+     * Writes the settings changed in game, logging the outcome
+     */
+    pub fn save(&self, config: &Config) {
+        let written = Value::try_from(&self.baseline)
+            .map_err(Error::from)
+            .and_then(|baseline| write_modified(config, &baseline, &self.path));
+
+        match written {
+            Ok(ref keys) if keys.is_empty() => (),
+            Ok(keys) => {
+                log::info!("Saved settings to {}: {}", self.path.display(), keys.join(", "));
+            },
+            Err(e) => {
+                log::error!("Failed to save settings: {}", e);
+            },
+        };
+    }
+}
+
+/**
+ * Complements `Config::write_default`: rather than replacing the file with
+ * defaults, only the settings that differ from 'baseline' (the effective
+ * configuration when it was loaded) are written, and everything else already
+ * in the file, including keys unknown to this client version, is kept.
+ *
+ * Comments and formatting in the file are not preserved.
+ */
+pub trait WriteModified {
+    fn write_modified(&self, baseline: &Value, path: &Path)
+        -> Result<Vec<String>, Error>;
+}
+
+impl<T> WriteModified for CoreConfig<T>
+where
+    T: Serialize,
+{
+    fn write_modified(&self, baseline: &Value, path: &Path)
+        -> Result<Vec<String>, Error>
+    {
+        write_modified(&self.data, baseline, path)
+    }
+}
+
+/**
+ * Writes the settings in 'data' that differ from 'baseline', as
+ * `WriteModified` does.
+ *
+ * Returns the keys that were written, nothing is written if no setting was
+ * modified
+ */
+pub fn write_modified<T>(data: &T, baseline: &Value, path: &Path)
+    -> Result<Vec<String>, Error>
+where
+    T: Serialize,
+{
+    let current = Value::try_from(data)?;
+    let keys = changed_keys(baseline, &current);
+    if keys.is_empty() {
+        return Ok(keys);
+    }
+
+    let mut file = match std::fs::read_to_string(path) {
+        Ok(contents) => {
+            contents.parse::<Value>()
+                .map_err(|e| {
+                    format_err!("refusing to overwrite malformed config file {}: {}", path.display(), e)
+                })?
+        },
+        Err(ref e) if e.kind() == std::io::ErrorKind::NotFound => {
+            Value::Table(Table::new())
+        },
+        Err(e) => {
+            return Err(format_err!("unable to read config file {}: {}", path.display(), e));
+        },
+    };
+
+    for key in &keys {
+        if let Some(value) = get_key(&current, key) {
+            set_key(&mut file, key, value.clone())?;
+        }
+    }
+
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir)?;
+    }
+
+    let tmp_path = path.with_extension("toml.tmp");
+    std::fs::write(&tmp_path, toml::to_string_pretty(&file)?)?;
+    std::fs::rename(&tmp_path, path)?;
+
+    Ok(keys)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::util::config;
+
+    #[test]
+    fn test_write_modified_keys_only() {
+        let path = std::env::temp_dir().join(format!(
+            "er-client-test-{}.toml",
+            std::process::id()
+        ));
+        std::fs::write(&path, "[mouse]\nsensitivity = 3.0\n\n[future]\nsetting = true\n").unwrap();
+
+        let mut data = config::Config::default();
+        let baseline = Value::try_from(&data).unwrap();
+
+        data.client.tick_rate = 128;
+        data.simulation.physics.gravity = 9.81;
+
+        let mut keys = write_modified(&data, &baseline, &path).unwrap();
+        keys.sort();
+        assert_eq!(keys, vec![
+            "client.tick-rate".to_string(),
+            "simulation.physics.gravity".to_string(),
+        ]);
+
+        let written = std::fs::read_to_string(&path).unwrap().parse::<Value>().unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        assert_eq!(written["client"]["tick-rate"].as_integer(), Some(128));
+        assert_eq!(written["simulation"]["physics"]["gravity"].as_float(), Some(9.81));
+        assert_eq!(written["mouse"]["sensitivity"].as_float(), Some(3.0));
+        assert_eq!(written["future"]["setting"].as_bool(), Some(true));
+
+        assert!(written["client"].get("server-address").is_none());
+        assert!(written.get("display").is_none());
+    }
+
+    #[test]
+    fn test_save_in_game_changes_only() {
+        let path = std::env::temp_dir().join(format!(
+            "er-client-save-{}.toml",
+            std::process::id()
+        ));
+        let _ = std::fs::remove_file(&path);
+
+        let mut settings = config::Config::default();
+        let mut writer = SettingsWriter::new(&settings, path.clone()).unwrap();
+
+        // changed in game
+        settings.apply(&ConfigEvent::MouseSensitivity(2.5));
+
+        // edited in a config file
+        let event = ConfigEvent::MovementSpeed(7.0);
+        settings.apply(&event);
+        writer.reloaded(&event);
+
+        writer.save(&settings);
+
+        let written = std::fs::read_to_string(&path).unwrap().parse::<Value>().unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        assert_eq!(written["mouse"]["sensitivity"].as_float(), Some(2.5));
+        assert!(written.get("simulation").is_none());
+        assert!(written.get("display").is_none());
+    }
+}
@@ -8,4 +8,15 @@ pub fn lerp(
     interp_vec *= progress;
 
     start + interp_vec
-}
\ No newline at end of file
+}
+
+pub fn slerp(
+    start: &nalgebra::UnitQuaternion<f32>,
+    stop: &nalgebra::UnitQuaternion<f32>,
+    progress: f32
+) -> nalgebra::UnitQuaternion<f32>
+{
+    // quaternions 180 degrees apart have no single path between them
+    start.try_slerp(stop, progress, 1.0e-6)
+        .unwrap_or(*stop)
+}
@@ -1,2 +1,5 @@
 pub mod config;
+pub mod configloader;
+pub mod configwatcher;
+pub mod configwriter;
 pub mod interpolate;
\ No newline at end of file